@@ -27,6 +27,13 @@ for target_dir in target/*/release/; do
     fi
 done
 
+# The header is generated into the build script's OUT_DIR
+HEADER=$(ls -t target/release/build/html_minifier_ffi-*/out/html_minifier.h 2>/dev/null | head -n 1)
+if [ -n "$HEADER" ]; then
+    cp "$HEADER" ../src/
+    COPIED_FILES="$COPIED_FILES html_minifier.h"
+fi
+
 if [ -n "$COPIED_FILES" ]; then
     echo "Rust shared library built and copied to src/:$COPIED_FILES"
 else
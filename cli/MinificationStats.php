@@ -2,10 +2,15 @@
 
 class MinificationStats
 {
+    /**
+     * @param array<string, int|array<string, int>>|null $report Report from
+     *     HTMLMinifier::minifyWithReport(), if available.
+     */
     public function __construct(
         public readonly int $originalSize,
         public readonly int $minifiedSize,
-        public readonly float $executionTimeMs
+        public readonly float $executionTimeMs,
+        public readonly ?array $report = null
     ) {}
 
     public function getSpaceSaved(): int
@@ -35,9 +40,35 @@ class MinificationStats
         $this->displayRow("Compression", number_format($this->getCompressionRatio(), 1) . "%", $width);
         $this->displayRow("Time taken", number_format($this->executionTimeMs, 2) . "ms", $width);
 
+        if ($this->report !== null) {
+            $this->displayReport($width);
+        }
+
         echo "└" . str_repeat("─", $width - 2) . "┘\n\n";
     }
 
+    private function displayReport(int $width): void
+    {
+        echo "├" . str_repeat("─", $width - 2) . "┤\n";
+
+        $labels = [
+            'comments' => 'Comments removed',
+            'whitespace' => 'Whitespace removed',
+            'attributes' => 'Attributes saved',
+            'optional_tags' => 'Optional tags removed',
+            'css' => 'CSS saved',
+            'js' => 'JS saved',
+        ];
+        foreach ($labels as $key => $label) {
+            $this->displayRow($label, $this->formatBytes($this->report['bytes_removed'][$key]), $width);
+        }
+
+        $this->displayRow("Elements", number_format($this->report['elements']), $width);
+        $this->displayRow("Attributes", number_format($this->report['attributes']), $width);
+        $this->displayRow("Parse time", number_format($this->report['parse_nanos'] / 1e6, 2) . "ms", $width);
+        $this->displayRow("Emit time", number_format($this->report['emit_nanos'] / 1e6, 2) . "ms", $width);
+    }
+
     private function displayRow(string $label, string $value, int $width): void
     {
         $innerWidth = $width - 4; // Account for "│ " and " │"
@@ -42,13 +42,14 @@ class UrlMinifier
     private function processAndDisplay(string $html, string $url, bool $shouldSave): void
     {
         $startTime = microtime(true);
-        $minified = $this->minifier->minify($html);
+        ['html' => $minified, 'report' => $report] = $this->minifier->minifyWithReport($html);
         $endTime = microtime(true);
 
         $stats = new MinificationStats(
             strlen($html),
             strlen($minified),
-            ($endTime - $startTime) * 1000
+            ($endTime - $startTime) * 1000,
+            $report
         );
 
         if ($shouldSave) {
@@ -48,6 +48,30 @@ for target_dir in target/*/release/; do
     fi
 done
 
+# The header is generated into each build's OUT_DIR, under the target's own
+# directory. Every target must have generated the same one.
+newest_header() {
+    ls -t "$1"build/html_minifier_ffi-*/out/html_minifier.h 2>/dev/null | head -n 1
+}
+
+HEADER=$(newest_header target/release/)
+for target_dir in target/*/release/; do
+    target_header=$(newest_header "$target_dir")
+    if [ -z "$target_header" ]; then
+        continue
+    fi
+    if [ -z "$HEADER" ]; then
+        HEADER="$target_header"
+    elif ! cmp -s "$HEADER" "$target_header"; then
+        echo "html_minifier.h generated for $(echo "$target_dir" | cut -d'/' -f2) differs from $HEADER"
+        exit 1
+    fi
+done
+if [ -n "$HEADER" ]; then
+    cp "$HEADER" ../src/
+    COPIED_FILES="$COPIED_FILES html_minifier.h"
+fi
+
 if [ -n "$COPIED_FILES" ]; then
     echo "Libraries built and copied:$COPIED_FILES"
 else
@@ -71,13 +71,14 @@ function runDefaultExample(HTMLMinifier $minifier): void
 HTML;
 
     $startTime = microtime(true);
-    $minifiedHtml = $minifier->minify($html);
+    ['html' => $minifiedHtml, 'report' => $report] = $minifier->minifyWithReport($html);
     $endTime = microtime(true);
 
     $stats = new MinificationStats(
         strlen($html),
         strlen($minifiedHtml),
-        ($endTime - $startTime) * 1000
+        ($endTime - $startTime) * 1000,
+        $report
     );
 
     $stats->display();
@@ -0,0 +1,91 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use html_minifier_ffi::{minify_html_tokens, minify_stream, MinifyOptions};
+
+const ARTICLE: &str = include_str!("fixtures/article.html");
+
+/// Builds a large page by repeating the fixture's `<main>` section, the way
+/// long listing and report pages repeat the same markup.
+fn large_page(repeats: usize) -> String {
+    let start = ARTICLE.find("<main").expect("fixture has <main>");
+    let end = ARTICLE.find("</main>").expect("fixture has </main>") + "</main>".len();
+    let mut page = String::with_capacity(ARTICLE.len() * repeats);
+    page.push_str(&ARTICLE[..start]);
+    for _ in 0..repeats {
+        page.push_str(&ARTICLE[start..end]);
+    }
+    page.push_str(&ARTICLE[end..]);
+    page
+}
+
+/// Builds a page whose size is dominated by `repeats` copies of the
+/// fixture's paragraphs wrapped in `open` and `close`, so the time goes to
+/// scanning for the end of each block rather than to rewriting tags.
+fn scan_heavy_page(open: &str, close: &str, repeats: usize) -> String {
+    let start = ARTICLE
+        .find("<div class=\"article-body\"")
+        .expect("fixture has a body");
+    let end = ARTICLE[start..]
+        .find("<table")
+        .expect("fixture has a table")
+        + start;
+    let prose = ARTICLE[start..end].replace('<', "&lt;");
+    let mut page = String::from("<!DOCTYPE html><html><body>");
+    for _ in 0..repeats {
+        page.push_str(open);
+        page.push_str(&prose);
+        page.push_str(close);
+    }
+    page.push_str("</body></html>");
+    page
+}
+
+fn bench_minify_html(c: &mut Criterion) {
+    let mut group = c.benchmark_group("minify_html");
+    for (name, page) in [
+        ("article", ARTICLE.to_string()),
+        ("large_page", large_page(200)),
+    ] {
+        group.throughput(Throughput::Bytes(page.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &page, |b, page| {
+            b.iter(|| minify_html_tokens(page))
+        });
+    }
+    group.finish();
+}
+
+fn bench_scanning(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scanning");
+    for (name, page) in [
+        ("pre_text", scan_heavy_page("<pre>", "</pre>", 1000)),
+        ("comments", scan_heavy_page("<!--", "-->", 1000)),
+        (
+            "textarea",
+            scan_heavy_page("<textarea>", "</textarea>", 1000),
+        ),
+    ] {
+        group.throughput(Throughput::Bytes(page.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &page, |b, page| {
+            b.iter(|| minify_html_tokens(page))
+        });
+    }
+    group.finish();
+}
+
+fn bench_minify_stream(c: &mut Criterion) {
+    let page = large_page(200);
+    let options = MinifyOptions::default();
+    let mut group = c.benchmark_group("minify_stream");
+    group.throughput(Throughput::Bytes(page.len() as u64));
+    group.bench_function("large_page", |b| {
+        b.iter(|| minify_stream(page.as_bytes(), Vec::new(), &options).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_minify_html,
+    bench_scanning,
+    bench_minify_stream
+);
+criterion_main!(benches);
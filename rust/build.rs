@@ -0,0 +1,19 @@
+use std::env;
+use std::path::Path;
+
+fn main() {
+    let crate_dir = env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is set by cargo");
+    let out_dir = env::var("OUT_DIR").expect("OUT_DIR is set by cargo");
+
+    // The header also declares types from outside src/ffi.rs
+    println!("cargo:rerun-if-changed=src");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+
+    // A broken header must not block building the library itself
+    match cbindgen::generate(&crate_dir) {
+        Ok(bindings) => {
+            bindings.write_to_file(Path::new(&out_dir).join("html_minifier.h"));
+        }
+        Err(err) => println!("cargo:warning=failed to generate html_minifier.h: {err}"),
+    }
+}
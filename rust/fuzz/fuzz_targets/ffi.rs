@@ -0,0 +1,121 @@
+#![no_main]
+
+//! Drives every exported C function with raw bytes, which need not be valid
+//! UTF-8, and checks the outputs against the Rust API.
+
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+
+use html_minifier_ffi::*;
+use libfuzzer_sys::fuzz_target;
+
+/// Calls one of the `*_bytes` functions, returning the status and a copy of
+/// the freed output.
+fn call_bytes(
+    input: &[u8],
+    f: impl FnOnce(*const c_char, usize, *mut *mut c_char, *mut usize) -> MinifyStatus,
+) -> (MinifyStatus, Vec<u8>) {
+    let mut out_ptr = ptr::null_mut();
+    let mut out_len = 0;
+    let status = f(
+        input.as_ptr().cast(),
+        input.len(),
+        &mut out_ptr,
+        &mut out_len,
+    );
+    if status != MinifyStatus::Ok {
+        assert!(out_ptr.is_null());
+        return (status, Vec::new());
+    }
+    let output = unsafe { std::slice::from_raw_parts(out_ptr as *const u8, out_len) }.to_vec();
+    unsafe { free_buffer(out_ptr, out_len) };
+    (status, output)
+}
+
+fn expect_output(input: &[u8], (status, output): (MinifyStatus, Vec<u8>), f: fn(&str) -> String) {
+    match std::str::from_utf8(input) {
+        Ok(text) => {
+            assert_eq!(status, MinifyStatus::Ok);
+            assert_eq!(output, f(text).into_bytes());
+        }
+        Err(_) => assert_eq!(status, MinifyStatus::InvalidUtf8),
+    }
+}
+
+fuzz_target!(|input: (bool, &str, &str, Vec<&[u8]>)| {
+    let (value, name, text_value, documents) = input;
+
+    let config = minifier_config_new();
+    if let (Ok(name), Ok(text_value)) = (CString::new(name), CString::new(text_value)) {
+        unsafe {
+            minifier_config_set_bool(config, name.as_ptr(), value);
+            minifier_config_set_str(config, name.as_ptr(), text_value.as_ptr());
+        }
+    }
+
+    for &document in &documents {
+        expect_output(
+            document,
+            call_bytes(document, |p, l, o, ol| unsafe {
+                minify_html_bytes(p, l, o, ol)
+            }),
+            minify_html_tokens,
+        );
+        expect_output(
+            document,
+            call_bytes(document, |p, l, o, ol| unsafe {
+                minify_css_bytes(p, l, o, ol)
+            }),
+            minify_css,
+        );
+        expect_output(
+            document,
+            call_bytes(document, |p, l, o, ol| unsafe {
+                minify_javascript_bytes(p, l, o, ol)
+            }),
+            minify_javascript,
+        );
+        call_bytes(document, |p, l, o, ol| unsafe {
+            minify_html_with_config(config, p, l, o, ol)
+        });
+        let mut report = MinifyReport::default();
+        call_bytes(document, |p, l, o, ol| unsafe {
+            minify_html_with_report(config, p, l, o, ol, &mut report)
+        });
+
+        if let Ok(c_input) = CString::new(document) {
+            for f in [
+                minify_html_string,
+                minify_css_string,
+                minify_javascript_string,
+            ] {
+                let output = unsafe { f(c_input.as_ptr()) };
+                if !output.is_null() {
+                    assert!(unsafe { CStr::from_ptr(output) }.to_str().is_ok());
+                    unsafe { free_string(output) };
+                }
+            }
+        }
+    }
+
+    let inputs: Vec<*const c_char> = documents.iter().map(|d| d.as_ptr().cast()).collect();
+    let input_lens: Vec<usize> = documents.iter().map(|d| d.len()).collect();
+    let mut outputs = vec![ptr::null_mut(); documents.len()];
+    let mut output_lens = vec![0; documents.len()];
+    let mut statuses = vec![MinifyStatus::Ok; documents.len()];
+    unsafe {
+        minify_html_batch(
+            config,
+            inputs.as_ptr(),
+            input_lens.as_ptr(),
+            documents.len(),
+            outputs.as_mut_ptr(),
+            output_lens.as_mut_ptr(),
+            statuses.as_mut_ptr(),
+        );
+        for (&output, &len) in outputs.iter().zip(&output_lens) {
+            free_buffer(output, len);
+        }
+        minifier_config_free(config);
+    }
+});
@@ -0,0 +1,23 @@
+#![no_main]
+
+//! Each document of a batch must come out as if minified on its own.
+
+use html_minifier_ffi::{minify_batch, minify_html};
+use libfuzzer_sys::fuzz_target;
+
+mod options;
+
+fuzz_target!(|input: (u16, Vec<&str>)| {
+    let (flags, documents) = input;
+    let options = options::from_flags(flags);
+
+    let results = minify_batch(&documents, &options);
+    assert_eq!(results.len(), documents.len());
+    for (html, result) in documents.iter().zip(results) {
+        let output = minify_html(html, &options);
+        match result {
+            Ok(minified) => assert_eq!(minified, output.html),
+            Err(_) => assert!(output.verification.is_some_and(|v| !v.is_ok())),
+        }
+    }
+});
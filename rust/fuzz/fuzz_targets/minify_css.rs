@@ -0,0 +1,8 @@
+#![no_main]
+
+use html_minifier_ffi::minify_css;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|css: &str| {
+    minify_css(css);
+});
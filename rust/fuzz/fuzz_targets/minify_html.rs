@@ -0,0 +1,32 @@
+#![no_main]
+
+use html_minifier_ffi::{
+    beautify_html, class_name_frequency, lint, minify_html, minify_html_tokens,
+    minify_html_with_options, verify_minified,
+};
+use libfuzzer_sys::fuzz_target;
+
+mod options;
+
+fuzz_target!(|input: (u16, &str)| {
+    let (flags, html) = input;
+    let options = options::from_flags(flags);
+
+    let output = minify_html(html, &options);
+    assert_eq!(minify_html_with_options(html, &options), output.html);
+    if let Some(report) = output.report {
+        assert_eq!(report.input_bytes, html.len() as u64);
+        assert_eq!(report.output_bytes, output.html.len() as u64);
+    }
+
+    minify_html_tokens(html);
+    verify_minified(html, &output.html, &options);
+    class_name_frequency([html, output.html.as_str()]);
+
+    for diagnostic in lint(html) {
+        assert!(diagnostic.span.start <= diagnostic.span.end && diagnostic.span.end <= html.len());
+    }
+
+    let pretty = beautify_html(html, "  ");
+    assert_eq!(minify_html_tokens(&pretty), minify_html_tokens(html));
+});
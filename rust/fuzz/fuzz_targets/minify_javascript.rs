@@ -0,0 +1,8 @@
+#![no_main]
+
+use html_minifier_ffi::minify_javascript;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|js: &str| {
+    minify_javascript(js);
+});
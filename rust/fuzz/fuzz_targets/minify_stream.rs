@@ -0,0 +1,40 @@
+#![no_main]
+
+//! Streaming must produce exactly the output of minifying the whole input,
+//! wherever the chunk boundaries fall.
+
+use html_minifier_ffi::{minify_html, Minifier};
+use libfuzzer_sys::fuzz_target;
+
+mod options;
+
+fuzz_target!(|input: (u16, Vec<u8>, &[u8])| {
+    let (flags, splits, bytes) = input;
+    // Streaming sorts by the configured order only, never by frequency
+    let mut options = options::from_flags(flags);
+    options.sort_class_names = false;
+    options.sort_attributes = false;
+    options.verify = false;
+
+    let mut minifier = Minifier::new(Vec::new(), &options);
+    let mut rest = bytes;
+    let mut streamed = Ok(());
+    for split in splits {
+        let (chunk, tail) = rest.split_at((split as usize).min(rest.len()));
+        streamed = streamed.and_then(|()| minifier.push(chunk));
+        rest = tail;
+    }
+    let streamed = streamed
+        .and_then(|()| minifier.push(rest))
+        .and_then(|()| minifier.finish());
+
+    // Invalid UTF-8 is an error for the stream and has no whole-input output
+    let Ok(html) = std::str::from_utf8(bytes) else {
+        assert!(streamed.is_err(), "invalid UTF-8 was accepted");
+        return;
+    };
+    let streamed = streamed.expect("valid UTF-8 streams without error");
+    let whole = minify_html(html, &options);
+    assert_eq!(String::from_utf8(streamed.writer).unwrap(), whole.html);
+    assert_eq!(streamed.warnings, whole.warnings);
+});
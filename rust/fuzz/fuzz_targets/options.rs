@@ -0,0 +1,22 @@
+use html_minifier_ffi::MinifyOptions;
+
+/// Options chosen by the bits of a fuzzer-provided number.
+pub fn from_flags(flags: u16) -> MinifyOptions {
+    let bit = |n: u16| flags & (1 << n) != 0;
+    MinifyOptions {
+        collapse_custom_element_booleans: bit(0),
+        sort_class_names: bit(1),
+        class_name_order: if bit(2) {
+            vec!["btn".to_string(), "card".to_string()]
+        } else {
+            Vec::new()
+        },
+        sort_attributes: bit(3),
+        warn_duplicate_attributes: bit(4),
+        collect_report: bit(5),
+        verify: bit(6),
+        source_map: bit(7),
+        minify_svg: bit(8),
+        svg_precision: bit(9).then_some(1),
+    }
+}
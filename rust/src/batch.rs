@@ -0,0 +1,128 @@
+//! Minification of many documents at once, spread across all cores.
+
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::{minify_html, MinifyOptions, Verification};
+
+/// Why one document of a batch has no output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MinifyError {
+    /// The minifier panicked on the document, with the panic message.
+    Panicked(String),
+    /// [`MinifyOptions::verify`] is set and the output failed verification.
+    Unverified(Box<Verification>),
+}
+
+impl std::fmt::Display for MinifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MinifyError::Panicked(message) => write!(f, "minifier panicked: {message}"),
+            MinifyError::Unverified(verification) => {
+                write!(f, "verification failed: {verification}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MinifyError {}
+
+/// Minifies one document, turning a panic into an error so that it does not
+/// take the rest of the batch down with it.
+fn minify_one(html: &str, options: &MinifyOptions) -> Result<String, MinifyError> {
+    let output = panic::catch_unwind(AssertUnwindSafe(|| minify_html(html, options))).map_err(
+        |payload| {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            MinifyError::Panicked(message)
+        },
+    )?;
+
+    match output.verification {
+        Some(verification) if !verification.is_ok() => {
+            Err(MinifyError::Unverified(Box::new(verification)))
+        }
+        _ => Ok(output.html),
+    }
+}
+
+/// Minifies every document in `inputs` with the same options, in parallel,
+/// returning the results in input order.
+///
+/// With the `rayon` feature the work runs on rayon's global thread pool;
+/// otherwise one scoped thread per core takes documents from a shared queue,
+/// so a few large files don't leave the other threads idle.
+pub fn minify_batch<S>(inputs: &[S], options: &MinifyOptions) -> Vec<Result<String, MinifyError>>
+where
+    S: AsRef<str> + Sync,
+{
+    run_batch(inputs, options)
+}
+
+#[cfg(feature = "rayon")]
+fn run_batch<S>(inputs: &[S], options: &MinifyOptions) -> Vec<Result<String, MinifyError>>
+where
+    S: AsRef<str> + Sync,
+{
+    use rayon::prelude::*;
+
+    inputs
+        .par_iter()
+        .map(|html| minify_one(html.as_ref(), options))
+        .collect()
+}
+
+#[cfg(not(feature = "rayon"))]
+fn run_batch<S>(inputs: &[S], options: &MinifyOptions) -> Vec<Result<String, MinifyError>>
+where
+    S: AsRef<str> + Sync,
+{
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let workers = std::thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(inputs.len());
+    if workers <= 1 {
+        return inputs
+            .iter()
+            .map(|html| minify_one(html.as_ref(), options))
+            .collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<Option<Result<String, MinifyError>>> = Vec::new();
+    results.resize_with(inputs.len(), || None);
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(html) = inputs.get(index) else {
+                            return done;
+                        };
+                        done.push((index, minify_one(html.as_ref(), options)));
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            let done = handle
+                .join()
+                .expect("batch worker panicked outside the minifier");
+            for (index, result) in done {
+                results[index] = Some(result);
+            }
+        }
+    });
+
+    results
+        .into_iter()
+        .map(|result| result.expect("every document is taken by a worker"))
+        .collect()
+}
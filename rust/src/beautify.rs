@@ -0,0 +1,1024 @@
+//! Pretty-printing, the inverse of minification.
+//!
+//! Documents are re-indented from the crate's own tokens, changing only
+//! whitespace that the minifier drops anyway: block elements go on lines of
+//! their own, whitespace runs in text collapse to one space, and inline
+//! content, `<pre>`, `<textarea>` and MathML token elements such as `<mi>`
+//! keep the spacing they were written with. Minifying the result therefore
+//! gives what minifying the input gives. Inline CSS and JavaScript are laid
+//! out one declaration or statement per line, unless that layout would not
+//! minify to the same code, in which case they are left as written.
+
+use crate::foreign::ForeignContent;
+use crate::verify::closes_implicitly;
+use crate::{
+    is_singleton_element, minify_css, minify_javascript, Attribute, Span, Token, Tokenizer,
+    BLOCK_ELEMENTS,
+};
+
+// =============================================================================
+// Options
+// =============================================================================
+
+/// Options for [`beautify_html_with_options`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BeautifyOptions {
+    /// One level of indentation.
+    pub indent: String,
+    /// Lay out `<style>` content one declaration per line.
+    pub format_css: bool,
+    /// Lay out `<script>` content one statement per line, for JavaScript
+    /// and JSON script types.
+    pub format_javascript: bool,
+}
+
+impl Default for BeautifyOptions {
+    fn default() -> Self {
+        Self {
+            indent: "  ".to_string(),
+            format_css: true,
+            format_javascript: true,
+        }
+    }
+}
+
+// =============================================================================
+// Document Tree
+// =============================================================================
+
+/// Elements kept byte for byte, tags included, since their whitespace
+/// renders.
+const VERBATIM_ELEMENTS: &[&str] = &["pre", "textarea"];
+
+enum Node<'a> {
+    /// Text, written with its whitespace runs collapsed.
+    Text(&'a str),
+    /// Markup written as it was, on whatever line it falls: comments, CDATA
+    /// sections, stray end tags, `<textarea>` and MathML token elements.
+    Inline(&'a str),
+    /// Markup written as it was on a line of its own: the doctype and
+    /// `<pre>` elements.
+    Block(&'a str),
+    Element(Element<'a>),
+}
+
+impl Node<'_> {
+    fn is_block(&self) -> bool {
+        match self {
+            Node::Block(_) => true,
+            Node::Element(element) => BLOCK_ELEMENTS.contains(&element.name.as_str()),
+            Node::Text(_) | Node::Inline(_) => false,
+        }
+    }
+}
+
+/// What the content of an element is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Content {
+    Markup,
+    Css,
+    JavaScript,
+    /// Raw text of another kind, such as a `<script>` template.
+    Other,
+}
+
+struct Element<'a> {
+    name: String,
+    /// The start tag, rebuilt with one space before each attribute.
+    start_tag: String,
+    children: Vec<Node<'a>>,
+    /// The end tag as written, when the element has one.
+    end_tag: Option<&'a str>,
+    content: Content,
+}
+
+/// How the `type` attribute of a `<style>` or `<script>` affects what its
+/// content is.
+fn content_of_type(content: Content, value: &str) -> Content {
+    let value = value.trim().to_ascii_lowercase();
+    match content {
+        Content::Css if value.is_empty() || value == "text/css" => Content::Css,
+        Content::JavaScript
+            if value.is_empty()
+                || value == "module"
+                || value == "importmap"
+                || value.ends_with("javascript")
+                || value.ends_with("ecmascript")
+                || value.ends_with("json") =>
+        {
+            Content::JavaScript
+        }
+        Content::Markup => Content::Markup,
+        _ => Content::Other,
+    }
+}
+
+/// Builds the tree that the printer lays out. Nothing of the input is lost:
+/// every token ends up in a node, and closing tags that were left out stay
+/// left out.
+struct TreeBuilder<'a> {
+    html: &'a str,
+    /// Open elements, outermost first. The bottom entry is the document.
+    stack: Vec<Element<'a>>,
+    /// The element whose start tag is being parsed, and where it starts.
+    opening: Option<(Element<'a>, usize)>,
+    /// Whether tags are HTML, SVG or MathML. Every token is tracked, even
+    /// those passed over, so that it agrees with the minifier.
+    foreign: ForeignContent,
+    /// The name of the last start tag, for tracking foreign content.
+    tag_name: String,
+    /// A `<pre>` or `<textarea>` being passed over: its name, where it
+    /// starts, and how many elements of the same name are open inside it.
+    verbatim: Option<(String, usize, usize)>,
+    /// A MathML token element being passed over: where it starts, and the
+    /// depth of foreign content inside it, which falls when it ends.
+    math_text: Option<(usize, usize)>,
+    /// Markup that runs to the end of the input without its closing
+    /// delimiter. Anything written after it would become part of it, so it
+    /// is written last, exactly as it was.
+    tail: Option<&'a str>,
+}
+
+impl<'a> TreeBuilder<'a> {
+    fn parse(html: &'a str) -> (Vec<Node<'a>>, Option<&'a str>) {
+        let mut builder = TreeBuilder {
+            html,
+            stack: vec![Element {
+                name: String::new(),
+                start_tag: String::new(),
+                children: Vec::new(),
+                end_tag: None,
+                content: Content::Markup,
+            }],
+            opening: None,
+            foreign: ForeignContent::default(),
+            tag_name: String::new(),
+            verbatim: None,
+            math_text: None,
+            tail: None,
+        };
+        for (token, span) in Tokenizer::new(html) {
+            builder.token(token, span);
+        }
+
+        if let Some((_, start, _)) = builder.verbatim.take() {
+            builder.tail = Some(&html[start..]);
+        }
+        if let Some((start, _)) = builder.math_text.take() {
+            builder.tail = Some(&html[start..]);
+        }
+        if let Some((_, start)) = builder.opening.take() {
+            builder.tail = Some(&html[start..]);
+        }
+        while builder.stack.len() > 1 {
+            builder.pop();
+        }
+        let document = builder
+            .stack
+            .pop()
+            .map_or_else(Vec::new, |root| root.children);
+        (document, builder.tail)
+    }
+
+    fn current(&mut self) -> &mut Element<'a> {
+        self.stack.last_mut().expect("the document is never popped")
+    }
+
+    fn push(&mut self, node: Node<'a>) {
+        self.current().children.push(node);
+    }
+
+    fn pop(&mut self) {
+        if let Some(element) = self.stack.pop() {
+            self.push(Node::Element(element));
+        }
+    }
+
+    /// Pushes markup, or keeps it for the tail when the input ran out
+    /// before it was closed.
+    fn push_closed(&mut self, node: Node<'a>, source: &'a str, closed: bool) {
+        if closed {
+            self.push(node);
+        } else {
+            self.tail = Some(source);
+        }
+    }
+
+    fn token(&mut self, token: Token<'a>, span: Span) {
+        let source = &self.html[span.start..span.end];
+        let foreign = self.foreign.is_foreign();
+        match token {
+            Token::TagOpenStart(name) => {
+                self.foreign.start_tag(name);
+                self.tag_name = name.to_string();
+            }
+            Token::Attribute(attr) => {
+                self.foreign.attribute(attr.name);
+            }
+            Token::TagOpenEnd => self.foreign.end_start_tag(&self.tag_name, false),
+            Token::TagSelfClose => self.foreign.end_start_tag(&self.tag_name, true),
+            Token::TagClose(tag) => self.foreign.end_tag(tag),
+            _ => {}
+        }
+
+        if let Some((start, depth)) = self.math_text {
+            if self.foreign.depth() < depth {
+                let source = &self.html[start..span.end];
+                self.math_text = None;
+                self.push_closed(Node::Inline(source), source, source.ends_with('>'));
+            }
+            return;
+        }
+        if let Some((name, start, nested)) = self.verbatim.as_mut() {
+            match token {
+                Token::TagOpenStart(tag) if tag.eq_ignore_ascii_case(name) => *nested += 1,
+                // An end tag inside a MathML token element is left there
+                Token::TagClose(tag)
+                    if end_tag_name(tag).eq_ignore_ascii_case(name)
+                        && !self.foreign.in_math_text() =>
+                {
+                    if *nested > 0 {
+                        *nested -= 1;
+                    } else {
+                        let block = name == "pre";
+                        let source = &self.html[*start..span.end];
+                        self.verbatim = None;
+                        let node = if block {
+                            Node::Block(source)
+                        } else {
+                            Node::Inline(source)
+                        };
+                        self.push_closed(node, source, source.ends_with('>'));
+                    }
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        match token {
+            Token::TagOpenStart(name) => {
+                // SVG and MathML `<style>` and `<script>` hold markup
+                let name = name.to_ascii_lowercase();
+                let content = match name.as_str() {
+                    "style" if !foreign => Content::Css,
+                    "script" if !foreign => Content::JavaScript,
+                    _ => Content::Markup,
+                };
+                let element = Element {
+                    start_tag: source.to_string(),
+                    name,
+                    children: Vec::new(),
+                    end_tag: None,
+                    content,
+                };
+                self.opening = Some((element, span.start));
+            }
+            Token::Attribute(attr) => {
+                if let Some((element, _)) = self.opening.as_mut() {
+                    element.start_tag.push(' ');
+                    element.start_tag.push_str(source);
+                    if let Attribute {
+                        name,
+                        value: Some(value),
+                        ..
+                    } = attr
+                    {
+                        if name.eq_ignore_ascii_case("type") {
+                            element.content = content_of_type(element.content, value);
+                        }
+                    }
+                }
+            }
+            Token::TagOpenEnd => self.open(">", foreign),
+            Token::TagSelfClose => self.open("/>", foreign),
+            Token::TagClose(tag) => self.close(tag, source),
+            Token::TextNode(text) => self.push(Node::Text(text)),
+            Token::Comment(_) => {
+                let closed = if source.starts_with("<!--") {
+                    source.len() >= "<!---->".len() && source.ends_with("-->")
+                } else {
+                    source.ends_with('>')
+                };
+                self.push_closed(Node::Inline(source), source, closed);
+            }
+            Token::Cdata(_) => {
+                self.push_closed(Node::Inline(source), source, source.ends_with("]]>"));
+            }
+            Token::Doctype(_) => {
+                self.push_closed(Node::Block(source), source, source.ends_with('>'));
+            }
+        }
+    }
+
+    /// Ends the start tag being parsed. `foreign` is whether it began in
+    /// foreign content.
+    fn open(&mut self, close: &str, foreign: bool) {
+        let Some((mut element, start)) = self.opening.take() else {
+            return;
+        };
+        // Without the whitespace that came before it, the close could be
+        // read as part of the last attribute
+        let has_attributes = element.start_tag.contains(' ');
+        if element.start_tag.ends_with('/') || (close == "/>" && has_attributes) {
+            element.start_tag.push(' ');
+        }
+        element.start_tag.push_str(close);
+
+        // SVG and MathML have no void elements or implied end tags
+        let self_closing = close == "/>";
+        while !foreign
+            && self.stack.len() > 1
+            && closes_implicitly(&self.current().name, &element.name)
+        {
+            self.pop();
+        }
+
+        if self_closing || (!foreign && is_singleton_element(&element.name)) {
+            self.push(Node::Element(element));
+        } else if self.foreign.in_math_text() {
+            self.math_text = Some((start, self.foreign.depth()));
+        } else if VERBATIM_ELEMENTS.contains(&element.name.as_str())
+            || (foreign && matches!(element.name.as_str(), "script" | "style"))
+        {
+            // The minifier keeps the text of SVG and MathML code as written
+            self.verbatim = Some((element.name, start, 0));
+        } else {
+            self.stack.push(element);
+        }
+    }
+
+    /// Closes the innermost open element named by `tag`, along with any
+    /// elements left open inside it. A stray end tag is kept as markup.
+    fn close(&mut self, tag: &str, source: &'a str) {
+        if !source.ends_with('>') {
+            self.tail = Some(source);
+            return;
+        }
+        let name = end_tag_name(tag);
+        let Some(depth) = self
+            .stack
+            .iter()
+            .skip(1)
+            .rposition(|element| element.name.eq_ignore_ascii_case(name))
+        else {
+            self.push(Node::Inline(source));
+            return;
+        };
+        while self.stack.len() > depth + 2 {
+            self.pop();
+        }
+        self.current().end_tag = Some(source);
+        self.pop();
+    }
+}
+
+/// The name in the content of an end tag, which may be followed by
+/// whitespace.
+fn end_tag_name(tag: &str) -> &str {
+    tag.split_ascii_whitespace().next().unwrap_or_default()
+}
+
+// =============================================================================
+// HTML Layout
+// =============================================================================
+
+struct Printer<'o> {
+    options: &'o BeautifyOptions,
+    out: String,
+}
+
+impl Printer<'_> {
+    fn indent(&mut self, depth: usize) {
+        for _ in 0..depth {
+            self.out.push_str(&self.options.indent);
+        }
+    }
+
+    /// Writes `text` on a line of its own, unless it is empty.
+    fn line(&mut self, depth: usize, text: &str) {
+        if !text.is_empty() {
+            self.indent(depth);
+            self.out.push_str(text);
+            self.out.push('\n');
+        }
+    }
+
+    /// Writes each block child on its own lines and each run of inline
+    /// children on one line. Whitespace at the ends of a run borders a tag,
+    /// where the minifier drops it.
+    fn children(&mut self, nodes: &[Node], depth: usize) {
+        let mut run = String::new();
+        for node in nodes {
+            if node.is_block() {
+                self.line(depth, trim_ascii_whitespace(&run));
+                run.clear();
+                self.block(node, depth);
+            } else {
+                write_inline(node, &mut run);
+            }
+        }
+        self.line(depth, trim_ascii_whitespace(&run));
+    }
+
+    fn block(&mut self, node: &Node, depth: usize) {
+        match node {
+            Node::Element(element) if element.content != Content::Markup => {
+                self.raw_text_element(element, depth);
+            }
+            Node::Element(element) if element.children.iter().any(Node::is_block) => {
+                self.line(depth, &element.start_tag);
+                self.children(&element.children, depth + 1);
+                self.line(depth, element.end_tag.unwrap_or_default());
+            }
+            Node::Element(element) => {
+                let mut content = String::new();
+                for child in &element.children {
+                    write_inline(child, &mut content);
+                }
+                let line = [
+                    element.start_tag.as_str(),
+                    trim_ascii_whitespace(&content),
+                    element.end_tag.unwrap_or_default(),
+                ]
+                .concat();
+                self.line(depth, &line);
+            }
+            other => {
+                let mut line = String::new();
+                write_inline(other, &mut line);
+                self.line(depth, &line);
+            }
+        }
+    }
+
+    /// Writes a `<style>` or `<script>` element, laying out its content
+    /// when that minifies to the same code.
+    fn raw_text_element(&mut self, element: &Element, depth: usize) {
+        let content = raw_text(element);
+        let Some(end_tag) = element.end_tag else {
+            // The content runs to the end of the input, so anything written
+            // after it would become part of it
+            self.indent(depth);
+            self.out.push_str(&element.start_tag);
+            self.out.push_str(content);
+            return;
+        };
+        let laid_out = match element.content {
+            Content::Css if self.options.format_css => {
+                layout_css(content, &self.options.indent, depth + 1)
+                    .map(|layout| (layout, minify_css as fn(&str) -> String))
+            }
+            Content::JavaScript if self.options.format_javascript => {
+                layout_javascript(content, &self.options.indent, depth + 1)
+                    .map(|layout| (layout, minify_javascript as fn(&str) -> String))
+            }
+            _ => None,
+        };
+
+        self.indent(depth);
+        self.out.push_str(&element.start_tag);
+        match laid_out {
+            // Whitespace alone minifies to nothing
+            Some((layout, _)) if layout.is_empty() => {}
+            Some((layout, minify)) => {
+                let mut formatted = String::from("\n");
+                formatted.push_str(&layout);
+                for _ in 0..depth {
+                    formatted.push_str(&self.options.indent);
+                }
+                if minify(&formatted) == minify(content) {
+                    self.out.push_str(&formatted);
+                } else {
+                    self.out.push_str(content);
+                }
+            }
+            None => self.out.push_str(content),
+        }
+        self.out.push_str(end_tag);
+        self.out.push('\n');
+    }
+}
+
+fn raw_text<'a>(element: &Element<'a>) -> &'a str {
+    match element.children.first() {
+        Some(Node::Text(text)) => text,
+        _ => "",
+    }
+}
+
+/// Appends a node as it appears within a line: text with its whitespace
+/// collapsed, and everything else as written.
+fn write_inline(node: &Node, out: &mut String) {
+    match node {
+        Node::Text(text) => {
+            let mut in_space = false;
+            for ch in text.chars() {
+                if ch.is_ascii_whitespace() {
+                    if !in_space {
+                        out.push(' ');
+                    }
+                    in_space = true;
+                } else {
+                    out.push(ch);
+                    in_space = false;
+                }
+            }
+        }
+        Node::Inline(source) | Node::Block(source) => out.push_str(source),
+        Node::Element(element) => {
+            out.push_str(&element.start_tag);
+            if element.content == Content::Markup {
+                for child in &element.children {
+                    write_inline(child, out);
+                }
+            } else {
+                out.push_str(raw_text(element));
+            }
+            out.push_str(element.end_tag.unwrap_or_default());
+        }
+    }
+}
+
+/// Trims the whitespace that HTML collapses, which unlike `str::trim`
+/// leaves no-break spaces alone.
+fn trim_ascii_whitespace(text: &str) -> &str {
+    text.trim_matches(|c: char| c.is_ascii_whitespace())
+}
+
+// =============================================================================
+// CSS and JavaScript Layout
+// =============================================================================
+
+/// Output built line by line, each line indented by its nesting level on
+/// top of a base depth.
+struct Lines<'i> {
+    out: String,
+    indent: &'i str,
+    depth: usize,
+    at_line_start: bool,
+}
+
+impl<'i> Lines<'i> {
+    fn new(indent: &'i str, depth: usize) -> Self {
+        Self {
+            out: String::new(),
+            indent,
+            depth,
+            at_line_start: true,
+        }
+    }
+
+    fn write(&mut self, level: usize, text: &str) {
+        if self.at_line_start {
+            for _ in 0..self.depth + level {
+                self.out.push_str(self.indent);
+            }
+            self.at_line_start = false;
+        }
+        self.out.push_str(text);
+    }
+
+    fn space(&mut self) {
+        if !self.at_line_start && !self.out.ends_with(' ') {
+            self.out.push(' ');
+        }
+    }
+
+    fn newline(&mut self) {
+        if !self.at_line_start {
+            let trimmed = self.out.trim_end_matches(' ').len();
+            self.out.truncate(trimmed);
+            self.out.push('\n');
+            self.at_line_start = true;
+        }
+    }
+
+    fn blank_line(&mut self) {
+        self.newline();
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.newline();
+        self.out
+    }
+}
+
+/// Copies the string starting at `quote`, escapes included, as the
+/// minifiers read it: up to the next unescaped `quote`. Returns whether
+/// the string was closed before the input ran out.
+fn copy_string(
+    quote: char,
+    chars: &mut std::iter::Peekable<std::str::CharIndices>,
+    out: &mut String,
+) -> bool {
+    out.push(quote);
+    while let Some((_, ch)) = chars.next() {
+        out.push(ch);
+        if ch == quote {
+            return true;
+        }
+        if ch == '\\' {
+            if let Some((_, escaped)) = chars.next() {
+                out.push(escaped);
+            }
+        }
+    }
+    false
+}
+
+/// Copies a `/* */` comment whose `/` was just read. Returns whether the
+/// comment was closed before the input ran out.
+fn copy_block_comment(
+    chars: &mut std::iter::Peekable<std::str::CharIndices>,
+    out: &mut String,
+) -> bool {
+    out.push('/');
+    let mut prev = ' ';
+    for (_, ch) in chars.by_ref() {
+        out.push(ch);
+        if prev == '*' && ch == '/' {
+            return true;
+        }
+        prev = ch;
+    }
+    false
+}
+
+/// Whether the CSS statement at the start of `rest` is a declaration
+/// rather than the selector or prelude of a block.
+fn is_css_declaration(rest: &str) -> bool {
+    let mut chars = rest.char_indices().peekable();
+    let mut parens = 0usize;
+    let mut skipped = String::new();
+    while let Some((_, ch)) = chars.next() {
+        match ch {
+            '"' | '\'' => {
+                copy_string(ch, &mut chars, &mut skipped);
+            }
+            '/' if matches!(chars.peek(), Some((_, '*'))) => {
+                copy_block_comment(&mut chars, &mut skipped);
+            }
+            '(' => parens += 1,
+            ')' => parens = parens.saturating_sub(1),
+            '{' if parens == 0 => return false,
+            ';' | '}' if parens == 0 => return true,
+            _ => {}
+        }
+        skipped.clear();
+    }
+    true
+}
+
+/// Lays out CSS with one selector, declaration or brace per line, `depth`
+/// levels in. Strings and comments are copied as they are. Whitespace is
+/// only added after `{`, `}`, `:`, `;` and `,`, where the CSS minifier
+/// drops it; elsewhere, as before a brace, it is kept as one space or line
+/// break where the input has it and not added where it does not. Gives up
+/// on a string or comment left open, which would take in the line breaks
+/// added after it.
+fn layout_css(css: &str, indent: &str, depth: usize) -> Option<String> {
+    let mut lines = Lines::new(indent, depth);
+    let mut chars = css.char_indices().peekable();
+    let mut level = 0usize;
+    let mut parens = 0usize;
+    let mut space = false;
+    // What the statement being written is, decided at its first character
+    let mut statement_start = true;
+    let mut declaration = false;
+    let mut at_rule = false;
+    let mut colon_written = false;
+
+    while let Some((index, ch)) = chars.next() {
+        if statement_start && !ch.is_whitespace() {
+            declaration = level > 0 && is_css_declaration(&css[index..]);
+            at_rule = ch == '@';
+            colon_written = false;
+            statement_start = false;
+        }
+        if ch.is_whitespace() {
+            space = true;
+            continue;
+        }
+        let spaced = space;
+        if space {
+            lines.space();
+        }
+        space = false;
+
+        match ch {
+            '"' | '\'' => {
+                let mut string = String::new();
+                if !copy_string(ch, &mut chars, &mut string) {
+                    return None;
+                }
+                lines.write(level, &string);
+            }
+            '/' if matches!(chars.peek(), Some((_, '*'))) => {
+                let own_line = lines.at_line_start;
+                let mut comment = String::new();
+                if !copy_block_comment(&mut chars, &mut comment) {
+                    return None;
+                }
+                lines.write(level, &comment);
+                if own_line {
+                    lines.newline();
+                }
+            }
+            '(' => {
+                parens += 1;
+                lines.write(level, "(");
+            }
+            ')' => {
+                parens = parens.saturating_sub(1);
+                lines.write(level, ")");
+            }
+            '{' if parens == 0 => {
+                lines.write(level, "{");
+                lines.newline();
+                level += 1;
+                statement_start = true;
+            }
+            '}' if parens == 0 => {
+                if spaced {
+                    lines.newline();
+                }
+                level = level.saturating_sub(1);
+                lines.write(level, "}");
+                lines.newline();
+                statement_start = true;
+            }
+            ';' if parens == 0 => {
+                lines.write(level, ";");
+                lines.newline();
+                statement_start = true;
+            }
+            ',' => {
+                lines.write(level, ",");
+                if parens == 0 && !declaration && !at_rule {
+                    lines.newline();
+                } else {
+                    space = true;
+                }
+            }
+            ':' if parens == 0 && declaration && !colon_written => {
+                lines.write(level, ":");
+                colon_written = true;
+                space = true;
+            }
+            _ => {
+                let mut buf = [0; 4];
+                lines.write(level, ch.encode_utf8(&mut buf));
+            }
+        }
+    }
+    Some(lines.finish())
+}
+
+/// Words after which a `/` starts a regular expression rather than a
+/// division.
+const REGEX_KEYWORDS: &[&str] = &[
+    "await",
+    "case",
+    "delete",
+    "do",
+    "else",
+    "in",
+    "instanceof",
+    "new",
+    "of",
+    "return",
+    "throw",
+    "typeof",
+    "void",
+    "yield",
+];
+
+fn is_js_word_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_' || ch == '$'
+}
+
+/// The first character after the whitespace starting `rest`, and whether
+/// that whitespace holds a line break.
+fn next_significant(rest: &str) -> (Option<char>, bool) {
+    let trimmed = rest.trim_start();
+    let newline = rest[..rest.len() - trimmed.len()].contains('\n');
+    (trimmed.chars().next(), newline)
+}
+
+/// Lays out JavaScript with one statement per line and blocks indented,
+/// `depth` levels in. Strings, template literals, comments and regular
+/// expressions are copied as they are. Whitespace is only added next to
+/// braces and after `;`, where the JavaScript minifier drops it, and other
+/// whitespace is re-indented but never removed. Gives up, like
+/// [`layout_css`], on a string or comment left open.
+fn layout_javascript(js: &str, indent: &str, depth: usize) -> Option<String> {
+    let mut lines = Lines::new(indent, depth);
+    let mut chars = js.char_indices().peekable();
+    // Open brackets, each with whether it has started a new line and so
+    // indents the lines inside it
+    let mut brackets: Vec<(char, bool)> = Vec::new();
+    // Whitespace read since the last token: none, a space or line breaks
+    let mut newlines: Option<usize> = None;
+    // A line break goes before the next token whatever the whitespace
+    let mut break_line = false;
+    let mut last: Option<char> = None;
+    let mut last_word = String::new();
+
+    while let Some((index, ch)) = chars.next() {
+        if ch.is_whitespace() {
+            let count = newlines.get_or_insert(0);
+            *count += usize::from(ch == '\n');
+            continue;
+        }
+
+        // Break the line where whitespace held a break, keeping one blank
+        // line where there were more, and mark the innermost bracket as
+        // indenting the lines inside it
+        match (newlines.take(), std::mem::take(&mut break_line)) {
+            (Some(count), _) if count > 1 && !last.is_some_and(|c| "{([".contains(c)) => {
+                lines.blank_line();
+            }
+            (Some(0), false) => lines.space(),
+            (Some(_), _) | (None, true) => lines.newline(),
+            (None, false) => {}
+        }
+        if lines.at_line_start {
+            if let Some(bracket) = brackets.last_mut() {
+                bracket.1 = !matches!(ch, '}' | ')' | ']') || bracket.1;
+            }
+        }
+        let closing = matches!(ch, '}' | ')' | ']');
+        if closing {
+            if let Some((_, indented)) = brackets.pop() {
+                if ch == '}' && indented {
+                    lines.newline();
+                }
+            }
+        }
+        let level = brackets.iter().filter(|(_, indented)| *indented).count();
+
+        match ch {
+            '"' | '\'' | '`' => {
+                let mut string = String::new();
+                if !copy_string(ch, &mut chars, &mut string) {
+                    return None;
+                }
+                lines.write(level, &string);
+            }
+            '/' if matches!(chars.peek(), Some((_, '/'))) => {
+                let end = js[index..].find('\n').map_or(js.len(), |end| index + end);
+                lines.write(level, &js[index..end]);
+                while chars.peek().is_some_and(|&(i, _)| i < end) {
+                    chars.next();
+                }
+                // The line break after it is read as whitespace
+                last = None;
+                continue;
+            }
+            '/' if matches!(chars.peek(), Some((_, '*'))) => {
+                let mut comment = String::new();
+                if !copy_block_comment(&mut chars, &mut comment) {
+                    return None;
+                }
+                lines.write(level, &comment);
+                continue;
+            }
+            '/' if regex_allowed(last, &last_word) => {
+                let mut regex = String::from("/");
+                let mut in_class = false;
+                while let Some(&(_, next)) = chars.peek() {
+                    if next == '\n' {
+                        break;
+                    }
+                    chars.next();
+                    regex.push(next);
+                    match next {
+                        '\\' => {
+                            if let Some((_, escaped)) = chars.next() {
+                                regex.push(escaped);
+                            }
+                        }
+                        '[' => in_class = true,
+                        ']' => in_class = false,
+                        '/' if !in_class => break,
+                        _ => {}
+                    }
+                }
+                lines.write(level, &regex);
+            }
+            '{' => {
+                if !last.is_some_and(|c| "([".contains(c)) {
+                    lines.space();
+                }
+                lines.write(level, "{");
+                brackets.push(('{', false));
+                match next_significant(&js[index + 1..]) {
+                    (Some('}'), _) => {
+                        // An empty block stays on one line
+                        while chars.peek().is_some_and(|(_, c)| c.is_whitespace()) {
+                            chars.next();
+                        }
+                    }
+                    _ => break_line = true,
+                }
+            }
+            '}' => {
+                lines.write(level, "}");
+                let (next, _) = next_significant(&js[index + 1..]);
+                let rest = js[index + 1..].trim_start();
+                let continues = ["else", "catch", "finally", "while"].iter().any(|word| {
+                    rest.starts_with(word) && !rest[word.len()..].starts_with(is_js_word_char)
+                });
+                if continues {
+                    newlines.get_or_insert(0);
+                } else if next.is_some_and(is_js_word_char) {
+                    break_line = true;
+                }
+            }
+            ';' => {
+                lines.write(level, ";");
+                let in_block = matches!(brackets.last(), None | Some(('{', _)));
+                let rest_of_line = js[index + 1..].trim_start_matches([' ', '\t']);
+                if in_block && !rest_of_line.starts_with("//") {
+                    break_line = true;
+                }
+            }
+            '(' | '[' => {
+                brackets.push((ch, false));
+                lines.write(level, if ch == '(' { "(" } else { "[" });
+            }
+            _ => {
+                let mut buf = [0; 4];
+                lines.write(level, ch.encode_utf8(&mut buf));
+            }
+        }
+
+        if is_js_word_char(ch) {
+            if !last.is_some_and(is_js_word_char) {
+                last_word.clear();
+            }
+            last_word.push(ch);
+        }
+        last = Some(ch);
+    }
+    Some(lines.finish())
+}
+
+/// Whether a `/` after `last`, the previous significant character, starts
+/// a regular expression. After a word, only keywords such as `return`
+/// allow one.
+fn regex_allowed(last: Option<char>, last_word: &str) -> bool {
+    match last {
+        None => true,
+        Some(ch) if is_js_word_char(ch) => REGEX_KEYWORDS.contains(&last_word),
+        Some(ch) => "(,=:[!&|?{};+-*%<>~^".contains(ch),
+    }
+}
+
+// =============================================================================
+// Public API
+// =============================================================================
+
+/// Re-indents `html` for reading, with `indent` written once per level of
+/// nesting, laying out inline CSS and JavaScript too. Minifying the result
+/// gives the same output as minifying `html`.
+///
+/// ```
+/// use html_minifier_ffi::{beautify_html, minify_html_tokens};
+///
+/// let html = "<ul><li>One<li><b>Two</b> items</ul>";
+/// let pretty = beautify_html(html, "  ");
+/// assert_eq!(pretty, "<ul>\n  <li>One\n  <li><b>Two</b> items\n</ul>\n");
+/// assert_eq!(minify_html_tokens(&pretty), minify_html_tokens(html));
+/// ```
+pub fn beautify_html(html: &str, indent: &str) -> String {
+    let options = BeautifyOptions {
+        indent: indent.to_string(),
+        ..BeautifyOptions::default()
+    };
+    beautify_html_with_options(html, &options)
+}
+
+/// Re-indents `html` as [`beautify_html`] does, with the given options.
+pub fn beautify_html_with_options(html: &str, options: &BeautifyOptions) -> String {
+    let (document, tail) = TreeBuilder::parse(html);
+    let mut printer = Printer {
+        options,
+        out: String::with_capacity(html.len() + html.len() / 4),
+    };
+    printer.children(&document, 0);
+    if let Some(tail) = tail {
+        // The tail may be an inline tag, so it starts a line of its own only
+        // if whitespace came before it
+        let before = &html[..html.len() - tail.len()];
+        if !before.ends_with(|c: char| c.is_ascii_whitespace()) && printer.out.ends_with('\n') {
+            printer.out.pop();
+        }
+        printer.out.push_str(tail);
+    }
+    printer.out
+}
@@ -0,0 +1,785 @@
+//! `html-minifier`: minifies HTML files, directory trees or standard input.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use html_minifier_ffi::{
+    beautify_html, lint, minify_html, ConfigError, MinifyConfig, MinifyOptions, MinifyReport,
+    OptionError, Severity, SourceMap,
+};
+use serde_json::json;
+
+const USAGE: &str = "\
+Usage: html-minifier [OPTIONS] [INPUT]...
+
+Minifies HTML files, directory trees or standard input. With no INPUT, or
+when INPUT is -, reads standard input. Directories are searched recursively
+for .html and .htm files.
+
+Output:
+  -o, --output PATH    Write to PATH, which is a directory when minifying a
+                       directory or several inputs, or when it is an existing
+                       directory (default: standard output)
+  -i, --in-place       Overwrite each input file with its minified form
+  -c, --config FILE    Read minifier options from this TOML or JSON file
+                       instead of the nearest htmlmin.toml or .htmlminrc
+                       above each input; flags take precedence
+      --no-config      Do not look for configuration files
+  -q, --quiet          Do not print per-file savings
+  -h, --help           Print this help
+  -V, --version        Print the version
+
+Checking:
+      --check          Report problems in the markup, such as stray end
+                       tags or unclosed comments, instead of minifying
+      --format FORMAT  Print diagnostics as `human` lines of
+                       FILE:LINE:COLUMN: SEVERITY[CODE]: MESSAGE or as a
+                       `json` array (default: human)
+
+Beautifying:
+      --beautify       Re-indent the markup for reading instead of
+                       minifying it; minifying the result gives the same
+                       output as minifying the input
+      --indent WIDTH   Indent by WIDTH spaces, or by tabs with `tab`
+                       (default: 2)
+
+Minifier options (turn a boolean off again with --no-NAME):
+      --collapse-custom-element-booleans
+      --sort-class-names
+      --class-name-order \"NAME...\"
+      --sort-attributes
+      --warn-duplicate-attributes
+      --collect-report
+      --verify         Check each output against its input and leave the
+                       file unwritten if they differ
+      --source-map     Write a Source Map v3 beside each output file as
+                       OUTPUT.map, embedding the input when it is standard
+                       input or overwritten by --in-place
+      --minify-svg     Shorten path data and numbers in inline SVG and drop
+                       its metadata and editor markup
+      --svg-precision DIGITS
+                       Round SVG numbers to DIGITS decimal places
+
+Exit status is 0 on success, 1 if any input could not be read, decoded,
+written or verified, 2 on usage or configuration errors, and 3 if the
+markup of inputs that were all read has errors, such as an unclosed comment
+or attribute value. Such inputs are still minified; --check lists every
+problem without writing anything.";
+
+/// File extensions picked up when walking a directory.
+const HTML_EXTENSIONS: &[&str] = &["html", "htm"];
+
+/// Exit status when an input could not be read, decoded, written or verified.
+const EXIT_FAILURE: u8 = 1;
+/// Exit status for usage and configuration errors.
+const EXIT_USAGE: u8 = 2;
+/// Exit status when the markup of an input has errors.
+const EXIT_MARKUP_ERRORS: u8 = 3;
+
+/// A problem with the command line or configuration, reported with exit
+/// status 2.
+struct UsageError(String);
+
+impl From<OptionError> for UsageError {
+    fn from(err: OptionError) -> Self {
+        UsageError(err.to_string())
+    }
+}
+
+impl From<ConfigError> for UsageError {
+    fn from(err: ConfigError) -> Self {
+        UsageError(err.to_string())
+    }
+}
+
+/// How `--check` prints diagnostics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Human,
+    Json,
+}
+
+/// A minifier option given as a flag, applied after the configuration file.
+enum OptionFlag {
+    Bool(String, bool),
+    Str(String, String),
+}
+
+struct Args {
+    config: Option<PathBuf>,
+    no_config: bool,
+    flags: Vec<OptionFlag>,
+    inputs: Vec<PathBuf>,
+    output: Option<PathBuf>,
+    in_place: bool,
+    quiet: bool,
+    check: bool,
+    format: Format,
+    beautify: bool,
+    indent: String,
+}
+
+enum Command {
+    Run(Args),
+    Help,
+    Version,
+}
+
+fn parse_args(mut argv: impl Iterator<Item = String>) -> Result<Command, UsageError> {
+    let mut args = Args {
+        config: None,
+        no_config: false,
+        flags: Vec::new(),
+        inputs: Vec::new(),
+        output: None,
+        in_place: false,
+        quiet: false,
+        check: false,
+        format: Format::Human,
+        beautify: false,
+        indent: "  ".to_string(),
+    };
+    // Probes whether a flag names a boolean option without touching the
+    // options that will actually be used
+    let mut probe = MinifyOptions::default();
+
+    while let Some(arg) = argv.next() {
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value)),
+            _ => (arg.clone(), None),
+        };
+        let mut value = |name: &str| match inline_value {
+            Some(value) => Ok(value.to_string()),
+            None => argv
+                .next()
+                .ok_or_else(|| UsageError(format!("{name} requires a value"))),
+        };
+
+        match flag.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            "-o" | "--output" => args.output = Some(PathBuf::from(value(&flag)?)),
+            "-c" | "--config" => args.config = Some(PathBuf::from(value(&flag)?)),
+            "--no-config" => args.no_config = true,
+            "-i" | "--in-place" => args.in_place = true,
+            "-q" | "--quiet" => args.quiet = true,
+            "--check" => args.check = true,
+            "--format" => {
+                args.format = match value(&flag)?.as_str() {
+                    "human" => Format::Human,
+                    "json" => Format::Json,
+                    other => {
+                        return Err(UsageError(format!(
+                            "unknown format `{other}`, expected `human` or `json`"
+                        )))
+                    }
+                }
+            }
+            "-" => args.inputs.push(PathBuf::from("-")),
+            "--beautify" => args.beautify = true,
+            "--indent" => {
+                args.indent = match value(&flag)?.as_str() {
+                    "tab" => "\t".to_string(),
+                    width => match width.parse::<usize>() {
+                        Ok(width) => " ".repeat(width),
+                        Err(_) => {
+                            return Err(UsageError(format!(
+                                "invalid indent `{width}`, expected a number of spaces or `tab`"
+                            )))
+                        }
+                    },
+                };
+            }
+            "--" => args.inputs.extend(argv.by_ref().map(PathBuf::from)),
+            _ if flag.starts_with("--no-") => {
+                let name = flag["--no-".len()..].replace('-', "_");
+                probe.set_bool(&name, false)?;
+                args.flags.push(OptionFlag::Bool(name, false));
+            }
+            _ if flag.starts_with("--") => {
+                let name = flag[2..].replace('-', "_");
+                match (inline_value, probe.set_bool(&name, true)) {
+                    (None, Ok(())) => args.flags.push(OptionFlag::Bool(name, true)),
+                    (None, Err(OptionError::Unknown(_))) => {
+                        return Err(UsageError(format!("unknown flag `{flag}`")))
+                    }
+                    _ => {
+                        let value = value(&flag)?;
+                        probe.set_str(&name, &value)?;
+                        args.flags.push(OptionFlag::Str(name, value));
+                    }
+                }
+            }
+            _ if flag.starts_with('-') => {
+                return Err(UsageError(format!("unknown flag `{flag}`")));
+            }
+            _ => args.inputs.push(PathBuf::from(arg)),
+        }
+    }
+
+    Ok(Command::Run(args))
+}
+
+/// Finds the configuration of each document: the `--config` file for all of
+/// them, or else the nearest `htmlmin.toml` or `.htmlminrc` above it unless
+/// `--no-config` is given.
+struct Configs {
+    explicit: Option<MinifyConfig>,
+    discover: bool,
+    /// Discovered configuration of each directory searched so far.
+    discovered: HashMap<PathBuf, Option<MinifyConfig>>,
+}
+
+impl Configs {
+    fn new(args: &Args) -> Result<Self, UsageError> {
+        let explicit = match &args.config {
+            Some(path) => Some(MinifyConfig::load(path)?),
+            None => None,
+        };
+        Ok(Configs {
+            explicit,
+            discover: !args.no_config,
+            discovered: HashMap::new(),
+        })
+    }
+
+    /// Builds the options of one document from its configuration, then the
+    /// flags on top.
+    fn options_for(&mut self, input: &Location, args: &Args) -> Result<MinifyOptions, UsageError> {
+        let document = match input {
+            Location::Stdio => None,
+            Location::File(path) => Some(path.as_path()),
+        };
+        let dir = match document {
+            Some(path) => path.parent().unwrap_or(Path::new("")).to_path_buf(),
+            None => PathBuf::new(),
+        };
+
+        let config = match &self.explicit {
+            Some(config) => Some(config),
+            None if self.discover => {
+                if !self.discovered.contains_key(&dir) {
+                    let start = if dir.as_os_str().is_empty() {
+                        Path::new(".")
+                    } else {
+                        &dir
+                    };
+                    let config = MinifyConfig::discover(start)?;
+                    self.discovered.insert(dir.clone(), config);
+                }
+                self.discovered[&dir].as_ref()
+            }
+            None => None,
+        };
+
+        let mut options = match (config, document) {
+            (Some(config), Some(path)) => config.options_for(path),
+            (Some(config), None) => config.options(),
+            (None, _) => MinifyOptions::default(),
+        };
+        for flag in &args.flags {
+            match flag {
+                OptionFlag::Bool(name, value) => options.set_bool(name, *value)?,
+                OptionFlag::Str(name, value) => options.set_str(name, value)?,
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// Where a job reads its HTML from or writes it to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Location {
+    Stdio,
+    File(PathBuf),
+}
+
+impl Location {
+    fn display(&self) -> String {
+        match self {
+            Location::Stdio => "<stdin>".to_string(),
+            Location::File(path) => path.display().to_string(),
+        }
+    }
+}
+
+struct Job {
+    input: Location,
+    output: Location,
+}
+
+/// Expands the inputs into one job per document.
+fn plan_jobs(args: &Args) -> Result<Vec<Job>, UsageError> {
+    if args.check && (args.output.is_some() || args.in_place) {
+        return Err(UsageError(
+            "--check writes no output, so it takes neither --output nor --in-place".to_string(),
+        ));
+    }
+    if args.check && args.beautify {
+        return Err(UsageError(
+            "--check and --beautify are exclusive".to_string(),
+        ));
+    }
+    let stdin_only = args.inputs.is_empty() || args.inputs == [PathBuf::from("-")];
+    if stdin_only {
+        if args.in_place {
+            return Err(UsageError("--in-place needs input files".to_string()));
+        }
+        if let Some(dir) = args.output.as_ref().filter(|output| output.is_dir()) {
+            return Err(UsageError(format!(
+                "{} is a directory; standard input needs an output file",
+                dir.display()
+            )));
+        }
+        let output = args.output.clone().map_or(Location::Stdio, Location::File);
+        return Ok(vec![Job {
+            input: Location::Stdio,
+            output,
+        }]);
+    }
+    if args.inputs.iter().any(|input| input.as_os_str() == "-") {
+        return Err(UsageError(
+            "- cannot be combined with other inputs".to_string(),
+        ));
+    }
+    if args.in_place && args.output.is_some() {
+        return Err(UsageError(
+            "--in-place and --output are exclusive".to_string(),
+        ));
+    }
+
+    let output_is_dir = args.inputs.len() > 1
+        || args.inputs.iter().any(|input| input.is_dir())
+        || args.output.as_ref().is_some_and(|output| output.is_dir());
+    if output_is_dir && args.output.is_none() && !args.in_place && !args.check {
+        return Err(UsageError(
+            "several inputs or a directory need --output DIR or --in-place".to_string(),
+        ));
+    }
+
+    // An output directory inside an input tree holds earlier output, which
+    // must not be minified again
+    let skip_dir = args
+        .output
+        .as_ref()
+        .and_then(|output| fs::canonicalize(output).ok());
+
+    let mut jobs = Vec::new();
+    for input in &args.inputs {
+        if input.is_dir() {
+            let mut files = Vec::new();
+            collect_html_files(input, skip_dir.as_deref(), &mut files)
+                .map_err(|err| UsageError(format!("{}: {err}", input.display())))?;
+            for file in files {
+                let output = match &args.output {
+                    Some(dir) => dir.join(file.strip_prefix(input).unwrap_or(&file)),
+                    None => file.clone(),
+                };
+                jobs.push(Job {
+                    input: Location::File(file),
+                    output: Location::File(output),
+                });
+            }
+            continue;
+        }
+
+        let output = match (&args.output, args.in_place) {
+            (_, true) => Location::File(input.clone()),
+            (Some(dir), false) if output_is_dir => {
+                Location::File(dir.join(input.file_name().unwrap_or(input.as_os_str())))
+            }
+            (Some(path), false) => Location::File(path.clone()),
+            (None, false) => Location::Stdio,
+        };
+        jobs.push(Job {
+            input: Location::File(input.clone()),
+            output,
+        });
+    }
+    Ok(jobs)
+}
+
+/// Collects the HTML files under `dir`, sorted so runs are reproducible,
+/// leaving out the subdirectory `skip_dir`, given in canonical form.
+/// Symbolic links are not followed.
+fn collect_html_files(
+    dir: &Path,
+    skip_dir: Option<&Path>,
+    files: &mut Vec<PathBuf>,
+) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            if skip_dir.is_some_and(|skip| fs::canonicalize(&path).is_ok_and(|dir| dir == skip)) {
+                continue;
+            }
+            collect_html_files(&path, skip_dir, files)?;
+        } else if file_type.is_file() && has_html_extension(&path) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn has_html_extension(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            HTML_EXTENSIONS
+                .iter()
+                .any(|html| ext.eq_ignore_ascii_case(html))
+        })
+}
+
+/// Sizes of one minified document.
+struct Savings {
+    input: u64,
+    output: u64,
+}
+
+impl Savings {
+    fn describe(&self) -> String {
+        let percent = if self.input == 0 {
+            0.0
+        } else {
+            100.0 * (self.input - self.output.min(self.input)) as f64 / self.input as f64
+        };
+        format!(
+            "{} -> {} bytes ({percent:.1}% smaller)",
+            self.input, self.output
+        )
+    }
+}
+
+fn read_input(input: &Location) -> io::Result<Vec<u8>> {
+    match input {
+        Location::Stdio => {
+            let mut bytes = Vec::new();
+            io::stdin().lock().read_to_end(&mut bytes)?;
+            Ok(bytes)
+        }
+        Location::File(path) => fs::read(path),
+    }
+}
+
+/// Reads a document, which must be UTF-8.
+fn read_html(input: &Location) -> Result<String, String> {
+    let name = input.display();
+    let bytes = read_input(input).map_err(|err| format!("{name}: {err}"))?;
+    String::from_utf8(bytes)
+        .map_err(|err| format!("{name}: input is not valid UTF-8: {}", err.utf8_error()))
+}
+
+fn write_output(output: &Location, html: &str) -> io::Result<()> {
+    match output {
+        Location::Stdio => {
+            let mut stdout = io::stdout().lock();
+            stdout.write_all(html.as_bytes())?;
+            stdout.flush()
+        }
+        Location::File(path) => {
+            if let Some(parent) = path
+                .parent()
+                .filter(|parent| !parent.as_os_str().is_empty())
+            {
+                fs::create_dir_all(parent)?;
+            }
+            write_atomically(path, html)
+        }
+    }
+}
+
+/// Writes `path` by renaming a finished temporary file over it, so an
+/// interrupted run never leaves it truncated. The file keeps its
+/// permissions when it already exists.
+fn write_atomically(path: &Path, contents: &str) -> io::Result<()> {
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(path.file_name().unwrap_or_default());
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    let temp = path.with_file_name(temp_name);
+
+    let result = fs::write(&temp, contents).and_then(|()| {
+        if let Ok(metadata) = fs::metadata(path) {
+            fs::set_permissions(&temp, metadata.permissions())?;
+        }
+        fs::rename(&temp, path)
+    });
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+/// Minifies one document, printing its warnings, markup errors and savings.
+/// Returns the savings along with the number of markup errors.
+fn run_job(job: &Job, options: &MinifyOptions, quiet: bool) -> Result<(Savings, usize), String> {
+    let name = job.input.display();
+    let map_path = match &job.output {
+        Location::File(path) if options.source_map => Some(map_path(path)),
+        Location::Stdio if options.source_map => {
+            return Err(format!("{name}: --source-map needs an output file"));
+        }
+        _ => None,
+    };
+    let html = read_html(&job.input)?;
+
+    // Markup errors fail the run, but the document is minified regardless
+    let markup_errors = lint(&html)
+        .into_iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .inspect(|diagnostic| eprintln!("{name}:{diagnostic}"))
+        .count();
+
+    let output = minify_html(&html, options);
+    for warning in &output.warnings {
+        eprintln!("{name}: warning: {warning}");
+    }
+    if let Some(verification) = output.verification.as_ref().filter(|v| !v.is_ok()) {
+        for difference in &verification.differences {
+            eprintln!("{name}: {difference}");
+        }
+        if let Some(second_pass) = &verification.second_pass {
+            eprintln!("{name}: {second_pass}");
+        }
+        return Err(format!("{name}: verification failed, output not written"));
+    }
+
+    write_output(&job.output, &output.html)
+        .map_err(|err| format!("{}: {err}", job.output.display()))?;
+    if let (Some(map_path), Some(source_map)) = (map_path, &output.source_map) {
+        let json = source_map_json(source_map, job, &map_path, &html);
+        write_output(&Location::File(map_path.clone()), &json)
+            .map_err(|err| format!("{}: {err}", map_path.display()))?;
+    }
+
+    let savings = Savings {
+        input: html.len() as u64,
+        output: output.html.len() as u64,
+    };
+    if !quiet {
+        eprintln!("{name}: {}", savings.describe());
+        if let Some(report) = &output.report {
+            eprintln!("  {}", describe_report(report));
+        }
+    }
+    Ok((savings, markup_errors))
+}
+
+/// `page.html` is mapped by `page.html.map`.
+fn map_path(output: &Path) -> PathBuf {
+    let mut path = output.as_os_str().to_owned();
+    path.push(".map");
+    PathBuf::from(path)
+}
+
+/// Serializes the map of one job, naming its files relative to the map.
+/// The input is embedded when it will not be readable at its path.
+fn source_map_json(source_map: &SourceMap, job: &Job, map_path: &Path, input: &str) -> String {
+    let map_dir = map_path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let file = map_path
+        .file_stem()
+        .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+    let source = match &job.input {
+        Location::Stdio => "<stdin>".to_string(),
+        Location::File(path) => relative_url(path, map_dir),
+    };
+    let embed = job.input == Location::Stdio || job.input == job.output;
+    source_map.to_json(&file, &source, embed.then_some(input))
+}
+
+/// `path` relative to `dir`, with `/` separators as source maps expect.
+fn relative_url(path: &Path, dir: &Path) -> String {
+    let absolute = |path: &Path| std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let (path, dir) = (absolute(path), absolute(dir));
+    let common = path
+        .components()
+        .zip(dir.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let parents = dir.components().skip(common).map(|_| "..".into());
+    let rest = path
+        .components()
+        .skip(common)
+        .map(|component| component.as_os_str().to_string_lossy());
+    parents.chain(rest).collect::<Vec<_>>().join("/")
+}
+
+fn describe_report(report: &MinifyReport) -> String {
+    let removed = &report.bytes_removed;
+    format!(
+        "removed: comments {}, whitespace {}, attributes {}, optional tags {}, css {}, js {}; \
+         {} elements, {} attributes, parse {:?}, emit {:?}",
+        removed.comments,
+        removed.whitespace,
+        removed.attributes,
+        removed.optional_tags,
+        removed.css,
+        removed.js,
+        report.elements,
+        report.attributes,
+        report.parse_time(),
+        report.emit_time(),
+    )
+}
+
+/// Lints every input instead of minifying it, returning the exit status.
+/// Warnings alone do not fail the check.
+fn run_check(args: &Args) -> Result<u8, UsageError> {
+    let jobs = plan_jobs(args)?;
+
+    let mut unreadable = 0;
+    let (mut errors, mut warnings) = (0, 0);
+    let mut records = Vec::new();
+    for job in &jobs {
+        let html = match read_html(&job.input) {
+            Ok(html) => html,
+            Err(message) => {
+                eprintln!("html-minifier: {message}");
+                unreadable += 1;
+                continue;
+            }
+        };
+
+        let name = job.input.display();
+        for diagnostic in lint(&html) {
+            match diagnostic.severity {
+                Severity::Error => errors += 1,
+                Severity::Warning => warnings += 1,
+            }
+            match args.format {
+                Format::Human => println!("{name}:{diagnostic}"),
+                Format::Json => records.push(json!({
+                    "file": name,
+                    "line": diagnostic.span.position.line + 1,
+                    "column": diagnostic.span.position.column + 1,
+                    "start": diagnostic.span.start,
+                    "end": diagnostic.span.end,
+                    "severity": diagnostic.severity.to_string(),
+                    "code": diagnostic.code.as_str(),
+                    "message": diagnostic.message,
+                })),
+            }
+        }
+    }
+
+    if args.format == Format::Json {
+        let json = serde_json::to_string_pretty(&records).expect("diagnostics serialize");
+        println!("{json}");
+    }
+    if !args.quiet {
+        let checked = jobs.len() - unreadable;
+        eprintln!("{checked} files checked: {errors} errors, {warnings} warnings");
+    }
+    Ok(if unreadable > 0 {
+        EXIT_FAILURE
+    } else if errors > 0 {
+        EXIT_MARKUP_ERRORS
+    } else {
+        0
+    })
+}
+
+/// Re-indents every input instead of minifying it, returning the exit
+/// status.
+fn run_beautify(args: &Args) -> Result<u8, UsageError> {
+    let jobs = plan_jobs(args)?;
+
+    let mut failed = 0;
+    for job in &jobs {
+        let result = read_html(&job.input).and_then(|html| {
+            write_output(&job.output, &beautify_html(&html, &args.indent))
+                .map_err(|err| format!("{}: {err}", job.output.display()))
+        });
+        if let Err(message) = result {
+            eprintln!("html-minifier: {message}");
+            failed += 1;
+        }
+    }
+    Ok(if failed == 0 { 0 } else { EXIT_FAILURE })
+}
+
+/// Runs the command, returning its exit status.
+fn run(args: &Args) -> Result<u8, UsageError> {
+    if args.check {
+        return run_check(args);
+    }
+    if args.beautify {
+        return run_beautify(args);
+    }
+    let mut configs = Configs::new(args)?;
+    let jobs = plan_jobs(args)?;
+
+    let mut failed = 0;
+    let mut markup_errors = 0;
+    let mut total = Savings {
+        input: 0,
+        output: 0,
+    };
+    for job in &jobs {
+        let options = configs.options_for(&job.input, args)?;
+        match run_job(job, &options, args.quiet) {
+            Ok((savings, errors)) => {
+                total.input += savings.input;
+                total.output += savings.output;
+                markup_errors += errors;
+            }
+            Err(message) => {
+                eprintln!("html-minifier: {message}");
+                failed += 1;
+            }
+        }
+    }
+
+    if jobs.len() > 1 && !args.quiet {
+        let minified = jobs.len() - failed;
+        eprintln!("{minified} files minified: {}", total.describe());
+    }
+    Ok(if failed > 0 {
+        EXIT_FAILURE
+    } else if markup_errors > 0 {
+        EXIT_MARKUP_ERRORS
+    } else {
+        0
+    })
+}
+
+fn main() -> ExitCode {
+    let command = match parse_args(std::env::args().skip(1)) {
+        Ok(command) => command,
+        Err(UsageError(message)) => {
+            eprintln!("html-minifier: {message}\n\n{USAGE}");
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+
+    let args = match command {
+        Command::Help => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Command::Version => {
+            println!("html-minifier {}", env!("CARGO_PKG_VERSION"));
+            return ExitCode::SUCCESS;
+        }
+        Command::Run(args) => args,
+    };
+
+    match run(&args) {
+        Ok(status) => ExitCode::from(status),
+        Err(UsageError(message)) => {
+            eprintln!("html-minifier: {message}");
+            ExitCode::from(EXIT_USAGE)
+        }
+    }
+}
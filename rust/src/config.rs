@@ -0,0 +1,381 @@
+//! Configuration files: loading, discovery and per-path overrides.
+//!
+//! A configuration sets [`MinifyOptions`] fields by name and may override
+//! them for documents matching glob patterns:
+//!
+//! ```toml
+//! sort_attributes = true
+//!
+//! [[override]]
+//! paths = ["emails/**"]
+//! sort_attributes = false
+//! ```
+//!
+//! The same structure can be written as JSON, with `override` as an array
+//! of objects. Patterns are relative to the directory of the configuration
+//! file, and matching overrides apply in file order.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+
+use crate::{MinifyOptions, OptionError};
+
+/// Error returned when loading a [`MinifyConfig`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    /// The file could not be read.
+    Io { path: PathBuf, message: String },
+    /// The file is not valid TOML or JSON, or is not shaped like a
+    /// configuration.
+    Parse { path: PathBuf, message: String },
+    /// A key does not name an option, or its value has the wrong type.
+    Option { path: PathBuf, error: OptionError },
+    /// An override has a malformed glob pattern.
+    Glob {
+        path: PathBuf,
+        pattern: String,
+        message: String,
+    },
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Io { path, message } | ConfigError::Parse { path, message } => {
+                write!(f, "{}: {message}", path.display())
+            }
+            ConfigError::Option { path, error } => write!(f, "{}: {error}", path.display()),
+            ConfigError::Glob {
+                path,
+                pattern,
+                message,
+            } => write!(
+                f,
+                "{}: invalid pattern `{pattern}`: {message}",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// One `name = value` entry of a configuration.
+#[derive(Debug, Clone)]
+enum Setting {
+    Bool(bool),
+    Str(String),
+}
+
+impl Setting {
+    fn apply(&self, options: &mut MinifyOptions, name: &str) -> Result<(), OptionError> {
+        match self {
+            Setting::Bool(value) => options.set_bool(name, *value),
+            Setting::Str(value) => options.set_str(name, value),
+        }
+    }
+}
+
+/// Options applied to the documents matching any of `paths`.
+#[derive(Debug, Clone)]
+struct Override {
+    paths: GlobSet,
+    settings: Vec<(String, Setting)>,
+}
+
+/// A loaded configuration file.
+#[derive(Debug, Clone)]
+pub struct MinifyConfig {
+    path: PathBuf,
+    settings: Vec<(String, Setting)>,
+    overrides: Vec<Override>,
+}
+
+/// A format-neutral view of a parsed file: scalar values, string lists and
+/// the `override` array of tables.
+enum Value {
+    Bool(bool),
+    Str(String),
+    List(Vec<String>),
+    Tables(Vec<Vec<(String, Value)>>),
+    Other(String),
+}
+
+impl From<toml::Value> for Value {
+    fn from(value: toml::Value) -> Self {
+        match value {
+            toml::Value::Boolean(value) => Value::Bool(value),
+            toml::Value::String(value) => Value::Str(value),
+            toml::Value::Array(items) if items.iter().all(toml::Value::is_str) => Value::List(
+                items
+                    .into_iter()
+                    .filter_map(|item| item.as_str().map(String::from))
+                    .collect(),
+            ),
+            toml::Value::Array(items) if items.iter().all(toml::Value::is_table) => Value::Tables(
+                items
+                    .into_iter()
+                    .filter_map(|item| match item {
+                        toml::Value::Table(table) => Some(toml_entries(table)),
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+            other => Value::Other(other.to_string()),
+        }
+    }
+}
+
+fn toml_entries(table: toml::Table) -> Vec<(String, Value)> {
+    table
+        .into_iter()
+        .map(|(name, value)| (name, value.into()))
+        .collect()
+}
+
+impl From<serde_json::Value> for Value {
+    fn from(value: serde_json::Value) -> Self {
+        use serde_json::Value as Json;
+
+        match value {
+            Json::Bool(value) => Value::Bool(value),
+            Json::String(value) => Value::Str(value),
+            Json::Array(items) if items.iter().all(Json::is_string) => Value::List(
+                items
+                    .into_iter()
+                    .filter_map(|item| item.as_str().map(String::from))
+                    .collect(),
+            ),
+            Json::Array(items) if items.iter().all(Json::is_object) => Value::Tables(
+                items
+                    .into_iter()
+                    .filter_map(|item| match item {
+                        Json::Object(object) => Some(json_entries(object)),
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+            other => Value::Other(other.to_string()),
+        }
+    }
+}
+
+fn json_entries(object: serde_json::Map<String, serde_json::Value>) -> Vec<(String, Value)> {
+    object
+        .into_iter()
+        .map(|(name, value)| (name, value.into()))
+        .collect()
+}
+
+impl MinifyConfig {
+    /// File names looked for in each directory by [`MinifyConfig::discover`],
+    /// in order of preference.
+    pub const FILE_NAMES: &'static [&'static str] = &["htmlmin.toml", ".htmlminrc"];
+
+    /// Reads a configuration file. Files ending in `.json` are JSON and
+    /// files ending in `.toml` are TOML; any other file, such as
+    /// `.htmlminrc`, is read as JSON if it starts with `{` and as TOML
+    /// otherwise.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path).map_err(|err| ConfigError::Io {
+            path: path.to_path_buf(),
+            message: err.to_string(),
+        })?;
+        Self::parse(path, &source)
+    }
+
+    /// Looks for a configuration file in the directory of `start` (or in
+    /// `start` itself if it is a directory) and then in each parent
+    /// directory, loading the nearest one.
+    pub fn discover(start: impl AsRef<Path>) -> Result<Option<Self>, ConfigError> {
+        let start = std::path::absolute(start.as_ref()).map_err(|err| ConfigError::Io {
+            path: start.as_ref().to_path_buf(),
+            message: err.to_string(),
+        })?;
+        let first_dir = if start.is_dir() {
+            Some(start.as_path())
+        } else {
+            start.parent()
+        };
+
+        for dir in first_dir.into_iter().flat_map(Path::ancestors) {
+            for name in Self::FILE_NAMES {
+                let candidate = dir.join(name);
+                if candidate.is_file() {
+                    return Self::load(candidate).map(Some);
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn parse(path: &Path, source: &str) -> Result<Self, ConfigError> {
+        let parse_error = |message: String| ConfigError::Parse {
+            path: path.to_path_buf(),
+            message,
+        };
+        let is_json = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => true,
+            Some("toml") => false,
+            _ => source.trim_start().starts_with('{'),
+        };
+
+        let entries = if is_json {
+            match serde_json::from_str(source) {
+                Ok(serde_json::Value::Object(object)) => json_entries(object),
+                Ok(_) => return Err(parse_error("expected a JSON object".to_string())),
+                Err(err) => return Err(parse_error(err.to_string())),
+            }
+        } else {
+            let table: toml::Table = source
+                .parse()
+                .map_err(|err: toml::de::Error| parse_error(err.message().to_string()))?;
+            toml_entries(table)
+        };
+
+        let mut config = MinifyConfig {
+            path: path.to_path_buf(),
+            settings: Vec::new(),
+            overrides: Vec::new(),
+        };
+        for (name, value) in entries {
+            if name == "override" {
+                let Value::Tables(tables) = value else {
+                    return Err(parse_error(
+                        "`override` must be an array of tables".to_string(),
+                    ));
+                };
+                for table in tables {
+                    let entry = config.parse_override(table)?;
+                    config.overrides.push(entry);
+                }
+            } else {
+                let setting = config.parse_setting(&name, value)?;
+                config.settings.push((name, setting));
+            }
+        }
+        Ok(config)
+    }
+
+    /// Converts a value to a setting, checking that it names an option of a
+    /// matching type so that errors surface when the file is loaded.
+    fn parse_setting(&self, name: &str, value: Value) -> Result<Setting, ConfigError> {
+        let setting = match value {
+            Value::Bool(value) => Setting::Bool(value),
+            Value::Str(value) => Setting::Str(value),
+            Value::List(items) => Setting::Str(items.join(" ")),
+            Value::Tables(_) => {
+                return Err(self.parse_error(&format!(
+                    "`{name}` is an array of tables, not an option value"
+                )));
+            }
+            Value::Other(value) => Setting::Str(value),
+        };
+        setting
+            .apply(&mut MinifyOptions::default(), name)
+            .map_err(|error| ConfigError::Option {
+                path: self.path.clone(),
+                error,
+            })?;
+        Ok(setting)
+    }
+
+    fn parse_override(&self, table: Vec<(String, Value)>) -> Result<Override, ConfigError> {
+        let mut patterns = None;
+        let mut settings = Vec::new();
+        for (name, value) in table {
+            if name == "paths" {
+                patterns = Some(match value {
+                    Value::Str(pattern) => vec![pattern],
+                    Value::List(patterns) => patterns,
+                    _ => return Err(self.parse_error("override `paths` must be strings")),
+                });
+            } else {
+                let setting = self.parse_setting(&name, value)?;
+                settings.push((name, setting));
+            }
+        }
+
+        let patterns = patterns.ok_or_else(|| self.parse_error("override without `paths`"))?;
+        let mut paths = GlobSetBuilder::new();
+        for pattern in &patterns {
+            let glob = GlobBuilder::new(pattern)
+                .literal_separator(true)
+                .build()
+                .map_err(|err| ConfigError::Glob {
+                    path: self.path.clone(),
+                    pattern: pattern.clone(),
+                    message: err.kind().to_string(),
+                })?;
+            paths.add(glob);
+        }
+        let paths = paths
+            .build()
+            .map_err(|err| self.parse_error(&err.to_string()))?;
+
+        Ok(Override { paths, settings })
+    }
+
+    fn parse_error(&self, message: &str) -> ConfigError {
+        ConfigError::Parse {
+            path: self.path.clone(),
+            message: message.to_string(),
+        }
+    }
+
+    /// The file this configuration was loaded from.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The options set at the top level of the file, ignoring overrides.
+    pub fn options(&self) -> MinifyOptions {
+        let mut options = MinifyOptions::default();
+        apply_settings(&mut options, &self.settings);
+        options
+    }
+
+    /// The options for `document`: the top-level settings followed by every
+    /// override whose patterns match the document's path relative to the
+    /// configuration file.
+    pub fn options_for(&self, document: impl AsRef<Path>) -> MinifyOptions {
+        let mut options = self.options();
+        let Some(relative) = self.relative_path(document.as_ref()) else {
+            return options;
+        };
+        for entry in &self.overrides {
+            if entry.paths.is_match(&relative) {
+                apply_settings(&mut options, &entry.settings);
+            }
+        }
+        options
+    }
+
+    fn relative_path(&self, document: &Path) -> Option<PathBuf> {
+        let base = std::path::absolute(&self.path).ok()?;
+        let document = std::path::absolute(document).ok()?;
+        document
+            .strip_prefix(base.parent()?)
+            .ok()
+            .map(Path::to_path_buf)
+    }
+}
+
+fn apply_settings(options: &mut MinifyOptions, settings: &[(String, Setting)]) {
+    for (name, setting) in settings {
+        setting
+            .apply(options, name)
+            .expect("settings are validated when the configuration is loaded");
+    }
+}
+
+impl MinifyOptions {
+    /// Loads the top-level options of a TOML or JSON configuration file.
+    /// Use [`MinifyConfig`] to honor its per-path overrides.
+    pub fn from_config_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        MinifyConfig::load(path).map(|config| config.options())
+    }
+}
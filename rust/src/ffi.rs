@@ -0,0 +1,622 @@
+//! C ABI exported by the `cdylib` for PHP and other FFI hosts.
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::{
+    minify_batch, minify_css, minify_html, minify_html_tokens, minify_javascript, MinifyError,
+    MinifyOptions, MinifyOutput, MinifyReport, OptionError,
+};
+
+// =============================================================================
+// Versioning
+// =============================================================================
+
+/// Version of the C ABI. Bumped whenever an exported signature or the
+/// meaning of a status code changes, so hosts can refuse a mismatched library.
+pub const HTML_MINIFIER_ABI_VERSION: u32 = 2;
+
+/// Returns [`HTML_MINIFIER_ABI_VERSION`] as compiled into this library.
+#[no_mangle]
+pub extern "C" fn html_minifier_abi_version() -> u32 {
+    HTML_MINIFIER_ABI_VERSION
+}
+
+/// Returns the crate version as a static NUL-terminated string that must not
+/// be freed.
+#[no_mangle]
+pub extern "C" fn html_minifier_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char
+}
+
+// =============================================================================
+// Error Reporting
+// =============================================================================
+
+/// Result of an exported call. Every function that can fail records its
+/// status, readable afterwards through [`html_minifier_last_status`] and
+/// [`html_minifier_last_error`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MinifyStatus {
+    /// The call succeeded.
+    Ok = 0,
+    /// A required pointer argument was null.
+    NullPointer = 1,
+    /// The input was not valid UTF-8.
+    InvalidUtf8 = 2,
+    /// The output contained a NUL byte and cannot be returned as a C string.
+    InteriorNul = 3,
+    /// The minifier panicked; the input was left unprocessed.
+    Panic = 4,
+    /// No configuration option has the given name.
+    UnknownOption = 5,
+    /// The value given for a configuration option has the wrong type.
+    InvalidOption = 6,
+    /// The `verify` option is set and the output was not equivalent to the
+    /// input; no output was produced.
+    VerificationFailed = 7,
+}
+
+struct LastError {
+    status: MinifyStatus,
+    message: Option<CString>,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<LastError> = const {
+        RefCell::new(LastError {
+            status: MinifyStatus::Ok,
+            message: None,
+        })
+    };
+}
+
+fn set_last_error(status: MinifyStatus, message: &str) {
+    LAST_ERROR.with(|last| {
+        *last.borrow_mut() = LastError {
+            status,
+            message: CString::new(message).ok(),
+        };
+    });
+}
+
+fn clear_last_error() {
+    LAST_ERROR.with(|last| {
+        *last.borrow_mut() = LastError {
+            status: MinifyStatus::Ok,
+            message: None,
+        };
+    });
+}
+
+/// Records `status` as the calling thread's last error and returns it.
+fn fail(status: MinifyStatus, message: &str) -> MinifyStatus {
+    set_last_error(status, message);
+    status
+}
+
+/// Status of the most recent call made on this thread.
+#[no_mangle]
+pub extern "C" fn html_minifier_last_status() -> MinifyStatus {
+    LAST_ERROR.with(|last| last.borrow().status)
+}
+
+/// Message describing the most recent failure on this thread, or null if the
+/// last call succeeded. The pointer stays valid until the next call on this
+/// thread and must not be freed.
+#[no_mangle]
+pub extern "C" fn html_minifier_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .message
+            .as_ref()
+            .map_or(std::ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Runs `minify` over `input`, turning a panic into [`MinifyStatus::Panic`]
+/// instead of unwinding across the FFI boundary.
+fn run_minifier(
+    input: &str,
+    minify: impl FnOnce(&str) -> Result<String, MinifyStatus>,
+) -> Result<String, MinifyStatus> {
+    panic::catch_unwind(AssertUnwindSafe(|| minify(input)))
+        .unwrap_or_else(|_| Err(fail(MinifyStatus::Panic, "minifier panicked")))
+}
+
+/// The HTML of `output`, or [`MinifyStatus::VerificationFailed`] if
+/// verification was requested and found a problem.
+fn verified_html(output: MinifyOutput) -> Result<String, MinifyStatus> {
+    match output.verification {
+        Some(verification) if !verification.is_ok() => Err(fail(
+            MinifyStatus::VerificationFailed,
+            &format!("verification failed: {verification}"),
+        )),
+        _ => Ok(output.html),
+    }
+}
+
+// =============================================================================
+// NUL-Terminated String Interface
+// =============================================================================
+
+/// Minifies the NUL-terminated string at `ptr` and returns a C string owned
+/// by the caller, or null with the last error set.
+unsafe fn minify_c_string(ptr: *const c_char, minify: fn(&str) -> String) -> *mut c_char {
+    if ptr.is_null() {
+        fail(MinifyStatus::NullPointer, "input pointer is null");
+        return std::ptr::null_mut();
+    }
+
+    let Ok(input) = CStr::from_ptr(ptr).to_str() else {
+        fail(MinifyStatus::InvalidUtf8, "input is not valid UTF-8");
+        return std::ptr::null_mut();
+    };
+
+    let Ok(minified) = run_minifier(input, |input| Ok(minify(input))) else {
+        return std::ptr::null_mut();
+    };
+
+    match CString::new(minified) {
+        Ok(c_string) => {
+            clear_last_error();
+            c_string.into_raw()
+        }
+        Err(_) => {
+            fail(MinifyStatus::InteriorNul, "output contains a NUL byte");
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Minifies a NUL-terminated HTML string and returns a newly allocated copy,
+/// or null on failure with the reason available from
+/// [`html_minifier_last_error`].
+///
+/// # Safety
+///
+/// `html_ptr` must be null or point to a valid NUL-terminated string. The
+/// returned pointer must be released with [`free_string`].
+#[no_mangle]
+pub unsafe extern "C" fn minify_html_string(html_ptr: *const c_char) -> *mut c_char {
+    minify_c_string(html_ptr, minify_html_tokens)
+}
+
+/// Minifies a NUL-terminated CSS string and returns a newly allocated copy,
+/// or null on failure with the reason available from
+/// [`html_minifier_last_error`].
+///
+/// # Safety
+///
+/// `css_ptr` must be null or point to a valid NUL-terminated string. The
+/// returned pointer must be released with [`free_string`].
+#[no_mangle]
+pub unsafe extern "C" fn minify_css_string(css_ptr: *const c_char) -> *mut c_char {
+    minify_c_string(css_ptr, minify_css)
+}
+
+/// Minifies a NUL-terminated JavaScript string and returns a newly allocated
+/// copy, or null on failure with the reason available from
+/// [`html_minifier_last_error`].
+///
+/// # Safety
+///
+/// `js_ptr` must be null or point to a valid NUL-terminated string. The
+/// returned pointer must be released with [`free_string`].
+#[no_mangle]
+pub unsafe extern "C" fn minify_javascript_string(js_ptr: *const c_char) -> *mut c_char {
+    minify_c_string(js_ptr, minify_javascript)
+}
+
+/// Releases a string returned by one of the minification functions.
+///
+/// # Safety
+///
+/// `ptr` must be null or a pointer previously returned by this library that
+/// has not already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn free_string(ptr: *mut c_char) {
+    if !ptr.is_null() {
+        let _ = CString::from_raw(ptr);
+    }
+}
+
+// =============================================================================
+// Length-Delimited Buffer Interface
+// =============================================================================
+
+/// Runs `minify` over the `len` bytes at `ptr` and hands the result back
+/// through `out_ptr`/`out_len` as a buffer owned by the caller.
+unsafe fn minify_buffer(
+    ptr: *const c_char,
+    len: usize,
+    out_ptr: *mut *mut c_char,
+    out_len: *mut usize,
+    minify: impl FnOnce(&str) -> Result<String, MinifyStatus>,
+) -> MinifyStatus {
+    if out_ptr.is_null() || out_len.is_null() {
+        return fail(MinifyStatus::NullPointer, "output pointer is null");
+    }
+    if ptr.is_null() && len != 0 {
+        return fail(MinifyStatus::NullPointer, "input pointer is null");
+    }
+
+    let input = if len == 0 {
+        &[][..]
+    } else {
+        std::slice::from_raw_parts(ptr as *const u8, len)
+    };
+    let input = match std::str::from_utf8(input) {
+        Ok(input) => input,
+        Err(err) => {
+            let message = format!("input is not valid UTF-8: {err}");
+            return fail(MinifyStatus::InvalidUtf8, &message);
+        }
+    };
+
+    let output = match run_minifier(input, minify) {
+        Ok(output) => output.into_bytes().into_boxed_slice(),
+        Err(status) => return status,
+    };
+    *out_len = output.len();
+    *out_ptr = Box::into_raw(output) as *mut c_char;
+    clear_last_error();
+    MinifyStatus::Ok
+}
+
+/// Minifies `len` bytes of HTML at `ptr`, which may contain NUL bytes.
+///
+/// Returns [`MinifyStatus::Ok`] and stores the output in `*out_ptr`/`*out_len`,
+/// or an error status and leaves both untouched.
+///
+/// # Safety
+///
+/// `ptr` must point to `len` readable bytes (or be null when `len` is 0), and
+/// `out_ptr`/`out_len` must be valid for writes. The output buffer must be
+/// released with [`free_buffer`].
+#[no_mangle]
+pub unsafe extern "C" fn minify_html_bytes(
+    ptr: *const c_char,
+    len: usize,
+    out_ptr: *mut *mut c_char,
+    out_len: *mut usize,
+) -> MinifyStatus {
+    minify_buffer(ptr, len, out_ptr, out_len, |html| {
+        Ok(minify_html_tokens(html))
+    })
+}
+
+/// Minifies `len` bytes of CSS at `ptr`. See [`minify_html_bytes`].
+///
+/// # Safety
+///
+/// Same contract as [`minify_html_bytes`].
+#[no_mangle]
+pub unsafe extern "C" fn minify_css_bytes(
+    ptr: *const c_char,
+    len: usize,
+    out_ptr: *mut *mut c_char,
+    out_len: *mut usize,
+) -> MinifyStatus {
+    minify_buffer(ptr, len, out_ptr, out_len, |css| Ok(minify_css(css)))
+}
+
+/// Minifies `len` bytes of JavaScript at `ptr`. See [`minify_html_bytes`].
+///
+/// # Safety
+///
+/// Same contract as [`minify_html_bytes`].
+#[no_mangle]
+pub unsafe extern "C" fn minify_javascript_bytes(
+    ptr: *const c_char,
+    len: usize,
+    out_ptr: *mut *mut c_char,
+    out_len: *mut usize,
+) -> MinifyStatus {
+    minify_buffer(ptr, len, out_ptr, out_len, |js| Ok(minify_javascript(js)))
+}
+
+/// Releases a buffer returned by one of the `*_bytes` functions.
+///
+/// # Safety
+///
+/// `ptr` and `len` must be exactly the values returned through `out_ptr` and
+/// `out_len`, and the buffer must not already have been freed.
+#[no_mangle]
+pub unsafe extern "C" fn free_buffer(ptr: *mut c_char, len: usize) {
+    if !ptr.is_null() {
+        let _ = Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr as *mut u8, len));
+    }
+}
+
+// =============================================================================
+// Configuration Interface
+// =============================================================================
+
+/// Opaque handle carrying [`MinifyOptions`] across the FFI boundary.
+pub struct MinifierConfig {
+    options: MinifyOptions,
+}
+
+/// Creates a configuration with default options. Release it with
+/// [`minifier_config_free`].
+#[no_mangle]
+pub extern "C" fn minifier_config_new() -> *mut MinifierConfig {
+    Box::into_raw(Box::new(MinifierConfig {
+        options: MinifyOptions::default(),
+    }))
+}
+
+/// Releases a configuration created by [`minifier_config_new`].
+///
+/// # Safety
+///
+/// `config` must be null or a handle from [`minifier_config_new`] that has not
+/// already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn minifier_config_free(config: *mut MinifierConfig) {
+    if !config.is_null() {
+        let _ = Box::from_raw(config);
+    }
+}
+
+fn option_status(result: Result<(), OptionError>) -> MinifyStatus {
+    match result {
+        Ok(()) => {
+            clear_last_error();
+            MinifyStatus::Ok
+        }
+        Err(err @ OptionError::Unknown(_)) => fail(MinifyStatus::UnknownOption, &err.to_string()),
+        Err(err @ OptionError::InvalidValue { .. }) => {
+            fail(MinifyStatus::InvalidOption, &err.to_string())
+        }
+    }
+}
+
+/// Reads the option name at `name`, recording the failure if it is unusable.
+unsafe fn option_name<'a>(name: *const c_char) -> Result<&'a str, MinifyStatus> {
+    if name.is_null() {
+        return Err(fail(MinifyStatus::NullPointer, "option name is null"));
+    }
+    CStr::from_ptr(name)
+        .to_str()
+        .map_err(|_| fail(MinifyStatus::InvalidUtf8, "option name is not valid UTF-8"))
+}
+
+/// Sets a boolean option, named as the corresponding [`MinifyOptions`] field.
+///
+/// # Safety
+///
+/// `config` must be a live handle from [`minifier_config_new`] and `name` a
+/// valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn minifier_config_set_bool(
+    config: *mut MinifierConfig,
+    name: *const c_char,
+    value: bool,
+) -> MinifyStatus {
+    if config.is_null() {
+        return fail(MinifyStatus::NullPointer, "config is null");
+    }
+    let name = match option_name(name) {
+        Ok(name) => name,
+        Err(status) => return status,
+    };
+    option_status((*config).options.set_bool(name, value))
+}
+
+/// Sets any option from a string value; see [`MinifyOptions::set_str`].
+///
+/// # Safety
+///
+/// `config` must be a live handle from [`minifier_config_new`], and `name` and
+/// `value` valid NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn minifier_config_set_str(
+    config: *mut MinifierConfig,
+    name: *const c_char,
+    value: *const c_char,
+) -> MinifyStatus {
+    if config.is_null() || value.is_null() {
+        return fail(MinifyStatus::NullPointer, "config or value is null");
+    }
+    let name = match option_name(name) {
+        Ok(name) => name,
+        Err(status) => return status,
+    };
+    let Ok(value) = CStr::from_ptr(value).to_str() else {
+        return fail(MinifyStatus::InvalidUtf8, "option value is not valid UTF-8");
+    };
+    option_status((*config).options.set_str(name, value))
+}
+
+/// Minifies `len` bytes of HTML at `ptr` using the options in `config`, or the
+/// defaults when `config` is null. Otherwise behaves like
+/// [`minify_html_bytes`].
+///
+/// # Safety
+///
+/// `config` must be null or a live handle from [`minifier_config_new`]; the
+/// remaining arguments follow [`minify_html_bytes`].
+#[no_mangle]
+pub unsafe extern "C" fn minify_html_with_config(
+    config: *const MinifierConfig,
+    ptr: *const c_char,
+    len: usize,
+    out_ptr: *mut *mut c_char,
+    out_len: *mut usize,
+) -> MinifyStatus {
+    let default_options = MinifyOptions::default();
+    let options = if config.is_null() {
+        &default_options
+    } else {
+        &(*config).options
+    };
+    minify_buffer(ptr, len, out_ptr, out_len, |html| {
+        verified_html(minify_html(html, options))
+    })
+}
+
+/// Like [`minify_html_with_config`], additionally filling `*report` with the
+/// savings and timings of the run.
+///
+/// # Safety
+///
+/// `report` must be valid for writes; the remaining arguments follow
+/// [`minify_html_with_config`].
+#[no_mangle]
+pub unsafe extern "C" fn minify_html_with_report(
+    config: *const MinifierConfig,
+    ptr: *const c_char,
+    len: usize,
+    out_ptr: *mut *mut c_char,
+    out_len: *mut usize,
+    report: *mut MinifyReport,
+) -> MinifyStatus {
+    if report.is_null() {
+        return fail(MinifyStatus::NullPointer, "report pointer is null");
+    }
+
+    let mut options = if config.is_null() {
+        MinifyOptions::default()
+    } else {
+        (*config).options.clone()
+    };
+    options.collect_report = true;
+
+    let mut collected = None;
+    let status = minify_buffer(ptr, len, out_ptr, out_len, |html| {
+        let output = minify_html(html, &options);
+        collected = output.report;
+        verified_html(output)
+    });
+    if let Some(collected) = collected {
+        *report = collected;
+    }
+    status
+}
+
+// =============================================================================
+// Batch Interface
+// =============================================================================
+
+/// Minifies `count` HTML documents in parallel with the options in `config`,
+/// or the defaults when `config` is null. Document `i` is the
+/// `input_lens[i]` bytes at `inputs[i]`.
+///
+/// Each document that succeeds gets its output in `outputs[i]` and
+/// `output_lens[i]`, to be released with [`free_buffer`]. A document that
+/// fails gets a null output and a length of 0, and the batch carries on
+/// with the others. When `statuses` is not null, `statuses[i]` receives each
+/// document's status.
+///
+/// Returns [`MinifyStatus::Ok`] if every document was minified. Otherwise it
+/// returns the status of the first failed document, and the last error
+/// names that document's index.
+///
+/// # Safety
+///
+/// `config` must be null or a live handle from [`minifier_config_new`].
+/// `inputs` and `input_lens` must each point to `count` readable elements,
+/// and every `inputs[i]` must point to `input_lens[i]` readable bytes, or be
+/// null when that length is 0. `outputs` and `output_lens` must each point to
+/// `count` writable elements, as must `statuses` unless it is null. Any of
+/// the array pointers may be null when `count` is 0.
+#[no_mangle]
+pub unsafe extern "C" fn minify_html_batch(
+    config: *const MinifierConfig,
+    inputs: *const *const c_char,
+    input_lens: *const usize,
+    count: usize,
+    outputs: *mut *mut c_char,
+    output_lens: *mut usize,
+    statuses: *mut MinifyStatus,
+) -> MinifyStatus {
+    if count == 0 {
+        clear_last_error();
+        return MinifyStatus::Ok;
+    }
+    if inputs.is_null() || input_lens.is_null() || outputs.is_null() || output_lens.is_null() {
+        return fail(MinifyStatus::NullPointer, "batch array pointer is null");
+    }
+
+    let default_options = MinifyOptions::default();
+    let options = if config.is_null() {
+        &default_options
+    } else {
+        &(*config).options
+    };
+    let inputs = std::slice::from_raw_parts(inputs, count);
+    let input_lens = std::slice::from_raw_parts(input_lens, count);
+    let outputs = std::slice::from_raw_parts_mut(outputs, count);
+    let output_lens = std::slice::from_raw_parts_mut(output_lens, count);
+    outputs.fill(std::ptr::null_mut());
+    output_lens.fill(0);
+
+    // Only valid UTF-8 goes to the worker threads; the rest fail up front
+    let mut failures = Vec::new();
+    let mut documents = Vec::with_capacity(count);
+    let mut document_indices = Vec::with_capacity(count);
+    for (index, (&ptr, &len)) in inputs.iter().zip(input_lens).enumerate() {
+        let bytes = if len == 0 {
+            &[][..]
+        } else if ptr.is_null() {
+            failures.push((
+                index,
+                MinifyStatus::NullPointer,
+                "input pointer is null".into(),
+            ));
+            continue;
+        } else {
+            std::slice::from_raw_parts(ptr as *const u8, len)
+        };
+
+        match std::str::from_utf8(bytes) {
+            Ok(input) => {
+                documents.push(input);
+                document_indices.push(index);
+            }
+            Err(err) => {
+                let message = format!("input is not valid UTF-8: {err}");
+                failures.push((index, MinifyStatus::InvalidUtf8, message));
+            }
+        }
+    }
+
+    let results = minify_batch(&documents, options);
+    for (index, result) in document_indices.into_iter().zip(results) {
+        match result {
+            Ok(output) => {
+                let output = output.into_bytes().into_boxed_slice();
+                output_lens[index] = output.len();
+                outputs[index] = Box::into_raw(output) as *mut c_char;
+            }
+            Err(err) => {
+                let status = match err {
+                    MinifyError::Panicked(_) => MinifyStatus::Panic,
+                    MinifyError::Unverified(_) => MinifyStatus::VerificationFailed,
+                };
+                failures.push((index, status, err.to_string()));
+            }
+        }
+    }
+
+    if !statuses.is_null() {
+        let statuses = std::slice::from_raw_parts_mut(statuses, count);
+        statuses.fill(MinifyStatus::Ok);
+        for &(index, status, _) in &failures {
+            statuses[index] = status;
+        }
+    }
+
+    match failures.into_iter().min_by_key(|&(index, _, _)| index) {
+        Some((index, status, message)) => fail(status, &format!("document {index}: {message}")),
+        None => {
+            clear_last_error();
+            MinifyStatus::Ok
+        }
+    }
+}
@@ -0,0 +1,653 @@
+//! Foreign content: inline `<svg>` and `<math>`, which the HTML parser
+//! reads by rules of its own. Element and attribute names there are
+//! case-sensitive, so they are written as authored, and `/>` closes any
+//! element. The text of MathML token elements such as `<mi>` and `<mtext>`
+//! is written as it is, since its whitespace renders. `<style>`, `<script>`
+//! and `<title>` hold markup there rather than raw text, and the text of
+//! foreign `<style>` and `<script>` is kept as written.
+//!
+//! With [`MinifyOptions::minify_svg`], SVG attributes are optimized too:
+//! path data and numbers take their shortest form, optionally rounded to
+//! [`MinifyOptions::svg_precision`] decimals, attributes at their initial
+//! values are dropped, and `<metadata>` and the markup that drawing editors
+//! leave behind are removed.
+
+use std::borrow::Cow;
+
+use crate::{contains_ignore_ascii_case, MinifyOptions, Token};
+
+// =============================================================================
+// Foreign Content Tracking
+// =============================================================================
+
+/// HTML elements whose start tags end foreign content. The parser closes
+/// the open SVG or MathML elements and reads them as HTML.
+const BREAKOUT_ELEMENTS: &[&str] = &[
+    "b",
+    "big",
+    "blockquote",
+    "body",
+    "br",
+    "center",
+    "code",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "embed",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "head",
+    "hr",
+    "i",
+    "img",
+    "li",
+    "listing",
+    "menu",
+    "meta",
+    "nobr",
+    "ol",
+    "p",
+    "pre",
+    "ruby",
+    "s",
+    "small",
+    "span",
+    "strike",
+    "strong",
+    "sub",
+    "sup",
+    "table",
+    "tt",
+    "u",
+    "ul",
+    "var",
+];
+
+/// Attributes that make `<font>` end foreign content like the elements in
+/// [`BREAKOUT_ELEMENTS`]. Without any of them it is an SVG or MathML element.
+const FONT_BREAKOUT_ATTRIBUTES: &[&str] = &["color", "face", "size"];
+
+/// MathML token elements, whose content the parser reads as HTML and
+/// whose whitespace renders.
+const MATH_TEXT_ELEMENTS: &[&str] = &["mi", "mn", "mo", "ms", "mtext"];
+
+/// The namespace the tag or text being read belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Namespace {
+    Html,
+    Svg,
+    MathMl,
+}
+
+/// A region of a document read by one set of rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Island {
+    Svg,
+    Math,
+    /// The HTML content of a `<foreignObject>` or MathML token element,
+    /// which the element of this name ends.
+    Html(&'static str),
+}
+
+impl Island {
+    /// The name of the element that opens and closes the region.
+    fn element(self) -> &'static str {
+        match self {
+            Island::Svg => "svg",
+            Island::Math => "math",
+            Island::Html(element) => element,
+        }
+    }
+}
+
+/// Whether the tokens of a document are HTML, SVG or MathML. It follows
+/// tags and the attributes of `<font>` alone, so the tokenizer, the
+/// emitter, the verifier, the beautifier and streaming minification share
+/// it.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ForeignContent {
+    /// Regions entered from the document outward, each with how many
+    /// elements of the name that opened it are nested inside it.
+    islands: Vec<(Island, usize)>,
+    /// The start tag being read is a `<font>` in foreign content, which
+    /// one of [`FONT_BREAKOUT_ATTRIBUTES`] would end.
+    font_start_tag: bool,
+}
+
+impl ForeignContent {
+    /// The namespace of the tag or text being read.
+    pub(crate) fn namespace(&self) -> Namespace {
+        namespace_of(&self.islands)
+    }
+
+    /// The namespace a start tag named `name` is read in, before
+    /// [`ForeignContent::start_tag`] notes it: HTML if it ends foreign
+    /// content, and otherwise that of the content around it. A `<font>`
+    /// that its attributes take out of foreign content is only known to be
+    /// HTML once [`ForeignContent::attribute`] says so.
+    pub(crate) fn start_tag_namespace(&self, name: &str) -> Namespace {
+        match self.namespace() {
+            Namespace::Svg | Namespace::MathMl
+                if contains_ignore_ascii_case(BREAKOUT_ELEMENTS, name) =>
+            {
+                Namespace::Html
+            }
+            namespace => namespace,
+        }
+    }
+
+    /// The namespace of the content an end tag returns to, before
+    /// [`ForeignContent::end_tag`] notes it.
+    pub(crate) fn end_tag_namespace(&self, tag: &str) -> Namespace {
+        match self.closed_by(tag) {
+            Some((open, _)) => namespace_of(&self.islands[..open]),
+            None => self.namespace(),
+        }
+    }
+
+    /// Whether the tag or text being read is SVG or MathML.
+    pub(crate) fn is_foreign(&self) -> bool {
+        self.namespace() != Namespace::Html
+    }
+
+    /// Whether the text being read is inside a MathML token element.
+    pub(crate) fn in_math_text(&self) -> bool {
+        matches!(self.islands.last(), Some((Island::Html(element), _))
+            if MATH_TEXT_ELEMENTS.contains(element))
+    }
+
+    /// Notes a start tag before its attributes are read, since the
+    /// attributes of `<svg>` and `<math>` are foreign themselves.
+    pub(crate) fn start_tag(&mut self, name: &str) {
+        let foreign = self.is_foreign();
+        self.font_start_tag = foreign && name.eq_ignore_ascii_case("font");
+        match self.islands.last_mut() {
+            Some((island, nested)) if island.element().eq_ignore_ascii_case(name) => *nested += 1,
+            _ if foreign && contains_ignore_ascii_case(BREAKOUT_ELEMENTS, name) => {
+                self.islands.pop();
+            }
+            _ if foreign => {}
+            _ if name.eq_ignore_ascii_case("svg") => self.islands.push((Island::Svg, 0)),
+            _ if name.eq_ignore_ascii_case("math") => self.islands.push((Island::Math, 0)),
+            _ => {}
+        }
+    }
+
+    /// Notes an attribute of the start tag being read, and returns whether
+    /// it ends foreign content. That makes the `<font>` it belongs to an
+    /// HTML element, along with the attributes from this one on.
+    pub(crate) fn attribute(&mut self, name: &str) -> bool {
+        if !(self.font_start_tag && contains_ignore_ascii_case(FONT_BREAKOUT_ATTRIBUTES, name)) {
+            return false;
+        }
+        self.font_start_tag = false;
+        self.islands.pop();
+        true
+    }
+
+    /// Notes the end of a start tag. The content of a `<foreignObject>` or
+    /// MathML token element is HTML again.
+    pub(crate) fn end_start_tag(&mut self, name: &str, self_closing: bool) {
+        self.font_start_tag = false;
+        let integration_point = match self.namespace() {
+            // Only foreign elements can be self-closing
+            Namespace::Svg | Namespace::MathMl if self_closing => {
+                self.end_tag(name);
+                return;
+            }
+            Namespace::Svg if name.eq_ignore_ascii_case("foreignObject") => "foreignObject",
+            Namespace::MathMl => match MATH_TEXT_ELEMENTS
+                .iter()
+                .find(|element| element.eq_ignore_ascii_case(name))
+            {
+                Some(element) => element,
+                None => return,
+            },
+            _ => return,
+        };
+        self.islands.push((Island::Html(integration_point), 0));
+    }
+
+    /// Notes an end tag before it is written, since the end tags of
+    /// `<foreignObject>` and MathML token elements belong to the foreign
+    /// content around them. In foreign content, an end tag also closes the
+    /// foreign elements left open inside the element it names.
+    pub(crate) fn end_tag(&mut self, tag: &str) {
+        if let Some((open, nested)) = self.closed_by(tag) {
+            self.islands.truncate(open);
+            if nested {
+                self.islands[open - 1].1 -= 1;
+            }
+        }
+    }
+
+    /// How many regions stay open after an end tag that ends any, and
+    /// whether it ends an element nested in the innermost of them rather
+    /// than the element that opened it.
+    fn closed_by(&self, tag: &str) -> Option<(usize, bool)> {
+        let name = tag.split_ascii_whitespace().next().unwrap_or_default();
+        for (index, (island, nested)) in self.islands.iter().enumerate().rev() {
+            if island.element().eq_ignore_ascii_case(name) {
+                return Some(match nested {
+                    0 => (index, false),
+                    _ => (index + 1, true),
+                });
+            }
+            if matches!(island, Island::Html(_)) {
+                return None;
+            }
+        }
+        None
+    }
+
+    /// How many regions are open, which falls below its earlier value once
+    /// the element that opened a region has ended.
+    pub(crate) fn depth(&self) -> usize {
+        self.islands.len()
+    }
+}
+
+/// The namespace of the innermost of `islands`.
+fn namespace_of(islands: &[(Island, usize)]) -> Namespace {
+    match islands.last() {
+        Some((Island::Svg, _)) => Namespace::Svg,
+        Some((Island::Math, _)) => Namespace::MathMl,
+        Some((Island::Html(_), _)) | None => Namespace::Html,
+    }
+}
+
+/// An element being dropped along with everything inside it.
+#[derive(Debug, Clone)]
+pub(crate) struct SkippedElement {
+    name: String,
+    /// Open elements of the same name, the dropped one included.
+    depth: usize,
+    /// The last start tag read was one of `name`.
+    in_start_tag: bool,
+}
+
+impl SkippedElement {
+    /// Starts dropping at the start tag of `name`.
+    pub(crate) fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            depth: 1,
+            in_start_tag: true,
+        }
+    }
+
+    /// Notes a token inside the element and returns whether the element
+    /// has ended with it.
+    pub(crate) fn token(&mut self, token: &Token) -> bool {
+        match *token {
+            Token::TagOpenStart(name) => {
+                self.in_start_tag = name.eq_ignore_ascii_case(&self.name);
+                self.depth += usize::from(self.in_start_tag);
+            }
+            Token::TagOpenEnd => self.in_start_tag = false,
+            Token::TagSelfClose => {
+                self.depth -= usize::from(self.in_start_tag);
+                self.in_start_tag = false;
+            }
+            Token::TagClose(tag) => {
+                let name = tag.split_ascii_whitespace().next().unwrap_or_default();
+                if name.eq_ignore_ascii_case(&self.name) {
+                    self.depth -= 1;
+                }
+            }
+            _ => {}
+        }
+        self.depth == 0
+    }
+}
+
+// =============================================================================
+// SVG Optimization Tables
+// =============================================================================
+
+/// Namespace prefixes of the markup drawing editors add for their own use.
+const EDITOR_PREFIXES: &[&str] = &[
+    "inkscape", "sodipodi", "sketch", "serif", "i", "x", "graph", "figma", "rdf", "cc", "dc",
+];
+
+/// Namespace declarations the HTML parser ignores, since it assigns SVG
+/// and XLink names their namespaces itself.
+const IMPLIED_NAMESPACES: &[(&str, &str)] = &[
+    ("xmlns", "http://www.w3.org/2000/svg"),
+    ("xmlns:xlink", "http://www.w3.org/1999/xlink"),
+];
+
+/// Attributes holding one number, optionally with a unit.
+pub(crate) const NUMBER_ATTRIBUTES: &[&str] = &[
+    "cx",
+    "cy",
+    "dx",
+    "dy",
+    "fill-opacity",
+    "flood-opacity",
+    "font-size",
+    "fr",
+    "fx",
+    "fy",
+    "height",
+    "markerHeight",
+    "markerWidth",
+    "offset",
+    "opacity",
+    "pathLength",
+    "r",
+    "refX",
+    "refY",
+    "rx",
+    "ry",
+    "startOffset",
+    "stop-opacity",
+    "stroke-dashoffset",
+    "stroke-miterlimit",
+    "stroke-opacity",
+    "stroke-width",
+    "width",
+    "x",
+    "x1",
+    "x2",
+    "y",
+    "y1",
+    "y2",
+];
+
+/// Attributes holding a list of numbers.
+pub(crate) const NUMBER_LIST_ATTRIBUTES: &[&str] = &["points", "stroke-dasharray", "viewBox"];
+
+/// Attributes at their initial values, which can be left out. Inherited
+/// properties such as `fill` are not listed: an ancestor or a stylesheet
+/// may set them to something else, which the attribute would override.
+const DEFAULT_VALUES: &[(&[&str], &str, &str)] = &[
+    (&[], "opacity", "1"),
+    (&[], "stop-opacity", "1"),
+    (&[], "flood-opacity", "1"),
+    (&[], "clip-path", "none"),
+    (&[], "mask", "none"),
+    (&[], "filter", "none"),
+    (&["rect", "image", "use", "foreignObject"], "x", "0"),
+    (&["rect", "image", "use", "foreignObject"], "y", "0"),
+    (&["circle", "ellipse"], "cx", "0"),
+    (&["circle", "ellipse"], "cy", "0"),
+    (&["line"], "x1", "0"),
+    (&["line"], "y1", "0"),
+    (&["line"], "x2", "0"),
+    (&["line"], "y2", "0"),
+];
+
+/// Whether `name` is in the namespace of a drawing editor.
+fn has_editor_prefix(name: &str) -> bool {
+    name.split_once(':')
+        .is_some_and(|(prefix, _)| contains_ignore_ascii_case(EDITOR_PREFIXES, prefix))
+}
+
+/// Whether the element `name`, read in `namespace`, is dropped with its
+/// content by [`MinifyOptions::minify_svg`].
+pub(crate) fn is_removable_element(
+    namespace: Namespace,
+    name: &str,
+    options: &MinifyOptions,
+) -> bool {
+    options.minify_svg
+        && namespace == Namespace::Svg
+        && (name.eq_ignore_ascii_case("metadata") || has_editor_prefix(name))
+}
+
+/// Whether an SVG attribute is dropped by [`MinifyOptions::minify_svg`].
+pub(crate) fn is_removable_attribute(tag: &str, name: &str, value: &str) -> bool {
+    if has_editor_prefix(name) {
+        return true;
+    }
+    let declared = name
+        .split_once(':')
+        .filter(|(xmlns, _)| xmlns.eq_ignore_ascii_case("xmlns"));
+    if let Some((_, prefix)) = declared {
+        if contains_ignore_ascii_case(EDITOR_PREFIXES, prefix) {
+            return true;
+        }
+    }
+    if IMPLIED_NAMESPACES
+        .iter()
+        .any(|&(attribute, uri)| attribute.eq_ignore_ascii_case(name) && uri == value.trim())
+    {
+        return true;
+    }
+    DEFAULT_VALUES.iter().any(|&(tags, attribute, default)| {
+        attribute.eq_ignore_ascii_case(name)
+            && (tags.is_empty() || contains_ignore_ascii_case(tags, tag))
+            && compact_number_list(value, None).is_some_and(|value| value == default)
+    })
+}
+
+// =============================================================================
+// Numbers and Path Data
+// =============================================================================
+
+/// Splits the number at the start of `text`, in SVG syntax, from what
+/// follows it.
+fn split_number(text: &str) -> Option<(&str, &str)> {
+    let bytes = text.as_bytes();
+    let mut end = usize::from(matches!(bytes.first(), Some(b'+' | b'-')));
+    let digits = |from: usize| {
+        bytes[from..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count()
+    };
+
+    let integer = digits(end);
+    end += integer;
+    let mut fraction = 0;
+    if bytes.get(end) == Some(&b'.') {
+        fraction = digits(end + 1);
+        if fraction > 0 {
+            end += 1 + fraction;
+        }
+    }
+    if integer == 0 && fraction == 0 {
+        return None;
+    }
+    if matches!(bytes.get(end), Some(b'e' | b'E')) {
+        let sign = usize::from(matches!(bytes.get(end + 1), Some(b'+' | b'-')));
+        let exponent = digits(end + 1 + sign);
+        if exponent > 0 {
+            end += 1 + sign + exponent;
+        }
+    }
+    Some(text.split_at(end))
+}
+
+/// Appends `number`, as written in SVG, in its shortest form: without a
+/// `+` sign or needless zeros, and rounded to `precision` decimals when
+/// given, unless rounding would make it longer.
+fn push_number(out: &mut String, number: &str, precision: Option<u32>) {
+    let rounded = precision.and_then(|precision| {
+        let value: f64 = number
+            .parse()
+            .ok()
+            .filter(|value: &f64| value.is_finite())?;
+        let rounded = format!("{value:.0$}", precision as usize);
+        let rounded = shorten_number(&rounded);
+        (rounded.len() <= number.len()).then_some(rounded)
+    });
+    match rounded {
+        Some(rounded) => out.push_str(&rounded),
+        None => out.push_str(&shorten_number(number)),
+    }
+}
+
+/// Drops the `+` sign, leading and trailing zeros of a number without
+/// changing its value.
+fn shorten_number(number: &str) -> String {
+    let (mantissa, exponent) = match number.find(['e', 'E']) {
+        Some(at) => number.split_at(at),
+        None => (number, ""),
+    };
+    let (negative, digits) = match mantissa.as_bytes().first() {
+        Some(b'-') => (true, &mantissa[1..]),
+        Some(b'+') => (false, &mantissa[1..]),
+        _ => (false, mantissa),
+    };
+    let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    let integer = integer.trim_start_matches('0');
+    let fraction = fraction.trim_end_matches('0');
+
+    let mut out = String::with_capacity(number.len());
+    if integer.is_empty() && fraction.is_empty() {
+        out.push('0');
+        return out;
+    }
+    if negative {
+        out.push('-');
+    }
+    out.push_str(integer);
+    if !fraction.is_empty() {
+        out.push('.');
+        out.push_str(fraction);
+    }
+    out.push_str(exponent);
+    out
+}
+
+/// Rewrites a list of numbers, each optionally with a unit, separated by
+/// single spaces. Returns `None` for anything that is not such a list.
+fn compact_number_list(value: &str, precision: Option<u32>) -> Option<String> {
+    let mut out = String::with_capacity(value.len());
+    for item in value
+        .split(|c: char| c.is_ascii_whitespace() || c == ',')
+        .filter(|item| !item.is_empty())
+    {
+        let (number, unit) = split_number(item)?;
+        if !(unit.is_empty() || unit == "%" || unit.bytes().all(|b| b.is_ascii_alphabetic())) {
+            return None;
+        }
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        push_number(&mut out, number, precision);
+        out.push_str(unit);
+    }
+    (!out.is_empty()).then_some(out)
+}
+
+/// How many arguments each command of path data takes.
+fn path_arguments(command: u8) -> Option<usize> {
+    match command.to_ascii_uppercase() {
+        b'Z' => Some(0),
+        b'H' | b'V' => Some(1),
+        b'M' | b'L' | b'T' => Some(2),
+        b'S' | b'Q' => Some(4),
+        b'C' => Some(6),
+        b'A' => Some(7),
+        _ => None,
+    }
+}
+
+/// Skips the whitespace and commas between path data and numbers.
+fn skip_separators(text: &str) -> &str {
+    text.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == ',')
+}
+
+/// Rewrites path data in its shortest form: numbers shortened, separators
+/// left out where a sign or a second decimal point already ends a number,
+/// and command letters left out where the previous command repeats.
+/// Returns `None` for path data that does not parse, which is kept as
+/// written.
+fn compact_path_data(data: &str, precision: Option<u32>) -> Option<String> {
+    let mut out = String::with_capacity(data.len());
+    let mut rest = skip_separators(data);
+    // The command a bare group of arguments would stand for here
+    let mut repeated: Option<u8> = None;
+    // The last thing written is a number, and one containing a `.`
+    let mut after_number = false;
+    let mut after_fraction = false;
+
+    while let Some(&letter) = rest.as_bytes().first() {
+        let arguments = path_arguments(letter)?;
+        if repeated.is_none() && !matches!(letter, b'M' | b'm') {
+            return None;
+        }
+        if repeated != Some(letter) || matches!(letter, b'Z' | b'z') {
+            out.push(letter as char);
+            after_number = false;
+        }
+        rest = skip_separators(&rest[1..]);
+        repeated = Some(match letter {
+            b'M' => b'L',
+            b'm' => b'l',
+            letter => letter,
+        });
+
+        // A command takes any number of argument groups after its letter
+        loop {
+            for index in 0..arguments {
+                let number = if letter.eq_ignore_ascii_case(&b'a') && matches!(index, 3 | 4) {
+                    let (flag, after) = match rest.as_bytes().first() {
+                        Some(b'0' | b'1') => rest.split_at(1),
+                        _ => return None,
+                    };
+                    rest = after;
+                    flag.to_string()
+                } else {
+                    let (number, after) = split_number(rest)?;
+                    rest = after;
+                    let mut shortened = String::new();
+                    push_number(&mut shortened, number, precision);
+                    shortened
+                };
+                let joins = number.starts_with('-') || (number.starts_with('.') && after_fraction);
+                if after_number && !joins {
+                    out.push(' ');
+                }
+                out.push_str(&number);
+                after_number = true;
+                after_fraction = number.contains('.') && !number.contains(['e', 'E']);
+                rest = skip_separators(rest);
+            }
+            if arguments == 0 || split_number(rest).is_none() {
+                break;
+            }
+        }
+    }
+    Some(out)
+}
+
+// =============================================================================
+// SVG Attributes
+// =============================================================================
+
+/// The value an SVG attribute is written with, or `None` when it is
+/// dropped, under [`MinifyOptions::minify_svg`].
+pub(crate) fn optimize_svg_attribute<'v>(
+    tag: &str,
+    name: &str,
+    value: &'v str,
+    options: &MinifyOptions,
+) -> Option<Cow<'v, str>> {
+    if !options.minify_svg {
+        return Some(Cow::Borrowed(value));
+    }
+    if is_removable_attribute(tag, name, value) {
+        return None;
+    }
+
+    let precision = options.svg_precision;
+    let is_list = contains_ignore_ascii_case(NUMBER_LIST_ATTRIBUTES, name);
+    let optimized = if name.eq_ignore_ascii_case("d") {
+        compact_path_data(value, precision)
+    } else if is_list || contains_ignore_ascii_case(NUMBER_ATTRIBUTES, name) {
+        compact_number_list(value, precision).filter(|list| is_list || !list.contains(' '))
+    } else {
+        None
+    };
+    Some(optimized.map_or(Cow::Borrowed(value), Cow::Owned))
+}
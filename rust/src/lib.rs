@@ -1,5 +1,31 @@
-use std::ffi::{CStr, CString};
-use std::os::raw::c_char;
+use memchr::{memchr, memmem};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use foreign::{ForeignContent, Namespace, SkippedElement};
+use source_map::{PositionCursor, SourceOffsets};
+
+mod batch;
+mod beautify;
+mod config;
+mod ffi;
+mod foreign;
+mod lint;
+mod source_map;
+mod stream;
+#[cfg(test)]
+mod tests;
+mod verify;
+
+pub use batch::{minify_batch, MinifyError};
+pub use beautify::{beautify_html, beautify_html_with_options, BeautifyOptions};
+pub use config::{ConfigError, MinifyConfig};
+pub use ffi::*;
+pub use lint::{lint, Diagnostic, DiagnosticCode, Severity};
+pub use source_map::{Mapping, Position, SourceMap};
+pub use stream::{minify_stream, Minifier, StreamOutput};
+pub use verify::{verify_minified, Difference, Mismatch, SecondPass, Verification};
 
 // =============================================================================
 // HTML Element and Attribute Constants
@@ -10,37 +36,110 @@ const SINGLETON_ELEMENTS: &[&str] = &[
     "track", "wbr",
 ];
 
+/// Elements whose content is text up to their end tag, never markup.
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style", "textarea", "title"];
+
 const CLOSE_OPTIONAL_ELEMENTS: &[&str] = &[
     "p", "dt", "dd", "li", "option", "thead", "th", "tbody", "tr", "td", "tfoot", "colgroup",
 ];
 
-const BOOLEAN_ATTRIBUTES: &[&str] = &[
-    "allowfullscreen",
-    "async",
-    "autofocus",
-    "autoplay",
-    "checked",
-    "controls",
-    "default",
-    "defer",
-    "disabled",
-    "formnovalidate",
-    "hidden",
-    "inert",
-    "ismap",
-    "itemscope",
-    "loop",
-    "multiple",
-    "muted",
-    "nomodule",
-    "novalidate",
-    "open",
-    "playsinline",
-    "readonly",
-    "required",
-    "reversed",
-    "selected",
-    "typemustmatch",
+/// Elements that start on a line of their own. Whitespace around them does
+/// not render, so the minifier drops it and the beautifier moves them to
+/// new lines.
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "base",
+    "blockquote",
+    "body",
+    "caption",
+    "colgroup",
+    "dd",
+    "details",
+    "dialog",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "head",
+    "header",
+    "hgroup",
+    "hr",
+    "html",
+    "li",
+    "link",
+    "main",
+    "menu",
+    "meta",
+    "nav",
+    "noscript",
+    "ol",
+    "optgroup",
+    "option",
+    "p",
+    "pre",
+    "script",
+    "section",
+    "style",
+    "summary",
+    "table",
+    "tbody",
+    "td",
+    "template",
+    "tfoot",
+    "th",
+    "thead",
+    "title",
+    "tr",
+    "ul",
+];
+
+/// Boolean attributes paired with the elements they apply to. An empty
+/// element list marks a global attribute.
+const BOOLEAN_ATTRIBUTES: &[(&str, &[&str])] = &[
+    ("allowfullscreen", &["iframe"]),
+    ("async", &["script"]),
+    ("autofocus", &[]),
+    ("autoplay", &["audio", "video"]),
+    ("checked", &["input"]),
+    ("controls", &["audio", "video"]),
+    ("default", &["track"]),
+    ("defer", &["script"]),
+    (
+        "disabled",
+        &[
+            "button", "fieldset", "input", "link", "optgroup", "option", "select", "textarea",
+        ],
+    ),
+    ("disablepictureinpicture", &["video"]),
+    ("disableremoteplayback", &["audio", "video"]),
+    ("formnovalidate", &["button", "input"]),
+    ("inert", &[]),
+    ("ismap", &["img"]),
+    ("itemscope", &[]),
+    ("loop", &["audio", "video"]),
+    ("multiple", &["input", "select"]),
+    ("muted", &["audio", "video"]),
+    ("nomodule", &["script"]),
+    ("novalidate", &["form"]),
+    ("open", &["details", "dialog"]),
+    ("playsinline", &["video"]),
+    ("readonly", &["input", "textarea"]),
+    ("required", &["input", "select", "textarea"]),
+    ("reversed", &["ol"]),
+    ("selected", &["option"]),
+    ("typemustmatch", &["object"]),
 ];
 
 const EMPTY_REMOVABLE_ATTRIBUTES: &[&str] = &[
@@ -67,54 +166,316 @@ const EMPTY_REMOVABLE_ATTRIBUTES: &[&str] = &[
     "target",
 ];
 
+/// Knowledge about an enumerated attribute: the keywords it accepts, the
+/// keyword the empty string maps to, and the state used when it is missing.
+struct EnumeratedAttribute {
+    /// Elements the attribute applies to; empty means any element.
+    tags: &'static [&'static str],
+    name: &'static str,
+    keywords: &'static [&'static str],
+    empty_keyword: Option<&'static str>,
+    missing_default: Option<&'static str>,
+}
+
+const ENUMERATED_ATTRIBUTES: &[EnumeratedAttribute] = &[
+    EnumeratedAttribute {
+        tags: &[],
+        name: "hidden",
+        keywords: &["hidden", "until-found"],
+        empty_keyword: Some("hidden"),
+        missing_default: None,
+    },
+    EnumeratedAttribute {
+        tags: &["form"],
+        name: "method",
+        keywords: &["get", "post", "dialog"],
+        empty_keyword: None,
+        missing_default: Some("get"),
+    },
+    EnumeratedAttribute {
+        tags: &["button", "input"],
+        name: "formmethod",
+        keywords: &["get", "post", "dialog"],
+        empty_keyword: None,
+        missing_default: None,
+    },
+    EnumeratedAttribute {
+        tags: &["form"],
+        name: "autocomplete",
+        keywords: &["on", "off"],
+        empty_keyword: None,
+        missing_default: Some("on"),
+    },
+    EnumeratedAttribute {
+        tags: &["input", "select", "textarea"],
+        name: "autocomplete",
+        keywords: &["on", "off"],
+        empty_keyword: None,
+        missing_default: None,
+    },
+    EnumeratedAttribute {
+        tags: &[],
+        name: "dir",
+        keywords: &["ltr", "rtl", "auto"],
+        empty_keyword: None,
+        missing_default: None,
+    },
+    EnumeratedAttribute {
+        tags: &["audio", "img", "link", "script", "video"],
+        name: "crossorigin",
+        keywords: &["anonymous", "use-credentials"],
+        empty_keyword: Some("anonymous"),
+        missing_default: None,
+    },
+    EnumeratedAttribute {
+        tags: &[],
+        name: "contenteditable",
+        keywords: &["true", "false", "plaintext-only"],
+        empty_keyword: Some("true"),
+        missing_default: None,
+    },
+    EnumeratedAttribute {
+        tags: &[],
+        name: "draggable",
+        keywords: &["true", "false", "auto"],
+        empty_keyword: None,
+        missing_default: Some("auto"),
+    },
+    EnumeratedAttribute {
+        tags: &[],
+        name: "spellcheck",
+        keywords: &["true", "false"],
+        empty_keyword: Some("true"),
+        missing_default: None,
+    },
+    EnumeratedAttribute {
+        tags: &[],
+        name: "translate",
+        keywords: &["yes", "no"],
+        empty_keyword: Some("yes"),
+        missing_default: None,
+    },
+    EnumeratedAttribute {
+        tags: &["iframe", "img"],
+        name: "loading",
+        keywords: &["eager", "lazy"],
+        empty_keyword: None,
+        missing_default: Some("eager"),
+    },
+    EnumeratedAttribute {
+        tags: &["img"],
+        name: "decoding",
+        keywords: &["sync", "async", "auto"],
+        empty_keyword: None,
+        missing_default: Some("auto"),
+    },
+    EnumeratedAttribute {
+        tags: &["img", "iframe", "link", "script"],
+        name: "fetchpriority",
+        keywords: &["high", "low", "auto"],
+        empty_keyword: None,
+        missing_default: Some("auto"),
+    },
+    EnumeratedAttribute {
+        tags: &["area"],
+        name: "shape",
+        keywords: &["rect", "circle", "poly", "default"],
+        empty_keyword: None,
+        missing_default: Some("rect"),
+    },
+    EnumeratedAttribute {
+        tags: &["textarea"],
+        name: "wrap",
+        keywords: &["soft", "hard"],
+        empty_keyword: None,
+        missing_default: Some("soft"),
+    },
+    EnumeratedAttribute {
+        tags: &["track"],
+        name: "kind",
+        keywords: &[
+            "subtitles",
+            "captions",
+            "descriptions",
+            "chapters",
+            "metadata",
+        ],
+        empty_keyword: None,
+        missing_default: Some("subtitles"),
+    },
+    EnumeratedAttribute {
+        tags: &["th"],
+        name: "scope",
+        keywords: &["row", "col", "rowgroup", "colgroup", "auto"],
+        empty_keyword: None,
+        missing_default: Some("auto"),
+    },
+];
+
 // =============================================================================
 // Token Types
 // =============================================================================
 
-#[derive(Debug, Clone, PartialEq)]
-enum Token<'a> {
+/// One lexical unit of HTML, borrowing from the input. A start tag arrives
+/// as [`Token::TagOpenStart`], its attributes, then [`Token::TagOpenEnd`] or
+/// [`Token::TagSelfClose`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Token<'a> {
+    /// Text between tags, or the whole content of an HTML `<script>`,
+    /// `<style>`, `<textarea>` or `<title>` element.
     TextNode(&'a str),
+    /// The name of a start tag, as written.
     TagOpenStart(&'a str),
-    Attribute(&'a str),
+    Attribute(Attribute<'a>),
+    /// The `>` ending a start tag.
     TagOpenEnd,
+    /// The `/>` ending a start tag.
     TagSelfClose,
+    /// Everything between `</` and `>` of an end tag, which may include
+    /// whitespace after the name.
     TagClose(&'a str),
+    /// The text between `<!--` and `-->`, or the whole of any other `<!...>`
+    /// markup that is not a doctype or CDATA section.
     Comment(&'a str),
+    /// The whole `<!DOCTYPE ...>`.
     Doctype(&'a str),
+    /// The content of a `<![CDATA[...]]>` section.
     Cdata(&'a str),
 }
 
+/// An attribute of a start tag, split into its parts by the tokenizer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attribute<'a> {
+    /// The name as written.
+    pub name: &'a str,
+    /// The value without its quotes, or `None` when there is no `=`. A
+    /// quoted value left open at the end of the input keeps its quote.
+    pub value: Option<&'a str>,
+    /// The quotes around the value, when it has closed quotes.
+    pub quote: Option<AttributeQuote>,
+}
+
+/// The quote character around an attribute value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeQuote {
+    Double,
+    Single,
+}
+
+/// Where a token lies in the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    /// Byte offset of the first byte of the token.
+    pub start: usize,
+    /// Byte offset just past the token.
+    pub end: usize,
+    /// Line and column of `start`.
+    pub position: Position,
+}
+
 // =============================================================================
 // HTML Element Utilities
 // =============================================================================
 
+/// Looks `name` up in one of the lowercase tables above, ignoring ASCII
+/// case so callers never need a lowercased copy.
+fn contains_ignore_ascii_case(table: &[&str], name: &str) -> bool {
+    table.iter().any(|entry| entry.eq_ignore_ascii_case(name))
+}
+
+/// Lowercases ASCII letters, borrowing `name` when it has none.
+fn ascii_lowercase(name: &str) -> Cow<'_, str> {
+    if name.bytes().any(|b| b.is_ascii_uppercase()) {
+        Cow::Owned(name.to_ascii_lowercase())
+    } else {
+        Cow::Borrowed(name)
+    }
+}
+
 fn is_singleton_element(tag: &str) -> bool {
-    SINGLETON_ELEMENTS.contains(&tag)
+    contains_ignore_ascii_case(SINGLETON_ELEMENTS, tag)
 }
 
 fn is_close_optional(tag: &str) -> bool {
-    CLOSE_OPTIONAL_ELEMENTS.contains(&tag)
+    contains_ignore_ascii_case(CLOSE_OPTIONAL_ELEMENTS, tag)
+}
+
+/// Whether a tag read in `namespace` sits within a line of text, so that
+/// whitespace next to it renders. Foreign elements other than those that
+/// lay out SVG text render no whitespace between them.
+fn is_inline_tag(name: &str, namespace: Namespace) -> bool {
+    match namespace {
+        Namespace::Html => !contains_ignore_ascii_case(BLOCK_ELEMENTS, name),
+        Namespace::Svg => contains_ignore_ascii_case(&["a", "textPath", "tspan"], name),
+        Namespace::MathMl => false,
+    }
+}
+
+fn is_boolean_attribute(tag: &str, attr: &str) -> bool {
+    BOOLEAN_ATTRIBUTES
+        .iter()
+        .any(|(name, tags)| *name == attr && (tags.is_empty() || tags.contains(&tag)))
 }
 
-fn is_boolean_attribute(attr: &str) -> bool {
-    BOOLEAN_ATTRIBUTES.contains(&attr)
+fn is_custom_element(tag: &str) -> bool {
+    tag.contains('-')
 }
 
 fn is_empty_removable(attr: &str) -> bool {
-    EMPTY_REMOVABLE_ATTRIBUTES.contains(&attr)
+    contains_ignore_ascii_case(EMPTY_REMOVABLE_ATTRIBUTES, attr)
+}
+
+/// Whether an empty value of the attribute means the same as leaving it
+/// out. An empty `alt` marks a decorative image and an empty `value` is a
+/// value of its own, so both are kept.
+fn is_removable_when_empty(attr: &str) -> bool {
+    is_empty_removable(attr) || attr == "type"
 }
 
 fn has_default_value(tag: &str, attr: &str, value: &str) -> bool {
-    match (tag, attr, value) {
-        ("script", "type", "text/javascript") => true,
-        ("style", "type", "text/css") => true,
-        ("style", "media", "all") => true,
-        ("form", "method", "get") => true,
-        ("form", "autocomplete", "on") => true,
-        ("form", "enctype", "application/x-www-form-urlencoded") => true,
-        ("input", "type", "text") => true,
-        ("button", "type", "submit") => true,
-        _ => false,
+    let default = match (tag, attr) {
+        ("script", "type") => "text/javascript",
+        ("style", "type") => "text/css",
+        ("style", "media") => "all",
+        ("form", "enctype") => "application/x-www-form-urlencoded",
+        ("input", "type") => "text",
+        ("button", "type") => "submit",
+        _ => return false,
+    };
+    value.eq_ignore_ascii_case(default)
+}
+
+/// The shortest equivalent form of an enumerated attribute value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EnumeratedValue {
+    /// The value equals the missing-value default, so the attribute can go.
+    Default,
+    /// The value is equivalent to the empty string, so a bare name suffices.
+    Empty,
+    /// The value is a known keyword, emitted in canonical lowercase.
+    Keyword(&'static str),
+}
+
+fn normalize_enumerated_value(tag: &str, attr: &str, value: &str) -> Option<EnumeratedValue> {
+    let spec = ENUMERATED_ATTRIBUTES
+        .iter()
+        .find(|spec| spec.name == attr && (spec.tags.is_empty() || spec.tags.contains(&tag)))?;
+
+    let state = if value.is_empty() {
+        spec.empty_keyword?
+    } else {
+        spec.keywords
+            .iter()
+            .copied()
+            .find(|keyword| keyword.eq_ignore_ascii_case(value))?
+    };
+
+    if spec.missing_default == Some(state) {
+        Some(EnumeratedValue::Default)
+    } else if spec.empty_keyword == Some(state) {
+        Some(EnumeratedValue::Empty)
+    } else {
+        Some(EnumeratedValue::Keyword(state))
     }
 }
 
@@ -157,49 +518,139 @@ fn should_remove_quotes(value: &str) -> bool {
 // Tokenizer
 // =============================================================================
 
-struct Tokenizer<'a> {
+/// Bytes past its end that the tokenizer may inspect before deciding how a
+/// token ends, as when checking for `</textarea` in raw text.
+const LOOKAHEAD_BYTES: usize = 16;
+
+/// Tokenizer state that has to carry over when input arrives in pieces.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct TokenizerState {
+    in_tag: bool,
+    /// The raw-text element whose content starts once the current tag closes.
+    raw_text_element: Option<&'static str>,
+    /// The next token is the content of `raw_text_element`.
+    in_raw_text: bool,
+}
+
+/// The lexer behind the minifier, iterating over the tokens of a document
+/// together with their spans. It never fails: malformed markup comes out as
+/// the tokens a browser would most likely see. Inside `<svg>` and `<math>`,
+/// a `<style>` or `<title>` holds markup rather than raw text.
+///
+/// ```
+/// use html_minifier_ffi::{Token, Tokenizer};
+///
+/// let html = "<p class=intro>\n  Hello</p>";
+/// for (token, span) in Tokenizer::new(html) {
+///     if let Token::Attribute(attribute) = token {
+///         assert_eq!((attribute.name, attribute.value), ("class", Some("intro")));
+///         assert_eq!(&html[span.start..span.end], "class=intro");
+///     }
+/// }
+/// ```
+pub struct Tokenizer<'a> {
     input: &'a str,
     position: usize,
     end: usize,
     bytes: &'a [u8],
     in_tag: bool,
+    raw_text_element: Option<&'static str>,
+    in_raw_text: bool,
+    /// Where the last token returned by `next_token` starts, past any
+    /// whitespace scanned before it.
+    token_start: usize,
+    /// How much of the input an earlier attempt scanned without finding the
+    /// end of its first token, when input arrives in pieces.
+    scanned: usize,
+    /// Built on first use by the iterator, which alone reports positions.
+    positions: Option<PositionCursor<'a>>,
+    /// Foreign content as the iterator follows it, with the name of the
+    /// last start tag. Callers of `next_token` follow it themselves.
+    foreign: ForeignContent,
+    tag: &'a str,
 }
 
 impl<'a> Tokenizer<'a> {
-    fn new(input: &'a str) -> Self {
+    pub fn new(input: &'a str) -> Self {
+        Self::resume(input, TokenizerState::default())
+    }
+
+    fn resume(input: &'a str, state: TokenizerState) -> Self {
         Self {
             input,
             position: 0,
             end: input.len(),
             bytes: input.as_bytes(),
-            in_tag: false,
+            in_tag: state.in_tag,
+            raw_text_element: state.raw_text_element,
+            in_raw_text: state.in_raw_text,
+            token_start: 0,
+            scanned: 0,
+            positions: None,
+            foreign: ForeignContent::default(),
+            tag: "",
         }
     }
 
-    fn skip_whitespace(&mut self) {
-        while self.position < self.end {
-            match self.bytes[self.position] {
-                b' ' | b'\t' | b'\n' | b'\r' => self.position += 1,
-                _ => break,
-            }
+    fn state(&self) -> TokenizerState {
+        TokenizerState {
+            in_tag: self.in_tag,
+            raw_text_element: self.raw_text_element,
+            in_raw_text: self.in_raw_text,
         }
     }
 
+    /// Notes that the start tag just read is an SVG or MathML element, whose
+    /// content is markup even where the HTML element of its name, such as
+    /// `<style>` or `<title>`, holds raw text.
+    fn foreign_start_tag(&mut self) {
+        self.raw_text_element = None;
+    }
+
+    fn restore(&mut self, state: TokenizerState, position: usize) {
+        self.in_tag = state.in_tag;
+        self.raw_text_element = state.raw_text_element;
+        self.in_raw_text = state.in_raw_text;
+        self.position = position;
+    }
+
+    /// The unscanned rest of the input.
+    fn remaining(&self) -> &'a [u8] {
+        &self.bytes[self.position..self.end]
+    }
+
+    /// Where the search for the end of a token may start. Input that an
+    /// earlier attempt scanned, short of what it could not look ahead at,
+    /// holds no end, so streamed input is not searched over and over.
+    fn search_start(&self) -> usize {
+        self.position
+            .max(self.scanned.saturating_sub(LOOKAHEAD_BYTES))
+    }
+
+    // Whitespace runs inside tags are a byte or two long, so a plain loop
+    // beats setting up a vectorized search for the first non-space byte.
+    fn skip_whitespace(&mut self) {
+        self.position += self
+            .remaining()
+            .iter()
+            .position(|&b| !matches!(b, b' ' | b'\t' | b'\n' | b'\r'))
+            .unwrap_or(self.end - self.position);
+    }
+
     fn consume_until_bytes(&mut self, delimiter: &[u8]) -> &'a str {
         let start = self.position;
+        let from = self.search_start();
 
-        while self.position < self.end {
-            if self.position + delimiter.len() <= self.end
-                && &self.bytes[self.position..self.position + delimiter.len()] == delimiter
-            {
-                let result = &self.input[start..self.position];
-                self.position += delimiter.len();
-                return result;
+        match memmem::find(&self.bytes[from..self.end], delimiter) {
+            Some(offset) => {
+                self.position = from + offset + delimiter.len();
+                &self.input[start..from + offset]
+            }
+            None => {
+                self.position = self.end;
+                &self.input[start..self.end]
             }
-            self.position += 1;
         }
-
-        &self.input[start..self.end]
     }
 
     fn consume_tag_name(&mut self) -> &'a str {
@@ -217,28 +668,25 @@ impl<'a> Tokenizer<'a> {
 
     fn consume_until_byte(&mut self, byte: u8) -> &'a str {
         let start = self.position;
-
-        while self.position < self.end && self.bytes[self.position] != byte {
-            self.position += 1;
-        }
-
+        let from = self.search_start();
+        self.position = memchr(byte, &self.bytes[from..self.end]).map_or(self.end, |at| from + at);
         &self.input[start..self.position]
     }
 
-    fn consume_attribute(&mut self) -> Option<&'a str> {
+    fn consume_attribute(&mut self) -> Option<Attribute<'a>> {
         self.skip_whitespace();
         if self.position >= self.end || self.bytes[self.position] == b'>' {
             return None;
         }
 
         let start = self.position;
-        let mut has_equals = false;
+        let mut name_end = None;
 
         // Find the attribute name
         while self.position < self.end {
             match self.bytes[self.position] {
                 b'=' => {
-                    has_equals = true;
+                    name_end = Some(self.position);
                     self.position += 1;
                     break;
                 }
@@ -247,53 +695,90 @@ impl<'a> Tokenizer<'a> {
             }
         }
 
-        // If we found '=', consume the value
-        if has_equals {
+        // Whitespace may separate the name from `=`. Whitespace running to
+        // the end of the input stays consumed, so a stream holds the
+        // attribute back until it sees what follows.
+        let after_name = self.position;
+        if name_end.is_none() {
             self.skip_whitespace();
-
-            // Check if value is quoted
-            if self.position < self.end && matches!(self.bytes[self.position], b'"' | b'\'') {
-                let quote_char = self.bytes[self.position];
+            if self.position < self.end && self.bytes[self.position] == b'=' {
+                name_end = Some(after_name);
                 self.position += 1;
-
-                // Consume until closing quote
-                while self.position < self.end {
-                    if self.bytes[self.position] == quote_char {
-                        self.position += 1;
-                        break;
-                    }
-                    self.position += 1;
-                }
-            } else {
-                // Unquoted value - consume until whitespace or >
-                while self.position < self.end {
-                    match self.bytes[self.position] {
-                        b' ' | b'\t' | b'\n' | b'\r' | b'>' => break,
-                        _ => self.position += 1,
-                    }
-                }
+            } else if self.position < self.end {
+                self.position = after_name;
             }
         }
 
-        if self.position > start {
-            Some(&self.input[start..self.position])
+        let Some(name_end) = name_end else {
+            return Some(Attribute {
+                name: &self.input[start..after_name],
+                value: None,
+                quote: None,
+            });
+        };
+        let name = &self.input[start..name_end];
+
+        // If we found '=', consume the value
+        self.skip_whitespace();
+        let value_start = self.position;
+
+        // Check if value is quoted
+        if self.position < self.end && matches!(self.bytes[self.position], b'"' | b'\'') {
+            let quote_char = self.bytes[self.position];
+            self.position += 1;
+
+            // Consume through the closing quote
+            let value = self.consume_until_byte(quote_char);
+            if self.position < self.end {
+                self.position += 1;
+                let quote = match quote_char {
+                    b'"' => AttributeQuote::Double,
+                    _ => AttributeQuote::Single,
+                };
+                return Some(Attribute {
+                    name,
+                    value: Some(value),
+                    quote: Some(quote),
+                });
+            }
         } else {
-            None
+            // Unquoted value - consume until whitespace or >
+            while self.position < self.end {
+                match self.bytes[self.position] {
+                    b' ' | b'\t' | b'\n' | b'\r' | b'>' => break,
+                    _ => self.position += 1,
+                }
+            }
         }
+
+        Some(Attribute {
+            name,
+            value: Some(&self.input[value_start..self.position]),
+            quote: None,
+        })
     }
 
     fn next_token(&mut self) -> Option<Token<'a>> {
-        self.skip_whitespace();
-
-        if self.position >= self.end {
-            return None;
+        if self.in_raw_text {
+            self.in_raw_text = false;
+            self.token_start = self.position;
+            if let Some(content) = self.consume_raw_text() {
+                return Some(Token::TextNode(content));
+            }
         }
 
         // Handle attributes if we're inside a tag
         if self.in_tag {
-            if self.position < self.end && self.bytes[self.position] == b'>' {
+            self.skip_whitespace();
+            self.token_start = self.position;
+            if self.position >= self.end {
+                return None;
+            }
+
+            if self.bytes[self.position] == b'>' {
                 self.position += 1;
                 self.in_tag = false;
+                self.in_raw_text = self.raw_text_element.is_some();
                 return Some(Token::TagOpenEnd);
             }
 
@@ -303,6 +788,7 @@ impl<'a> Tokenizer<'a> {
             {
                 self.position += 2;
                 self.in_tag = false;
+                self.in_raw_text = self.raw_text_element.is_some();
                 return Some(Token::TagSelfClose);
             }
 
@@ -314,6 +800,12 @@ impl<'a> Tokenizer<'a> {
             self.in_tag = false;
         }
 
+        // Whitespace between tags is left in text nodes for the emitter
+        self.token_start = self.position;
+        if self.position >= self.end {
+            return None;
+        }
+
         match self.bytes[self.position] {
             b'<' => self.parse_tag(),
             _ => self.parse_text_node(),
@@ -342,7 +834,7 @@ impl<'a> Tokenizer<'a> {
             let content = self.consume_until_bytes(b"-->");
             Some(Token::Comment(content))
         } else if self.position + 7 < self.end
-            && &self.bytes[self.position..self.position + 7] == b"DOCTYPE"
+            && self.bytes[self.position..self.position + 7].eq_ignore_ascii_case(b"DOCTYPE")
         {
             // Doctype
             let start = self.position - 2;
@@ -381,14 +873,39 @@ impl<'a> Tokenizer<'a> {
     fn parse_open_tag(&mut self) -> Option<Token<'a>> {
         let tag_name = self.consume_tag_name();
         self.in_tag = true;
+        self.raw_text_element = RAW_TEXT_ELEMENTS
+            .iter()
+            .copied()
+            .find(|element| element.eq_ignore_ascii_case(tag_name));
         Some(Token::TagOpenStart(tag_name))
     }
 
+    /// Consumes the content of a raw-text element up to its end tag, which
+    /// is left for the next token. Returns `None` for empty content.
+    fn consume_raw_text(&mut self) -> Option<&'a str> {
+        let element = self.raw_text_element.take()?.as_bytes();
+        let start = self.position;
+
+        let is_end_tag = |rest: &[u8]| {
+            rest.len() > element.len() + 2
+                && rest[2..2 + element.len()].eq_ignore_ascii_case(element)
+                && matches!(
+                    rest[2 + element.len()],
+                    b'>' | b'/' | b' ' | b'\t' | b'\n' | b'\r' | b'\x0c'
+                )
+        };
+        let from = self.search_start();
+        self.position = memmem::find_iter(&self.bytes[from..self.end], b"</")
+            .map(|offset| from + offset)
+            .find(|&at| is_end_tag(&self.bytes[at..self.end]))
+            .unwrap_or(self.end);
+
+        (self.position > start).then(|| &self.input[start..self.position])
+    }
+
     fn parse_text_node(&mut self) -> Option<Token<'a>> {
         let start = self.position;
-        while self.position < self.end && self.bytes[self.position] != b'<' {
-            self.position += 1;
-        }
+        self.consume_until_byte(b'<');
 
         if self.position > start {
             Some(Token::TextNode(&self.input[start..self.position]))
@@ -398,170 +915,288 @@ impl<'a> Tokenizer<'a> {
     }
 }
 
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = (Token<'a>, Span);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let token = self.next_token()?;
+        match token {
+            Token::TagOpenStart(name) => {
+                if self.foreign.start_tag_namespace(name) != Namespace::Html {
+                    self.foreign_start_tag();
+                }
+                self.foreign.start_tag(name);
+                self.tag = name;
+            }
+            Token::Attribute(attr) => {
+                self.foreign.attribute(attr.name);
+            }
+            Token::TagOpenEnd => self.foreign.end_start_tag(self.tag, false),
+            Token::TagSelfClose => self.foreign.end_start_tag(self.tag, true),
+            Token::TagClose(tag) => self.foreign.end_tag(tag),
+            _ => {}
+        }
+        let start = self.token_start;
+        let positions = self
+            .positions
+            .get_or_insert_with(|| PositionCursor::new(self.input));
+        let span = Span {
+            start,
+            end: self.position,
+            position: positions.position_of(start),
+        };
+        Some((token, span))
+    }
+}
+
 // =============================================================================
 // CSS and JavaScript Minifiers
 // =============================================================================
 
 pub fn minify_javascript(js: &str) -> String {
     let mut result = String::with_capacity(js.len());
-    let mut chars = js.chars().peekable();
+    minify_javascript_into(js, 0, &mut result, None);
+    result
+}
 
-    while let Some(ch) = chars.next() {
+/// Appends minified `js` to `result`. When `offsets` is given, records the
+/// start of each token, counting input offsets from `source_start`.
+fn minify_javascript_into(
+    js: &str,
+    source_start: usize,
+    result: &mut String,
+    offsets: Option<&mut SourceOffsets>,
+) {
+    let start = result.len();
+    let mut marks = TokenMarks::new(offsets, source_start);
+    let mut chars = js.char_indices().peekable();
+
+    while let Some((index, ch)) = chars.next() {
         match ch {
             // Handle string literals - preserve them completely
             '"' | '\'' | '`' => {
-                result.push(ch);
+                marks.gap();
+                marks.push(result, index, ch);
                 let quote = ch;
 
-                while let Some(inner_ch) = chars.next() {
+                while let Some((_, inner_ch)) = chars.next() {
                     result.push(inner_ch);
                     if inner_ch == quote {
                         break;
                     }
                     // Handle escaped characters
                     if inner_ch == '\\' {
-                        if let Some(escaped) = chars.next() {
+                        if let Some((_, escaped)) = chars.next() {
                             result.push(escaped);
                         }
                     }
                 }
+                marks.gap();
             }
             // Handle comments
             '/' => {
-                if let Some(&'/') = chars.peek() {
+                if let Some(&(_, '/')) = chars.peek() {
                     // Line comment - skip to end of line
                     chars.next(); // consume second '/'
-                    while let Some(c) = chars.next() {
+                    for (_, c) in chars.by_ref() {
                         if c == '\n' {
                             break;
                         }
                     }
-                } else if let Some(&'*') = chars.peek() {
+                    marks.gap();
+                } else if let Some(&(_, '*')) = chars.peek() {
                     // Block comment - skip to */
                     chars.next(); // consume '*'
                     let mut prev = ' ';
-                    while let Some(c) = chars.next() {
+                    for (_, c) in chars.by_ref() {
                         if prev == '*' && c == '/' {
                             break;
                         }
                         prev = c;
                     }
+                    marks.gap();
                 } else {
-                    result.push(ch);
+                    marks.push(result, index, ch);
                 }
             }
             // Handle whitespace conservatively
             c if c.is_whitespace() => {
-                if !result.is_empty() && !result.ends_with(' ') {
+                if result.len() > start && !result.ends_with(' ') {
                     // Check if we need a space for separation
-                    if let Some(&next_ch) = chars.peek() {
+                    if let Some(&(_, next_ch)) = chars.peek() {
                         let last_ch = result.chars().last().unwrap_or(' ');
                         if (last_ch.is_alphanumeric() || last_ch == '_')
                             && (next_ch.is_alphanumeric() || next_ch == '_')
                         {
-                            result.push(' ');
+                            marks.push(result, index, ' ');
                         }
                     }
                 }
                 // Skip consecutive whitespace
-                while let Some(&next_ch) = chars.peek() {
+                while let Some(&(_, next_ch)) = chars.peek() {
                     if next_ch.is_whitespace() {
                         chars.next();
                     } else {
                         break;
                     }
                 }
+                marks.gap();
             }
-            _ => result.push(ch),
+            _ => marks.push(result, index, ch),
         }
     }
 
-    result.trim().to_string()
+    trim_end_from(result, start);
 }
 
 pub fn minify_css(css: &str) -> String {
     let mut result = String::with_capacity(css.len());
-    let mut chars = css.chars().peekable();
+    minify_css_into(css, 0, &mut result, None);
+    result
+}
 
-    while let Some(ch) = chars.next() {
+/// Appends minified `css` to `result`, recording token starts like
+/// [`minify_javascript_into`].
+fn minify_css_into(
+    css: &str,
+    source_start: usize,
+    result: &mut String,
+    offsets: Option<&mut SourceOffsets>,
+) {
+    let start = result.len();
+    let mut marks = TokenMarks::new(offsets, source_start);
+    let mut chars = css.char_indices().peekable();
+
+    while let Some((index, ch)) = chars.next() {
         match ch {
-            '/' if chars.peek() == Some(&'*') => {
+            // Copy strings verbatim, escapes included
+            '"' | '\'' => {
+                marks.gap();
+                marks.push(result, index, ch);
+                while let Some((_, inner_ch)) = chars.next() {
+                    result.push(inner_ch);
+                    if inner_ch == ch {
+                        break;
+                    }
+                    if inner_ch == '\\' {
+                        if let Some((_, escaped)) = chars.next() {
+                            result.push(escaped);
+                        }
+                    }
+                }
+                marks.gap();
+            }
+            '/' if matches!(chars.peek(), Some((_, '*'))) => {
                 // Skip CSS comments
                 chars.next(); // consume '*'
                 let mut prev = ' ';
-                while let Some(c) = chars.next() {
+                for (_, c) in chars.by_ref() {
                     if prev == '*' && c == '/' {
                         break;
                     }
                     prev = c;
                 }
+                marks.gap();
             }
             c if c.is_whitespace() => {
                 // Skip unnecessary whitespace
-                if !result.is_empty() {
+                if result.len() > start {
                     let last_ch = result.chars().last().unwrap_or(' ');
-                    if !matches!(last_ch, '{' | '}' | ':' | ';' | ',' | '>' | '+' | '~') {
-                        if !result.ends_with(' ') {
-                            result.push(' ');
-                        }
+                    if !matches!(last_ch, '{' | '}' | ':' | ';' | ',' | '>' | '+' | '~')
+                        && !result.ends_with(' ')
+                    {
+                        marks.push(result, index, ' ');
                     }
                 }
                 // Skip consecutive whitespace
-                while let Some(&next_ch) = chars.peek() {
+                while let Some(&(_, next_ch)) = chars.peek() {
                     if next_ch.is_whitespace() {
                         chars.next();
                     } else {
                         break;
                     }
                 }
+                marks.gap();
             }
-            _ => result.push(ch),
+            _ => marks.push(result, index, ch),
         }
     }
 
-    result.trim().to_string()
+    trim_end_from(result, start);
 }
 
-// =============================================================================
-// HTML Processing Utilities
-// =============================================================================
+/// Drops trailing whitespace from the part of `result` after `start`.
+fn trim_end_from(result: &mut String, start: usize) {
+    let trimmed = result[start..].trim_end().len();
+    result.truncate(start + trimmed);
+}
 
-fn append_collapsed_whitespace(result: &mut String, content: &str) {
-    let mut prev_was_space = false;
-    for ch in content.chars() {
-        if ch.is_whitespace() {
-            if !prev_was_space {
-                result.push(' ');
-                prev_was_space = true;
+/// Records where the tokens of minified CSS or JavaScript came from: a
+/// mapping starts wherever output resumes after skipped input and wherever
+/// it switches between word and punctuation characters.
+struct TokenMarks<'m> {
+    offsets: Option<&'m mut SourceOffsets>,
+    source_start: usize,
+    /// Whether the last character pushed was part of a word, or `None`
+    /// after a gap.
+    last_was_word: Option<bool>,
+}
+
+impl<'m> TokenMarks<'m> {
+    fn new(offsets: Option<&'m mut SourceOffsets>, source_start: usize) -> Self {
+        Self {
+            offsets,
+            source_start,
+            last_was_word: None,
+        }
+    }
+
+    /// Pushes `ch`, which came from byte `index` of the input.
+    fn push(&mut self, result: &mut String, index: usize, ch: char) {
+        if let Some(offsets) = self.offsets.as_deref_mut() {
+            let is_word = ch.is_alphanumeric() || matches!(ch, '_' | '$' | '-' | '#');
+            if self.last_was_word != Some(is_word) {
+                offsets.add(result.len(), self.source_start + index);
             }
-        } else {
-            result.push(ch);
-            prev_was_space = false;
+            self.last_was_word = Some(is_word);
         }
+        result.push(ch);
+    }
+
+    fn gap(&mut self) {
+        self.last_was_word = None;
     }
 }
 
-fn process_style_attribute(value: &str) -> String {
-    let minified_style = value
-        .replace('\t', " ")
-        .replace('\r', "")
-        .replace('\n', " ");
+// =============================================================================
+// HTML Processing Utilities
+// =============================================================================
 
-    let mut style_result = String::with_capacity(minified_style.len());
+fn process_style_attribute(value: &str) -> Cow<'_, str> {
+    let untouched = !value.contains(['\t', '\r', '\n'])
+        && !value.contains("  ")
+        && !value.contains(" :")
+        && !value.contains(" ;")
+        && !value.ends_with(';')
+        && value.trim().len() == value.len();
+    if untouched {
+        return Cow::Borrowed(value);
+    }
+
+    let mut style_result = String::with_capacity(value.len());
     let mut prev_space = false;
 
-    for ch in minified_style.chars() {
+    for ch in value.chars() {
         match ch {
-            ' ' => {
+            '\r' => {}
+            ' ' | '\t' | '\n' => {
                 if !prev_space {
                     style_result.push(' ');
                     prev_space = true;
                 }
             }
             ':' | ';' => {
-                while style_result.ends_with(' ') {
-                    style_result.pop();
-                }
+                style_result.truncate(style_result.trim_end_matches(' ').len());
                 style_result.push(ch);
                 prev_space = false;
             }
@@ -572,26 +1207,454 @@ fn process_style_attribute(value: &str) -> String {
         }
     }
 
-    style_result.trim_end_matches(';').trim().to_string()
+    let trimmed = style_result.trim_end_matches(';').trim();
+    if trimmed.len() == style_result.len() {
+        Cow::Owned(style_result)
+    } else {
+        Cow::Owned(trimmed.to_string())
+    }
 }
 
-fn process_class_attribute(value: &str) -> String {
-    let mut class_result = String::with_capacity(value.len());
-    let mut prev_space = false;
+fn process_class_attribute<'a>(
+    value: &'a str,
+    class_order: Option<&FrequencyOrder>,
+) -> Cow<'a, str> {
+    // Already a single-space separated list without repeats
+    let canonical = !value.is_empty()
+        && !value.contains(['\t', '\n', '\r', '\x0c'])
+        && !value.contains("  ")
+        && !value.starts_with(' ')
+        && !value.ends_with(' ');
+    let repeats = || {
+        value
+            .split(' ')
+            .enumerate()
+            .any(|(i, class)| value.split(' ').take(i).any(|earlier| earlier == class))
+    };
+    if canonical && class_order.is_none() && !repeats() {
+        return Cow::Borrowed(value);
+    }
 
-    for ch in value.chars() {
-        if ch.is_whitespace() {
-            if !prev_space && !class_result.is_empty() {
-                class_result.push(' ');
-                prev_space = true;
+    let mut classes: Vec<&str> = Vec::new();
+    for class in value.split_ascii_whitespace() {
+        if !classes.contains(&class) {
+            classes.push(class);
+        }
+    }
+
+    if let Some(order) = class_order {
+        classes.sort_by_key(|class| order.rank(class));
+    }
+
+    Cow::Owned(classes.join(" "))
+}
+
+// =============================================================================
+// Frequency Ordering
+// =============================================================================
+
+fn tally(counts: &mut HashMap<String, usize>, name: &str) {
+    match counts.get_mut(name) {
+        Some(count) => *count += 1,
+        None => {
+            counts.insert(name.to_string(), 1);
+        }
+    }
+}
+
+/// Calls `f` with every attribute that survives minification, skipping
+/// repeats and attributes the minifier drops, so that frequencies counted on
+/// the output match those counted on the input.
+fn for_each_kept_attribute(html: &str, options: &MinifyOptions, mut f: impl FnMut(Attribute)) {
+    let mut tag = String::new();
+    let mut foreign = ForeignContent::default();
+    let mut skipping: Option<SkippedElement> = None;
+    let mut seen = AttributeNames::default();
+    let mut processed = String::new();
+
+    let mut tokenizer = Tokenizer::new(html);
+    while let Some(token) = tokenizer.next_token() {
+        if let Token::TagOpenStart(name) = token {
+            if foreign.start_tag_namespace(name) != Namespace::Html {
+                tokenizer.foreign_start_tag();
+            }
+        }
+        if let Some(skipped) = skipping.as_mut() {
+            if skipped.token(&token) {
+                skipping = None;
+            }
+            continue;
+        }
+        match token {
+            Token::TagOpenStart(name)
+                if foreign::is_removable_element(foreign.namespace(), name, options) =>
+            {
+                skipping = Some(SkippedElement::new(name));
+            }
+            Token::TagOpenStart(name) => {
+                tag.clear();
+                tag.push_str(name);
+                tag.make_ascii_lowercase();
+                foreign.start_tag(name);
+                seen.clear();
+            }
+            Token::TagOpenEnd => foreign.end_start_tag(&tag, false),
+            Token::TagSelfClose => foreign.end_start_tag(&tag, true),
+            Token::TagClose(name) => foreign.end_tag(name),
+            Token::Attribute(attr) => {
+                foreign.attribute(attr.name);
+                if attr.name.is_empty() || seen.contains(attr.name) {
+                    continue;
+                }
+                seen.insert(attr.name);
+
+                processed.clear();
+                let namespace = foreign.namespace();
+                process_attribute(&mut processed, attr, &tag, namespace, options, None);
+                if !processed.is_empty() {
+                    f(attr);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn count_class_names(html: &str, options: &MinifyOptions, counts: &mut HashMap<String, usize>) {
+    for_each_kept_attribute(html, options, |attr| {
+        if let Attribute {
+            name,
+            value: Some(value),
+            ..
+        } = attr
+        {
+            if name.eq_ignore_ascii_case("class") {
+                let classes = value.split_ascii_whitespace();
+                for (i, class) in classes.clone().enumerate() {
+                    // Repeats within one list are dropped from the output
+                    if !classes.clone().take(i).any(|earlier| earlier == class) {
+                        tally(counts, class);
+                    }
+                }
+            }
+        }
+    });
+}
+
+fn count_attribute_names(html: &str, options: &MinifyOptions, counts: &mut HashMap<String, usize>) {
+    for_each_kept_attribute(html, options, |attr| {
+        tally(counts, &ascii_lowercase(attr.name));
+    });
+}
+
+fn rank_by_frequency(counts: HashMap<String, usize>) -> Vec<String> {
+    let mut ranked: Vec<(String, usize)> = counts.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked.into_iter().map(|(name, _)| name).collect()
+}
+
+/// Returns the class names used across `documents`, most frequent first.
+///
+/// Passing the result as [`MinifyOptions::class_name_order`] gives every page
+/// of a site the same class ordering, which compresses better than per-page
+/// orderings.
+pub fn class_name_frequency<'a, I>(documents: I) -> Vec<String>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let options = MinifyOptions::default();
+    let mut counts = HashMap::new();
+    for html in documents {
+        count_class_names(html, &options, &mut counts);
+    }
+    rank_by_frequency(counts)
+}
+
+/// Sort ranks for class or attribute names: a preferred order first, then
+/// the names of the current document by descending frequency.
+struct FrequencyOrder {
+    ranks: HashMap<String, usize>,
+}
+
+impl FrequencyOrder {
+    fn new(preferred: &[String], counts: HashMap<String, usize>) -> Self {
+        let mut ranks = HashMap::new();
+        for name in preferred.iter().cloned().chain(rank_by_frequency(counts)) {
+            let next = ranks.len();
+            ranks.entry(name).or_insert(next);
+        }
+        Self { ranks }
+    }
+
+    fn for_class_names(html: &str, options: &MinifyOptions) -> Self {
+        let mut counts = HashMap::new();
+        count_class_names(html, options, &mut counts);
+        Self::new(&options.class_name_order, counts)
+    }
+
+    fn for_attribute_names(html: &str, options: &MinifyOptions) -> Self {
+        let mut counts = HashMap::new();
+        count_attribute_names(html, options, &mut counts);
+        Self::new(&[], counts)
+    }
+
+    fn rank(&self, name: &str) -> usize {
+        self.ranks.get(name).copied().unwrap_or(usize::MAX)
+    }
+}
+
+// =============================================================================
+// Minifier Options
+// =============================================================================
+
+/// Options controlling the optimizations applied by [`minify_html_with_options`].
+#[derive(Debug, Clone, Default)]
+pub struct MinifyOptions {
+    /// Collapse global boolean attributes such as `inert` on custom elements,
+    /// which may define attributes of the same name with other meanings.
+    pub collapse_custom_element_booleans: bool,
+    /// Reorder the tokens of every `class` attribute by frequency so that
+    /// repeated class lists compress better.
+    pub sort_class_names: bool,
+    /// Class names ranked ahead of the document's own frequencies when
+    /// `sort_class_names` is set, typically from [`class_name_frequency`].
+    pub class_name_order: Vec<String>,
+    /// Reorder the attributes of every tag by their frequency across the
+    /// document. Custom elements, which can observe attribute order, are left
+    /// as authored.
+    pub sort_attributes: bool,
+    /// Report attributes repeated on the same tag in [`MinifyOutput::warnings`].
+    /// Duplicates are dropped either way, keeping the first occurrence.
+    pub warn_duplicate_attributes: bool,
+    /// Gather a [`MinifyReport`] of savings and timings into
+    /// [`MinifyOutput::report`].
+    pub collect_report: bool,
+    /// Re-parse the input and output, compare them and check that a second
+    /// pass is a no-op, storing the result in [`MinifyOutput::verification`].
+    /// Ignored by [`minify_stream`], which never holds the whole document.
+    pub verify: bool,
+    /// Record where each token of the output came from in the input,
+    /// including the tokens of inline CSS and JavaScript, and return it as
+    /// [`MinifyOutput::source_map`]. Ignored by [`minify_stream`].
+    pub source_map: bool,
+    /// Optimize inline SVG: shorten path data and numbers, drop attributes
+    /// at their initial values, and remove `<metadata>` along with the
+    /// markup drawing editors leave behind.
+    pub minify_svg: bool,
+    /// Decimal places that `minify_svg` rounds SVG numbers to, where that
+    /// makes them shorter. `None` keeps numbers at their full precision.
+    pub svg_precision: Option<u32>,
+}
+
+/// Error returned when setting a [`MinifyOptions`] field by name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptionError {
+    /// No option has this name.
+    Unknown(String),
+    /// The value cannot be converted to the option's type.
+    InvalidValue { name: String, value: String },
+}
+
+impl std::fmt::Display for OptionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OptionError::Unknown(name) => write!(f, "unknown option `{name}`"),
+            OptionError::InvalidValue { name, value } => {
+                write!(f, "invalid value `{value}` for option `{name}`")
+            }
+        }
+    }
+}
+
+impl std::error::Error for OptionError {}
+
+impl MinifyOptions {
+    fn bool_field(&mut self, name: &str) -> Option<&mut bool> {
+        match name {
+            "collapse_custom_element_booleans" => Some(&mut self.collapse_custom_element_booleans),
+            "sort_class_names" => Some(&mut self.sort_class_names),
+            "sort_attributes" => Some(&mut self.sort_attributes),
+            "warn_duplicate_attributes" => Some(&mut self.warn_duplicate_attributes),
+            "collect_report" => Some(&mut self.collect_report),
+            "verify" => Some(&mut self.verify),
+            "source_map" => Some(&mut self.source_map),
+            "minify_svg" => Some(&mut self.minify_svg),
+            _ => None,
+        }
+    }
+
+    fn invalid_value(&mut self, name: &str, value: &str) -> OptionError {
+        if self.bool_field(name).is_some() || matches!(name, "class_name_order" | "svg_precision") {
+            OptionError::InvalidValue {
+                name: name.to_string(),
+                value: value.to_string(),
             }
         } else {
-            class_result.push(ch);
-            prev_space = false;
+            OptionError::Unknown(name.to_string())
+        }
+    }
+
+    /// Sets a boolean option by its field name, as used by FFI hosts and
+    /// configuration files.
+    pub fn set_bool(&mut self, name: &str, value: bool) -> Result<(), OptionError> {
+        match self.bool_field(name) {
+            Some(field) => {
+                *field = value;
+                Ok(())
+            }
+            None => Err(self.invalid_value(name, &value.to_string())),
+        }
+    }
+
+    /// Sets any option by its field name from a string. Booleans accept
+    /// `true`/`false`/`1`/`0`; lists are whitespace-separated.
+    pub fn set_str(&mut self, name: &str, value: &str) -> Result<(), OptionError> {
+        if name == "class_name_order" {
+            self.class_name_order = value.split_ascii_whitespace().map(String::from).collect();
+            return Ok(());
+        }
+        if name == "svg_precision" {
+            let digits = value.parse().map_err(|_| self.invalid_value(name, value))?;
+            self.svg_precision = Some(digits);
+            return Ok(());
+        }
+
+        match value {
+            "true" | "1" => self.set_bool(name, true),
+            "false" | "0" => self.set_bool(name, false),
+            _ => Err(self.invalid_value(name, value)),
+        }
+    }
+}
+
+// =============================================================================
+// Minifier Output
+// =============================================================================
+
+/// A problem noticed in the input while minifying it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MinifyWarning {
+    /// An attribute appeared more than once on a tag; only the first was kept.
+    DuplicateAttribute { tag: String, attribute: String },
+}
+
+impl std::fmt::Display for MinifyWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MinifyWarning::DuplicateAttribute { tag, attribute } => {
+                write!(f, "duplicate attribute `{attribute}` on <{tag}>")
+            }
+        }
+    }
+}
+
+/// Minified HTML together with any warnings collected along the way.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MinifyOutput {
+    pub html: String,
+    pub warnings: Vec<MinifyWarning>,
+    /// Present when [`MinifyOptions::collect_report`] is set.
+    pub report: Option<MinifyReport>,
+    /// Present when [`MinifyOptions::verify`] is set.
+    pub verification: Option<Verification>,
+    /// Present when [`MinifyOptions::source_map`] is set.
+    pub source_map: Option<SourceMap>,
+}
+
+/// Bytes saved by each kind of optimization.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BytesRemoved {
+    pub comments: u64,
+    /// Collapsed whitespace, plus tag-level savings such as lowercasing.
+    pub whitespace: u64,
+    pub attributes: u64,
+    pub optional_tags: u64,
+    pub css: u64,
+    pub js: u64,
+}
+
+impl BytesRemoved {
+    pub fn total(&self) -> u64 {
+        self.comments + self.whitespace + self.attributes + self.optional_tags + self.css + self.js
+    }
+}
+
+/// Statistics about a minification run. Also passed across the FFI boundary,
+/// hence the fixed-width fields.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MinifyReport {
+    pub input_bytes: u64,
+    pub output_bytes: u64,
+    pub bytes_removed: BytesRemoved,
+    /// Opening tags processed.
+    pub elements: u64,
+    /// Attributes processed, including dropped ones.
+    pub attributes: u64,
+    /// Time spent tokenizing the input.
+    pub parse_nanos: u64,
+    /// Time spent rewriting tokens and producing the output.
+    pub emit_nanos: u64,
+}
+
+impl MinifyReport {
+    pub fn parse_time(&self) -> Duration {
+        Duration::from_nanos(self.parse_nanos)
+    }
+
+    pub fn emit_time(&self) -> Duration {
+        Duration::from_nanos(self.emit_nanos)
+    }
+
+    fn record(&mut self, token: &Token, category: SavingsCategory, raw: usize, emitted: usize) {
+        match token {
+            Token::TagOpenStart(_) => self.elements += 1,
+            Token::Attribute(_) => self.attributes += 1,
+            _ => {}
+        }
+
+        let saved = raw.saturating_sub(emitted) as u64;
+        let removed = &mut self.bytes_removed;
+        match category {
+            SavingsCategory::Comments => removed.comments += saved,
+            SavingsCategory::Whitespace => removed.whitespace += saved,
+            SavingsCategory::Attributes => removed.attributes += saved,
+            SavingsCategory::CloseTag if emitted == 0 => removed.optional_tags += saved,
+            SavingsCategory::CloseTag => removed.whitespace += saved,
+            SavingsCategory::Css => removed.css += saved,
+            SavingsCategory::Js => removed.js += saved,
+        }
+    }
+}
+
+/// Which [`BytesRemoved`] bucket the savings on a token are credited to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SavingsCategory {
+    Comments,
+    Whitespace,
+    Attributes,
+    /// Optional tags when the close tag was omitted, whitespace otherwise.
+    CloseTag,
+    Css,
+    Js,
+}
+
+impl SavingsCategory {
+    fn of(token: &Token, in_script_tag: bool, in_style_tag: bool) -> Self {
+        match token {
+            Token::Comment(_) => SavingsCategory::Comments,
+            Token::Attribute(_) => SavingsCategory::Attributes,
+            Token::TagClose(_) => SavingsCategory::CloseTag,
+            Token::TextNode(_) if in_style_tag => SavingsCategory::Css,
+            Token::TextNode(_) if in_script_tag => SavingsCategory::Js,
+            _ => SavingsCategory::Whitespace,
         }
     }
+}
 
-    class_result
+fn elapsed_nanos(start: Instant, end: Instant) -> u64 {
+    end.duration_since(start).as_nanos() as u64
 }
 
 // =============================================================================
@@ -599,17 +1662,236 @@ fn process_class_attribute(value: &str) -> String {
 // =============================================================================
 
 pub fn minify_html_tokens(html: &str) -> String {
+    minify_html_with_options(html, &MinifyOptions::default())
+}
+
+pub fn minify_html_with_options(html: &str, options: &MinifyOptions) -> String {
+    minify_html(html, options).html
+}
+
+pub fn minify_html(html: &str, options: &MinifyOptions) -> MinifyOutput {
+    let class_order = options
+        .sort_class_names
+        .then(|| FrequencyOrder::for_class_names(html, options));
+    let attribute_order = options
+        .sort_attributes
+        .then(|| FrequencyOrder::for_attribute_names(html, options));
+    let mut emitter = HtmlEmitter::new(options, class_order, attribute_order);
+    if let Some(report) = emitter.report.as_mut() {
+        report.input_bytes = html.len() as u64;
+    }
+
     let mut result = String::with_capacity(html.len() * 3 / 4);
     let mut tokenizer = Tokenizer::new(html);
-    let mut in_pre_tag = false;
-    let mut in_script_tag = false;
-    let mut in_style_tag = false;
-    let mut current_tag = String::new();
+    emitter.drive(&mut tokenizer, &mut result, true);
+    emitter.finish(&mut result);
+    if let Some(report) = emitter.report.as_mut() {
+        report.output_bytes = result.len() as u64;
+    }
 
-    while let Some(token) = tokenizer.next_token() {
+    let verification = options
+        .verify
+        .then(|| verify_minified(html, &result, options));
+    let source_map = emitter
+        .offsets
+        .map(|offsets| SourceMap::new(&offsets, &result, html));
+
+    MinifyOutput {
+        html: result,
+        warnings: emitter.warnings,
+        report: emitter.report,
+        verification,
+        source_map,
+    }
+}
+
+/// The token-rewriting state of one minification run. It only ever looks at
+/// the current token, so input can be fed to it in pieces, and it writes
+/// final output directly: whitespace is decided as text is emitted rather
+/// than cleaned up afterwards.
+struct HtmlEmitter<'o> {
+    options: &'o MinifyOptions,
+    class_order: Option<FrequencyOrder>,
+    attribute_order: Option<FrequencyOrder>,
+    in_pre_tag: bool,
+    in_script_tag: bool,
+    in_style_tag: bool,
+    /// The depth of foreign content an SVG or MathML `<script>` or `<style>`
+    /// was opened in. Its text is kept as written since it may be split by
+    /// markup the CSS and JavaScript minifiers never see.
+    foreign_code: Option<usize>,
+    /// Whether tags are HTML or SVG.
+    foreign: ForeignContent,
+    /// An SVG element being dropped by [`MinifyOptions::minify_svg`].
+    skipping: Option<SkippedElement>,
+    current_tag: String,
+    /// Attributes held back for sorting, written to one buffer and keyed
+    /// by their sort rank, with the input offset each came from.
+    pending_attributes: Vec<((bool, usize), std::ops::Range<usize>, usize)>,
+    pending_output: String,
+    seen_attributes: AttributeNames,
+    /// Text or inline content was written since the last block-level tag,
+    /// so whitespace after it matters.
+    after_text: bool,
+    /// The start tag being written sits within a line of text.
+    inline_start_tag: bool,
+    /// Whitespace seen after text and held until more text or an inline tag
+    /// follows, since a block-level tag or the end of input drops it.
+    pending_space: bool,
+    /// Input offset of the whitespace behind `pending_space`.
+    pending_space_source: usize,
+    warnings: Vec<MinifyWarning>,
+    report: Option<MinifyReport>,
+    /// Where each token of the output came from, when building a source map.
+    offsets: Option<SourceOffsets>,
+}
+
+impl<'o> HtmlEmitter<'o> {
+    fn new(
+        options: &'o MinifyOptions,
+        class_order: Option<FrequencyOrder>,
+        attribute_order: Option<FrequencyOrder>,
+    ) -> Self {
+        Self {
+            options,
+            class_order,
+            attribute_order,
+            in_pre_tag: false,
+            foreign_code: None,
+            in_script_tag: false,
+            in_style_tag: false,
+            foreign: ForeignContent::default(),
+            skipping: None,
+            current_tag: String::new(),
+            pending_attributes: Vec::new(),
+            pending_output: String::new(),
+            seen_attributes: AttributeNames::default(),
+            after_text: false,
+            inline_start_tag: false,
+            pending_space: false,
+            pending_space_source: 0,
+            warnings: Vec::new(),
+            report: options.collect_report.then(MinifyReport::default),
+            offsets: options.source_map.then(SourceOffsets::default),
+        }
+    }
+
+    /// Rewrites tokens from `tokenizer` into `result` and returns how many
+    /// bytes of its input were consumed. Unless `is_final`, a token that may
+    /// continue past the end of the input is left unconsumed, except that
+    /// text is emitted up to its last whitespace.
+    fn drive(&mut self, tokenizer: &mut Tokenizer, result: &mut String, is_final: bool) -> usize {
+        loop {
+            let parse_start = self.report.is_some().then(Instant::now);
+            let token_start = tokenizer.position;
+            let state = tokenizer.state();
+
+            let Some(mut token) = tokenizer.next_token() else {
+                if !is_final {
+                    tokenizer.restore(state, token_start);
+                    return token_start;
+                }
+                if let Some(report) = self.report.as_mut() {
+                    report.bytes_removed.whitespace += (tokenizer.position - token_start) as u64;
+                }
+                return tokenizer.position;
+            };
+
+            let at_end = tokenizer.position == tokenizer.end;
+            if !is_final && at_end && !matches!(token, Token::TagOpenEnd | Token::TagSelfClose) {
+                let split = match token {
+                    Token::TextNode(text) if !state.in_raw_text => text
+                        .rfind(|c: char| c.is_ascii_whitespace())
+                        .map(|i| (text, i + 1)),
+                    _ => None,
+                };
+                let Some((text, split)) = split else {
+                    tokenizer.restore(state, token_start);
+                    return token_start;
+                };
+                tokenizer.position = tokenizer.end - text.len() + split;
+                token = Token::TextNode(&text[..split]);
+            }
+
+            if let Token::TagOpenStart(name) = token {
+                if self.foreign.start_tag_namespace(name) != Namespace::Html {
+                    tokenizer.foreign_start_tag();
+                }
+            }
+            let emit_start = self.report.is_some().then(Instant::now);
+            let category = SavingsCategory::of(&token, self.in_script_tag, self.in_style_tag);
+            let emitted_start = result.len();
+            let deferred_bytes = self.emit(token, tokenizer.token_start, result);
+
+            if let (Some(report), Some(parse_start), Some(emit_start)) =
+                (self.report.as_mut(), parse_start, emit_start)
+            {
+                let raw_bytes = tokenizer.position - token_start;
+                let emitted_bytes = result.len() - emitted_start + deferred_bytes;
+                report.record(&token, category, raw_bytes, emitted_bytes);
+                report.parse_nanos += elapsed_nanos(parse_start, emit_start);
+                report.emit_nanos += elapsed_nanos(emit_start, Instant::now());
+            }
+        }
+    }
+
+    /// Writes the minified form of `token`, which starts at byte `source` of
+    /// the input. Returns the number of bytes held back for attribute sorting
+    /// rather than written to `result`.
+    fn emit(&mut self, token: Token, source: usize, result: &mut String) -> usize {
+        if let Some(skipping) = self.skipping.as_mut() {
+            if skipping.token(&token) {
+                self.skipping = None;
+            }
+            return 0;
+        }
+        if let Token::TagOpenStart(tag_name) = token {
+            if foreign::is_removable_element(self.foreign.namespace(), tag_name, self.options) {
+                self.skipping = Some(SkippedElement::new(tag_name));
+                return 0;
+            }
+        }
+        let inline_tag = match token {
+            Token::TagOpenStart(name) => {
+                self.inline_start_tag = is_inline_tag(name, self.foreign.start_tag_namespace(name));
+                self.inline_start_tag
+            }
+            Token::Attribute(_) | Token::TagOpenEnd | Token::TagSelfClose => self.inline_start_tag,
+            Token::TagClose(tag) => is_inline_tag(
+                tag.split_ascii_whitespace().next().unwrap_or_default(),
+                self.foreign.end_tag_namespace(tag),
+            ),
+            _ => false,
+        };
+        if !matches!(token, Token::Attribute(_)) {
+            self.finish(result);
+            if inline_tag {
+                self.push_pending_space(result);
+            }
+            if let Some(offsets) = self.offsets.as_mut() {
+                offsets.add(result.len(), source);
+            }
+        }
+        // Comments vanish, text may continue earlier text and whitespace
+        // next to an inline tag is written before it, but anything else ends
+        // the line along with any whitespace held after it
+        let continues_text = match token {
+            Token::Comment(_) => true,
+            Token::TextNode(_) => !(self.in_style_tag || self.in_script_tag || self.is_verbatim()),
+            _ => inline_tag,
+        };
+        if !continues_text {
+            self.after_text = false;
+            self.pending_space = false;
+        }
+
+        let mut deferred_bytes = 0;
         match token {
             Token::Doctype(content) => {
-                result.push_str(&content.to_lowercase());
+                let start = result.len();
+                self.push_collapsed_text(content, source, result);
+                result[start..].make_ascii_lowercase();
+                self.after_text = false;
             }
             Token::Comment(_) => {
                 // Skip comments for minification
@@ -620,100 +1902,338 @@ pub fn minify_html_tokens(html: &str) -> String {
                 result.push_str("]]>");
             }
             Token::TagOpenStart(tag_name) => {
-                current_tag = tag_name.to_lowercase();
-                in_pre_tag = matches!(current_tag.as_str(), "pre" | "code" | "textarea");
-                in_script_tag = current_tag == "script";
-                in_style_tag = current_tag == "style";
-
+                self.current_tag.clear();
+                self.current_tag.push_str(tag_name);
+                self.current_tag.make_ascii_lowercase();
+                // An `<svg>` tag is itself read as HTML, as is an HTML tag
+                // that ends SVG content, and MathML names are all lowercase
+                let namespace = self.foreign.start_tag_namespace(tag_name);
+                let code = matches!(self.current_tag.as_str(), "script" | "style");
+                self.in_pre_tag = matches!(self.current_tag.as_str(), "pre" | "code" | "textarea");
+                self.in_script_tag = namespace == Namespace::Html && self.current_tag == "script";
+                self.in_style_tag = namespace == Namespace::Html && self.current_tag == "style";
+                self.foreign.start_tag(tag_name);
+                self.leave_foreign_code();
+                if namespace != Namespace::Html && code && self.foreign_code.is_none() {
+                    self.foreign_code = Some(self.foreign.depth());
+                }
                 result.push('<');
-                result.push_str(&current_tag);
+                if namespace == Namespace::Svg {
+                    result.push_str(tag_name);
+                } else {
+                    result.push_str(&self.current_tag);
+                }
+                self.seen_attributes.clear();
+                // Void elements such as `<img>` are content of the line
+                if inline_tag && is_singleton_element(&self.current_tag) {
+                    self.after_text = true;
+                }
             }
             Token::Attribute(attr) => {
-                process_attribute(&mut result, attr, &current_tag);
+                // A `<font>` that this attribute takes out of SVG content
+                // is an inline HTML element
+                if self.foreign.attribute(attr.name) {
+                    self.inline_start_tag = true;
+                    self.leave_foreign_code();
+                }
+                // Browsers keep the first of repeated attributes
+                let name = attr.name;
+                if self.seen_attributes.contains(name) {
+                    if self.options.warn_duplicate_attributes {
+                        self.warnings.push(MinifyWarning::DuplicateAttribute {
+                            tag: self.current_tag.clone(),
+                            attribute: name.to_ascii_lowercase(),
+                        });
+                    }
+                } else if let Some(order) = self
+                    .attribute_order
+                    .as_ref()
+                    .filter(|_| !is_custom_element(&self.current_tag))
+                {
+                    self.seen_attributes.insert(name);
+                    let start = self.pending_output.len();
+                    process_attribute(
+                        &mut self.pending_output,
+                        attr,
+                        &self.current_tag,
+                        self.foreign.namespace(),
+                        self.options,
+                        self.class_order.as_ref(),
+                    );
+                    if self.pending_output.len() > start {
+                        let name = ascii_lowercase(name);
+                        let pinned = self.current_tag == "input" && name == "type";
+                        let rank = (!pinned, order.rank(&name));
+                        deferred_bytes = self.pending_output.len() - start;
+                        self.pending_attributes.push((
+                            rank,
+                            start..self.pending_output.len(),
+                            source,
+                        ));
+                    }
+                } else {
+                    self.seen_attributes.insert(name);
+                    let start = result.len();
+                    process_attribute(
+                        result,
+                        attr,
+                        &self.current_tag,
+                        self.foreign.namespace(),
+                        self.options,
+                        self.class_order.as_ref(),
+                    );
+                    // Map the attribute name, after the space written before it
+                    if let Some(offsets) = self.offsets.as_mut().filter(|_| result.len() > start) {
+                        offsets.add(start + 1, source);
+                    }
+                }
             }
             Token::TagOpenEnd => {
                 result.push('>');
+                self.foreign.end_start_tag(&self.current_tag, false);
             }
             Token::TagSelfClose => {
-                if is_singleton_element(&current_tag) {
+                // Only SVG honors `/>` on any element
+                if is_singleton_element(&self.current_tag) && !self.foreign.is_foreign() {
                     result.push('>');
                 } else {
+                    // An unquoted value would take in the slash
+                    if ends_with_unquoted_value(result) {
+                        result.push(' ');
+                    }
                     result.push_str("/>");
                 }
+                if matches!(self.current_tag.as_str(), "script" | "style") {
+                    self.foreign_code = None;
+                }
+                self.foreign.end_start_tag(&self.current_tag, true);
             }
             Token::TagClose(tag_name) => {
-                let tag_lower = tag_name.to_lowercase();
-
-                if !is_close_optional(&tag_lower) {
+                // An inline element may render something, so whitespace
+                // after it matters even if it held no text. A nameless end
+                // tag ends no element.
+                if inline_tag && tag_name.split_ascii_whitespace().next().is_some() {
+                    self.after_text = true;
+                }
+                self.foreign.end_tag(tag_name);
+                self.leave_foreign_code();
+                let namespace = self.foreign.namespace();
+                if namespace != Namespace::Html || !is_close_optional(tag_name) {
                     result.push_str("</");
-                    result.push_str(&tag_lower);
+                    let start = result.len();
+                    result.push_str(tag_name);
+                    if namespace != Namespace::Svg {
+                        result[start..].make_ascii_lowercase();
+                    }
                     result.push('>');
                 }
 
                 // Update context flags
-                if matches!(tag_lower.as_str(), "pre" | "code" | "textarea") {
-                    in_pre_tag = false;
+                if contains_ignore_ascii_case(&["pre", "code", "textarea"], tag_name) {
+                    self.in_pre_tag = false;
+                }
+                if tag_name.eq_ignore_ascii_case("script") {
+                    self.in_script_tag = false;
                 }
-                if tag_lower == "script" {
-                    in_script_tag = false;
+                if tag_name.eq_ignore_ascii_case("style") {
+                    self.in_style_tag = false;
                 }
-                if tag_lower == "style" {
-                    in_style_tag = false;
+                if contains_ignore_ascii_case(&["script", "style"], tag_name) {
+                    self.foreign_code = None;
                 }
             }
             Token::TextNode(content) => {
-                if in_style_tag {
-                    let minified_css = minify_css(content);
-                    result.push_str(&minified_css);
-                } else if in_script_tag {
-                    let minified_js = minify_javascript(content);
-                    result.push_str(&minified_js);
-                } else if in_pre_tag {
+                if self.in_style_tag {
+                    minify_css_into(content, source, result, self.offsets.as_mut());
+                } else if self.in_script_tag {
+                    minify_javascript_into(content, source, result, self.offsets.as_mut());
+                } else if self.is_verbatim() {
                     result.push_str(content);
+                    self.after_text |= !content.bytes().all(|b| b.is_ascii_whitespace());
                 } else {
-                    append_collapsed_whitespace(&mut result, content);
+                    self.push_collapsed_text(content, source, result);
                 }
             }
         }
+        deferred_bytes
     }
 
-    // Final cleanup with UTF-8 awareness
-    cleanup_html_spacing(&result)
+    /// Whether text is written as it is, whitespace included.
+    fn is_verbatim(&self) -> bool {
+        self.in_pre_tag
+            || self.foreign_code == Some(self.foreign.depth())
+            || self.foreign.in_math_text()
+    }
+
+    /// Forgets an SVG or MathML `<script>` or `<style>` once the foreign
+    /// content it was opened in has ended without its end tag.
+    fn leave_foreign_code(&mut self) {
+        if self.foreign_code > Some(self.foreign.depth()) {
+            self.foreign_code = None;
+        }
+    }
+
+    /// Writes out any attributes still held back for sorting.
+    fn finish(&mut self, result: &mut String) {
+        if !self.pending_attributes.is_empty() {
+            flush_sorted_attributes(
+                result,
+                &mut self.pending_attributes,
+                &self.pending_output,
+                self.offsets.as_mut(),
+            );
+            self.pending_output.clear();
+        }
+    }
+
+    /// Writes the whitespace held in `pending_space`, if any.
+    fn push_pending_space(&mut self, result: &mut String) {
+        if self.pending_space {
+            if let Some(offsets) = self.offsets.as_mut() {
+                offsets.add(result.len(), self.pending_space_source);
+            }
+            result.push(' ');
+            self.pending_space = false;
+        }
+    }
+
+    /// Writes text with whitespace runs collapsed to a single space. Leading
+    /// whitespace is dropped unless it follows earlier text, and trailing
+    /// whitespace is held in `pending_space` until the next token shows
+    /// whether it is needed. Text resuming after whitespace other than a
+    /// single space is mapped back to `source`, where `content` starts.
+    fn push_collapsed_text(&mut self, content: &str, source: usize, result: &mut String) {
+        let bytes = content.as_bytes();
+        let mut start = 0;
+        while start < bytes.len() {
+            let run = bytes[start..]
+                .iter()
+                .position(u8::is_ascii_whitespace)
+                .unwrap_or(bytes.len() - start);
+            if run == 0 {
+                if self.after_text && !self.pending_space {
+                    self.pending_space = true;
+                    self.pending_space_source = source + start;
+                }
+                start += 1;
+                continue;
+            }
+
+            let wrote_space = self.pending_space;
+            if self.pending_space {
+                // A space held over from an earlier token maps back there
+                if let Some(offsets) = self.offsets.as_mut().filter(|_| start == 0) {
+                    offsets.add(result.len(), self.pending_space_source);
+                }
+                result.push(' ');
+                self.pending_space = false;
+            }
+            if let Some(offsets) = self.offsets.as_mut() {
+                // Output stays aligned with the input only where a lone
+                // space was written for a lone space
+                let aligned = match start {
+                    0 => !wrote_space,
+                    _ => {
+                        wrote_space
+                            && bytes[start - 1] == b' '
+                            && (start == 1 || !bytes[start - 2].is_ascii_whitespace())
+                    }
+                };
+                if !aligned {
+                    offsets.add(result.len(), source + start);
+                }
+            }
+            result.push_str(&content[start..start + run]);
+            self.after_text = true;
+            start += run;
+        }
+    }
 }
 
-fn process_attribute(result: &mut String, attr: &str, current_tag: &str) {
-    let clean_attr = attr.trim();
-    if clean_attr.is_empty() {
-        return;
+/// Whether the start tag at the end of `output` ends with an attribute
+/// value written without quotes. Such values hold no `<`, `=` or spaces.
+fn ends_with_unquoted_value(output: &str) -> bool {
+    let tag = &output[output.rfind('<').unwrap_or(0)..];
+    let last = tag.rsplit(' ').next().unwrap_or_default();
+    last.contains('=') && !last.ends_with(['"', '\''])
+}
+
+/// Emits the buffered attributes of a tag in frequency order. The sort is
+/// stable, and `type` is ranked first on `<input>` since older engines apply
+/// it before `value`.
+fn flush_sorted_attributes(
+    result: &mut String,
+    pending: &mut Vec<((bool, usize), std::ops::Range<usize>, usize)>,
+    emitted: &str,
+    mut offsets: Option<&mut SourceOffsets>,
+) {
+    pending.sort_by_key(|(rank, _, _)| *rank);
+    for (_, range, source) in pending.drain(..) {
+        if let Some(offsets) = offsets.as_deref_mut() {
+            offsets.add(result.len() + 1, source);
+        }
+        result.push_str(&emitted[range]);
+    }
+}
+
+/// Names of the attributes seen so far on the current tag. They share one
+/// buffer so that checking for duplicates does not allocate per attribute.
+#[derive(Debug, Default)]
+struct AttributeNames {
+    names: String,
+    ends: Vec<usize>,
+}
+
+impl AttributeNames {
+    fn clear(&mut self) {
+        self.names.clear();
+        self.ends.clear();
     }
 
-    if let Some(eq_pos) = clean_attr.find('=') {
-        let key = clean_attr[..eq_pos].trim().to_lowercase();
-        let raw_value = clean_attr[eq_pos + 1..].trim();
+    fn contains(&self, name: &str) -> bool {
+        let starts = std::iter::once(0).chain(self.ends.iter().copied());
+        starts
+            .zip(&self.ends)
+            .any(|(start, &end)| self.names[start..end].eq_ignore_ascii_case(name))
+    }
 
-        let value = if raw_value.len() >= 2
-            && ((raw_value.starts_with('"') && raw_value.ends_with('"'))
-                || (raw_value.starts_with('\'') && raw_value.ends_with('\'')))
-        {
-            &raw_value[1..raw_value.len() - 1]
-        } else {
-            raw_value
-        };
+    fn insert(&mut self, name: &str) {
+        self.names.push_str(name);
+        self.ends.push(self.names.len());
+    }
+}
+
+fn process_attribute(
+    result: &mut String,
+    attr: Attribute,
+    current_tag: &str,
+    namespace: Namespace,
+    options: &MinifyOptions,
+    class_order: Option<&FrequencyOrder>,
+) {
+    let Attribute { name, value, .. } = attr;
+    if name.is_empty() {
+        return;
+    }
+    if namespace != Namespace::Html {
+        process_foreign_attribute(result, attr, current_tag, namespace, options, class_order);
+        return;
+    }
 
-        // Handle boolean attributes
-        if is_boolean_attribute(&key) {
+    let key = ascii_lowercase(name);
+    if let Some(value) = value {
+        // Handle boolean attributes, leaving custom elements alone unless asked
+        if is_boolean_attribute(current_tag, &key)
+            && (options.collapse_custom_element_booleans || !is_custom_element(current_tag))
+        {
             result.push(' ');
             result.push_str(&key);
             return;
         }
 
         // Skip empty removable attributes
-        if value.is_empty() {
-            if is_empty_removable(&key)
-                || matches!(key.as_str(), "type" | "value" | "alt" | "title")
-            {
-                return;
-            }
+        if value.is_empty() && is_removable_when_empty(&key) {
+            return;
         }
 
         // Skip attributes with default values
@@ -721,28 +2241,39 @@ fn process_attribute(result: &mut String, attr: &str, current_tag: &str) {
             return;
         }
 
-        result.push(' ');
-        result.push_str(&key);
-        result.push('=');
+        // Canonicalize enumerated keywords to their shortest equivalent
+        if let Some(normalized) = normalize_enumerated_value(current_tag, &key, value) {
+            match normalized {
+                EnumeratedValue::Default => {}
+                EnumeratedValue::Empty => {
+                    result.push(' ');
+                    result.push_str(&key);
+                }
+                EnumeratedValue::Keyword(keyword) => {
+                    result.push(' ');
+                    result.push_str(&key);
+                    result.push('=');
+                    result.push_str(keyword);
+                }
+            }
+            return;
+        }
 
         // Process specific attribute types
-        let processed_value = match key.as_str() {
+        let processed_value = match key.as_ref() {
             "style" => process_style_attribute(value),
-            "class" if value.contains(' ') => process_class_attribute(value),
-            _ => value.to_string(),
+            "class" => process_class_attribute(value, class_order),
+            _ => Cow::Borrowed(value),
         };
 
-        // Add quotes if necessary
-        if should_remove_quotes(&processed_value) {
-            result.push_str(&processed_value);
-        } else {
-            result.push('"');
-            result.push_str(&processed_value);
-            result.push('"');
+        // Drop class lists that were only whitespace
+        if key == "class" && processed_value.is_empty() {
+            return;
         }
+
+        push_attribute_value(result, &key, &processed_value);
     } else {
         // Attribute without value
-        let key = clean_attr.to_lowercase();
         if !is_empty_removable(&key) {
             result.push(' ');
             result.push_str(&key);
@@ -750,118 +2281,74 @@ fn process_attribute(result: &mut String, attr: &str, current_tag: &str) {
     }
 }
 
-fn cleanup_html_spacing(html: &str) -> String {
-    let mut cleaned = String::with_capacity(html.len());
-    let mut chars = html.chars().peekable();
-
-    while let Some(ch) = chars.next() {
-        match ch {
-            '>' => {
-                cleaned.push('>');
-                // Skip whitespace after >
-                while let Some(&next_ch) = chars.peek() {
-                    if next_ch.is_whitespace() {
-                        chars.next();
-                    } else {
-                        break;
-                    }
-                }
-            }
-            ch if ch.is_whitespace() => {
-                // Check if next character is '<'
-                if let Some(&'<') = chars.peek() {
-                    continue; // Skip whitespace before <
-                }
-
-                // Collapse whitespace
-                if !cleaned.ends_with(' ') {
-                    cleaned.push(' ');
-                }
-
-                // Skip additional whitespace
-                while let Some(&next_ch) = chars.peek() {
-                    if next_ch.is_whitespace() {
-                        chars.next();
-                    } else {
-                        break;
-                    }
-                }
-            }
-            '=' => {
-                // Remove spaces around =
-                while cleaned.ends_with(' ') {
-                    cleaned.pop();
-                }
-                cleaned.push('=');
-
-                // Skip whitespace after =
-                while let Some(&next_ch) = chars.peek() {
-                    if next_ch == ' ' {
-                        chars.next();
-                    } else {
-                        break;
-                    }
-                }
-            }
-            _ => cleaned.push(ch),
-        }
-    }
-
-    cleaned.trim().to_string()
-}
-
-// =============================================================================
-// FFI Interface
-// =============================================================================
-
-#[no_mangle]
-pub unsafe extern "C" fn minify_html_string(html_ptr: *const c_char) -> *mut c_char {
-    if html_ptr.is_null() {
-        return std::ptr::null_mut();
-    }
-
-    let c_str = match CStr::from_ptr(html_ptr).to_str() {
-        Ok(s) => s,
-        Err(_) => {
-            // Handle invalid UTF-8 gracefully
-            let c_str_bytes = CStr::from_ptr(html_ptr);
-            return match CString::new(c_str_bytes.to_string_lossy().as_ref()) {
-                Ok(c_string) => c_string.into_raw(),
-                Err(_) => std::ptr::null_mut(),
-            };
+/// Writes an attribute of an SVG or MathML element. Its name keeps its
+/// case, as in `viewBox` or `definitionURL`, and the HTML rules for
+/// booleans and default values do not apply.
+fn process_foreign_attribute(
+    result: &mut String,
+    attr: Attribute,
+    current_tag: &str,
+    namespace: Namespace,
+    options: &MinifyOptions,
+    class_order: Option<&FrequencyOrder>,
+) {
+    let Attribute { name, value, .. } = attr;
+    // The parser lowercases MathML attribute names but one
+    let name = match namespace {
+        Namespace::MathMl if name.eq_ignore_ascii_case("definitionURL") => {
+            Cow::Borrowed("definitionURL")
         }
+        Namespace::MathMl => ascii_lowercase(name),
+        _ => Cow::Borrowed(name),
     };
-
-    let minified = minify_html_tokens(c_str);
-
-    match CString::new(minified) {
-        Ok(c_string) => c_string.into_raw(),
-        Err(_) => std::ptr::null_mut(),
-    }
-}
-
-#[no_mangle]
-pub unsafe extern "C" fn minify_javascript_string(js_ptr: *const c_char) -> *mut c_char {
-    if js_ptr.is_null() {
-        return std::ptr::null_mut();
-    }
-
-    let c_str = match CStr::from_ptr(js_ptr).to_str() {
-        Ok(s) => s,
-        Err(_) => return std::ptr::null_mut(),
+    let name = name.as_ref();
+    let Some(value) = value else {
+        result.push(' ');
+        result.push_str(name);
+        return;
+    };
+    let value = match namespace {
+        Namespace::Svg => foreign::optimize_svg_attribute(current_tag, name, value, options),
+        _ => Some(Cow::Borrowed(value)),
+    };
+    let Some(value) = value else {
+        return;
     };
 
-    let minified = minify_javascript(c_str);
-
-    match CString::new(minified) {
-        Ok(c_string) => c_string.into_raw(),
-        Err(_) => std::ptr::null_mut(),
-    }
+    let processed_value = if name.eq_ignore_ascii_case("style") {
+        process_style_attribute(&value)
+    } else if name.eq_ignore_ascii_case("class") {
+        let classes = process_class_attribute(&value, class_order);
+        if classes.is_empty() {
+            return;
+        }
+        classes
+    } else {
+        Cow::Borrowed(value.as_ref())
+    };
+    push_attribute_value(result, name, &processed_value);
 }
 
-#[no_mangle]
-pub unsafe extern "C" fn free_string(ptr: *mut c_char) {
-    if !ptr.is_null() {
-        let _ = CString::from_raw(ptr);
+/// Writes ` name=value`, quoting the value only if necessary and switching
+/// to single quotes around values that contain double quotes.
+fn push_attribute_value(result: &mut String, name: &str, value: &str) {
+    result.push(' ');
+    result.push_str(name);
+    result.push('=');
+
+    if should_remove_quotes(value) {
+        result.push_str(value);
+    } else if !value.contains('"') {
+        result.push('"');
+        result.push_str(value);
+        result.push('"');
+    } else if !value.contains('\'') {
+        result.push('\'');
+        result.push_str(value);
+        result.push('\'');
+    } else {
+        result.push('"');
+        result.push_str(&value.replace('"', "&quot;"));
+        result.push('"');
     }
 }
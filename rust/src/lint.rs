@@ -0,0 +1,339 @@
+//! Reports problems in markup without rewriting it.
+//!
+//! The checks run on the crate's own tokenizer, so they flag the places
+//! where the minifier, like a browser, has to guess what the author meant:
+//! comments and quoted values that swallow the rest of the document,
+//! attributes that repeat or split at a space, and end tags that close
+//! nothing or close elements out of order.
+
+use std::collections::HashSet;
+
+use crate::verify::closes_implicitly;
+use crate::{
+    is_boolean_attribute, is_close_optional, is_singleton_element, Attribute, Span, Token,
+    Tokenizer,
+};
+
+// =============================================================================
+// Diagnostics
+// =============================================================================
+
+/// How much a problem matters. Errors change what the document means, as a
+/// browser reads it, from what it appears to say; warnings are recovered
+/// from without surprises.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+/// The kind of problem a [`Diagnostic`] reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiagnosticCode {
+    /// A comment without `-->`, which hides the rest of the document.
+    UnclosedComment,
+    /// A quoted attribute value without its closing quote, which takes in
+    /// the rest of the document.
+    UnclosedAttributeValue,
+    /// An attribute repeated on one tag; browsers keep the first.
+    DuplicateAttribute,
+    /// An unquoted value followed by a word that reads as its continuation
+    /// but becomes an attribute of its own.
+    UnquotedValueWithSpace,
+    /// An end tag with no open element of its name, which browsers ignore.
+    StrayEndTag,
+    /// An end tag closing its element while an element opened inside it,
+    /// whose end tag is required, is still open.
+    MisnestedEndTag,
+    /// An element whose end tag is required but never given.
+    UnclosedElement,
+}
+
+impl DiagnosticCode {
+    /// The kebab-case name used in command-line and JSON output.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DiagnosticCode::UnclosedComment => "unclosed-comment",
+            DiagnosticCode::UnclosedAttributeValue => "unclosed-attribute-value",
+            DiagnosticCode::DuplicateAttribute => "duplicate-attribute",
+            DiagnosticCode::UnquotedValueWithSpace => "unquoted-value-with-space",
+            DiagnosticCode::StrayEndTag => "stray-end-tag",
+            DiagnosticCode::MisnestedEndTag => "misnested-end-tag",
+            DiagnosticCode::UnclosedElement => "unclosed-element",
+        }
+    }
+
+    pub fn severity(self) -> Severity {
+        match self {
+            DiagnosticCode::UnclosedComment
+            | DiagnosticCode::UnclosedAttributeValue
+            | DiagnosticCode::UnquotedValueWithSpace
+            | DiagnosticCode::MisnestedEndTag => Severity::Error,
+            DiagnosticCode::DuplicateAttribute
+            | DiagnosticCode::StrayEndTag
+            | DiagnosticCode::UnclosedElement => Severity::Warning,
+        }
+    }
+}
+
+impl std::fmt::Display for DiagnosticCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// One problem found by [`lint`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: DiagnosticCode,
+    pub message: String,
+    /// The markup at fault: a whole token, or for a split unquoted value
+    /// both of its parts.
+    pub span: Span,
+}
+
+impl Diagnostic {
+    fn new(code: DiagnosticCode, span: Span, message: String) -> Self {
+        Diagnostic {
+            severity: code.severity(),
+            code,
+            message,
+            span,
+        }
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {}[{}]: {}",
+            self.span.position, self.severity, self.code, self.message
+        )
+    }
+}
+
+// =============================================================================
+// Linting
+// =============================================================================
+
+/// An element left open, with the span of its start tag name.
+struct OpenElement {
+    name: String,
+    span: Span,
+}
+
+struct Linter<'a> {
+    html: &'a str,
+    diagnostics: Vec<Diagnostic>,
+    /// Open elements, outermost first.
+    open: Vec<OpenElement>,
+    /// The start tag being parsed.
+    tag: Option<OpenElement>,
+    /// Lowercased names of the attributes seen on that tag.
+    seen_attributes: HashSet<String>,
+    /// The last attribute of the tag, when it has an unquoted value that a
+    /// following bare word may have been meant to continue.
+    unquoted: Option<(&'a str, Span)>,
+    /// Elements closed early by a misnested end tag. Their own end tags
+    /// come later and are not reported again as stray.
+    closed_early: Vec<String>,
+}
+
+impl<'a> Linter<'a> {
+    fn report(&mut self, code: DiagnosticCode, span: Span, message: String) {
+        self.diagnostics.push(Diagnostic::new(code, span, message));
+    }
+
+    fn token(&mut self, token: Token<'a>, span: Span) {
+        match token {
+            Token::TagOpenStart(name) => {
+                self.seen_attributes.clear();
+                self.unquoted = None;
+                self.tag = Some(OpenElement {
+                    name: name.to_ascii_lowercase(),
+                    span,
+                });
+            }
+            Token::Attribute(attr) => self.attribute(attr, span),
+            Token::TagOpenEnd => self.open_element(false),
+            Token::TagSelfClose => self.open_element(true),
+            Token::TagClose(name) => self.close_element(name, span),
+            Token::Comment(_) => {
+                let text = &self.html[span.start..span.end];
+                let closed = text.len() >= "<!---->".len() && text.ends_with("-->");
+                if text.starts_with("<!--") && !closed {
+                    self.report(
+                        DiagnosticCode::UnclosedComment,
+                        span,
+                        "comment is never closed with `-->`, so it hides the rest of the document"
+                            .to_string(),
+                    );
+                }
+            }
+            Token::TextNode(_) | Token::Doctype(_) | Token::Cdata(_) => {}
+        }
+    }
+
+    fn attribute(&mut self, attr: Attribute<'a>, span: Span) {
+        let Some(tag) = &self.tag else {
+            return;
+        };
+        let tag_name = tag.name.clone();
+        let name = attr.name.to_ascii_lowercase();
+
+        if !self.seen_attributes.insert(name.clone()) {
+            self.report(
+                DiagnosticCode::DuplicateAttribute,
+                span,
+                format!(
+                    "duplicate attribute `{}` on <{tag_name}>; only the first is used",
+                    attr.name
+                ),
+            );
+        }
+
+        match attr.value {
+            None => {
+                // Boolean and data attributes are legitimately bare
+                if let Some((value_name, value_span)) = self.unquoted.take() {
+                    if !is_boolean_attribute(&tag_name, &name) && !name.contains('-') {
+                        let span = Span {
+                            end: span.end,
+                            ..value_span
+                        };
+                        self.report(
+                            DiagnosticCode::UnquotedValueWithSpace,
+                            span,
+                            format!(
+                                "the unquoted value of `{value_name}` ends at a space, \
+                                 leaving `{}` as an attribute of its own; quote the value",
+                                attr.name
+                            ),
+                        );
+                    }
+                }
+            }
+            Some(value) if attr.quote.is_none() && value.starts_with(['"', '\'']) => {
+                self.report(
+                    DiagnosticCode::UnclosedAttributeValue,
+                    span,
+                    format!(
+                        "the value of `{}` is never closed with `{}`, so it takes in the rest \
+                         of the document",
+                        attr.name,
+                        &value[..1]
+                    ),
+                );
+                self.unquoted = None;
+            }
+            Some(value) => {
+                self.unquoted =
+                    (attr.quote.is_none() && !value.is_empty()).then_some((attr.name, span));
+            }
+        }
+    }
+
+    fn open_element(&mut self, self_closing: bool) {
+        let Some(element) = self.tag.take() else {
+            return;
+        };
+        while self
+            .open
+            .last()
+            .is_some_and(|open| closes_implicitly(&open.name, &element.name))
+        {
+            self.open.pop();
+        }
+        if !self_closing && !is_singleton_element(&element.name) {
+            self.open.push(element);
+        }
+    }
+
+    fn close_element(&mut self, tag: &str, span: Span) {
+        let tag = tag.split_ascii_whitespace().next().unwrap_or_default();
+        let name = tag.to_ascii_lowercase();
+        let Some(depth) = self.open.iter().rposition(|open| open.name == name) else {
+            if let Some(early) = self.closed_early.iter().rposition(|early| *early == name) {
+                self.closed_early.remove(early);
+                return;
+            }
+            let message = if is_singleton_element(&name) {
+                format!("`</{tag}>` closes nothing: <{name}> is a void element")
+            } else {
+                format!("`</{tag}>` has no open <{name}> to close")
+            };
+            self.report(DiagnosticCode::StrayEndTag, span, message);
+            return;
+        };
+
+        let inner = self.open.split_off(depth + 1);
+        if let Some(unclosed) = inner.iter().find(|open| !is_close_optional(&open.name)) {
+            let message = format!(
+                "`</{tag}>` closes <{name}> while <{}> opened inside it at {} is still open",
+                unclosed.name, unclosed.span.position
+            );
+            self.report(DiagnosticCode::MisnestedEndTag, span, message);
+            self.closed_early
+                .extend(inner.into_iter().map(|open| open.name));
+        }
+        self.open.pop();
+    }
+
+    fn finish(mut self) -> Vec<Diagnostic> {
+        // The end tags of these may be left out, along with the optional
+        // ones the minifier itself omits
+        const END_TAG_OPTIONAL: &[&str] = &["html", "head", "body"];
+
+        for element in std::mem::take(&mut self.open) {
+            if is_close_optional(&element.name) || END_TAG_OPTIONAL.contains(&element.name.as_str())
+            {
+                continue;
+            }
+            let message = format!("<{}> is never closed", element.name);
+            self.report(DiagnosticCode::UnclosedElement, element.span, message);
+        }
+        self.diagnostics
+            .sort_by_key(|diagnostic| diagnostic.span.start);
+        self.diagnostics
+    }
+}
+
+/// Checks `html` for markup problems, returning them in document order.
+///
+/// ```
+/// use html_minifier_ffi::{lint, DiagnosticCode};
+///
+/// let diagnostics = lint("<p title=Hello world>Hi</span>");
+/// let codes: Vec<_> = diagnostics.iter().map(|d| d.code).collect();
+/// assert_eq!(
+///     codes,
+///     [DiagnosticCode::UnquotedValueWithSpace, DiagnosticCode::StrayEndTag]
+/// );
+/// assert_eq!(diagnostics[1].to_string(), "1:24: warning[stray-end-tag]: `</span>` has no open <span> to close");
+/// ```
+pub fn lint(html: &str) -> Vec<Diagnostic> {
+    let mut linter = Linter {
+        html,
+        diagnostics: Vec::new(),
+        open: Vec::new(),
+        tag: None,
+        seen_attributes: HashSet::new(),
+        unquoted: None,
+        closed_early: Vec::new(),
+    };
+    for (token, span) in Tokenizer::new(html) {
+        linter.token(token, span);
+    }
+    linter.finish()
+}
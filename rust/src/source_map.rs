@@ -0,0 +1,237 @@
+//! Source Map v3 output relating minified HTML back to its input.
+//!
+//! The emitter records a mapping at the start of every token it writes, and
+//! the CSS and JavaScript minifiers add one for every token of inline
+//! `<style>` and `<script>` content, so a column in single-line output can be
+//! traced to the line and column of the template it came from.
+
+use memchr::memchr_iter;
+use serde_json::Value;
+
+/// A zero-based line and column. Columns count UTF-16 code units, as source
+/// maps and the Language Server Protocol do.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+    pub line: u32,
+    pub column: u32,
+}
+
+impl std::fmt::Display for Position {
+    /// Formats the position one-based, as editors show it.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line + 1, self.column + 1)
+    }
+}
+
+/// Output from `generated` onwards, up to the next mapping, was produced from
+/// the input at `original`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mapping {
+    pub generated: Position,
+    pub original: Position,
+}
+
+/// The mappings of one minified document, ordered by generated position.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    mappings: Vec<Mapping>,
+}
+
+impl SourceMap {
+    /// Resolves recorded byte offsets against the output and input text.
+    /// Output copied verbatim across several lines, as in `<pre>`, gets a
+    /// mapping at the start of each line, since a mapping never carries over
+    /// to the next line.
+    pub(crate) fn new(offsets: &SourceOffsets, output: &str, input: &str) -> Self {
+        let mut generated = PositionCursor::new(output);
+        let mut original = PositionCursor::new(input);
+        let mut mappings = Vec::with_capacity(offsets.pairs.len());
+
+        let mut add = |generated_offset: usize, original_offset: usize| {
+            mappings.push(Mapping {
+                generated: generated.position_of(generated_offset),
+                original: original.position_of(original_offset),
+            });
+        };
+        let pairs = &offsets.pairs;
+        for (i, &(generated_start, original_start)) in pairs.iter().enumerate() {
+            if generated_start >= output.len() {
+                break;
+            }
+            add(generated_start, original_start);
+
+            let generated_end = pairs.get(i + 1).map_or(output.len(), |&(next, _)| next);
+            let segment = &output.as_bytes()[generated_start..generated_end.min(output.len())];
+            for newline in memchr_iter(b'\n', segment) {
+                let original_newline = original_start + newline;
+                if input.as_bytes().get(original_newline) != Some(&b'\n') {
+                    break;
+                }
+                if generated_start + newline + 1 < generated_end {
+                    add(generated_start + newline + 1, original_newline + 1);
+                }
+            }
+        }
+
+        SourceMap { mappings }
+    }
+
+    pub fn mappings(&self) -> &[Mapping] {
+        &self.mappings
+    }
+
+    /// The input position that the output at `generated` came from: that of
+    /// the last mapping at or before it on the same line.
+    pub fn original_position(&self, generated: Position) -> Option<Position> {
+        let after = self
+            .mappings
+            .partition_point(|mapping| mapping.generated <= generated);
+        let mapping = self.mappings[..after].last()?;
+        (mapping.generated.line == generated.line).then_some(mapping.original)
+    }
+
+    /// Serializes the map as Source Map v3 JSON. `file` names the minified
+    /// output and `source` the input, both usually relative to the map file.
+    /// `source_content` embeds the input so that the map stands alone.
+    pub fn to_json(&self, file: &str, source: &str, source_content: Option<&str>) -> String {
+        let string = |value: &str| Value::from(value).to_string();
+        let mut json = format!(
+            r#"{{"version":3,"file":{},"sources":[{}],"names":[],"mappings":"{}""#,
+            string(file),
+            string(source),
+            self.encode_mappings()
+        );
+        if let Some(content) = source_content {
+            json.push_str(r#","sourcesContent":["#);
+            json.push_str(&string(content));
+            json.push(']');
+        }
+        json.push('}');
+        json
+    }
+
+    /// Encodes the `mappings` field: lines separated by `;`, segments by
+    /// `,`, each segment a run of Base64 VLQ deltas from the previous one.
+    fn encode_mappings(&self) -> String {
+        let mut encoded = String::with_capacity(self.mappings.len() * 6);
+        let mut line = 0;
+        let mut previous_column = 0;
+        let mut previous_original = Position::default();
+        for mapping in &self.mappings {
+            if mapping.generated.line > line {
+                for _ in line..mapping.generated.line {
+                    encoded.push(';');
+                }
+                line = mapping.generated.line;
+                previous_column = 0;
+            } else if !encoded.is_empty() && !encoded.ends_with(';') {
+                encoded.push(',');
+            }
+
+            encode_vlq(
+                &mut encoded,
+                delta(mapping.generated.column, previous_column),
+            );
+            // Every mapping refers to the one source
+            encode_vlq(&mut encoded, 0);
+            encode_vlq(
+                &mut encoded,
+                delta(mapping.original.line, previous_original.line),
+            );
+            encode_vlq(
+                &mut encoded,
+                delta(mapping.original.column, previous_original.column),
+            );
+            previous_column = mapping.generated.column;
+            previous_original = mapping.original;
+        }
+        encoded
+    }
+}
+
+fn delta(value: u32, previous: u32) -> i64 {
+    i64::from(value) - i64::from(previous)
+}
+
+fn encode_vlq(out: &mut String, value: i64) {
+    const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    // The sign moves to the lowest bit, then 5 bits go in each digit with
+    // the sixth flagging a continuation
+    let mut rest = (value.unsigned_abs() << 1) | u64::from(value < 0);
+    loop {
+        let digit = (rest & 0b11111) as usize;
+        rest >>= 5;
+        if rest == 0 {
+            out.push(BASE64[digit] as char);
+            return;
+        }
+        out.push(BASE64[digit | 0b100000] as char);
+    }
+}
+
+/// Byte offsets recorded while minifying: each pair is an offset in the
+/// output and the input offset it was produced from, in output order.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SourceOffsets {
+    pairs: Vec<(usize, usize)>,
+}
+
+impl SourceOffsets {
+    /// Records that output from `generated` on comes from `original`. A
+    /// token that wrote nothing is superseded by the next one, which starts
+    /// at the same output offset.
+    pub(crate) fn add(&mut self, generated: usize, original: usize) {
+        match self.pairs.last_mut() {
+            Some(last) if last.0 == generated => last.1 = original,
+            _ => self.pairs.push((generated, original)),
+        }
+    }
+}
+
+/// Converts byte offsets of one text to positions. Offsets mostly increase,
+/// so it scans on from the previous one and only steps back to the start of
+/// a line when asked for an earlier offset.
+pub(crate) struct PositionCursor<'a> {
+    text: &'a str,
+    line_starts: Vec<usize>,
+    offset: usize,
+    position: Position,
+}
+
+impl<'a> PositionCursor<'a> {
+    pub(crate) fn new(text: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(memchr_iter(b'\n', text.as_bytes()).map(|newline| newline + 1))
+            .collect();
+        Self {
+            text,
+            line_starts,
+            offset: 0,
+            position: Position::default(),
+        }
+    }
+
+    pub(crate) fn position_of(&mut self, offset: usize) -> Position {
+        let offset = offset.min(self.text.len());
+        if offset < self.offset {
+            let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+            self.offset = self.line_starts[line];
+            self.position = Position {
+                line: line as u32,
+                column: 0,
+            };
+        }
+
+        for ch in self.text[self.offset..offset].chars() {
+            if ch == '\n' {
+                self.position.line += 1;
+                self.position.column = 0;
+            } else {
+                self.position.column += ch.len_utf16() as u32;
+            }
+        }
+        self.offset = offset;
+        self.position
+    }
+}
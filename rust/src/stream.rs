@@ -0,0 +1,194 @@
+//! Incremental HTML minification over `std::io`.
+
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+
+use crate::{
+    FrequencyOrder, HtmlEmitter, MinifyOptions, MinifyReport, MinifyWarning, Tokenizer,
+    TokenizerState,
+};
+
+/// Size of the chunks [`Minifier::read_from`] reads at a time.
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Minifies HTML pushed to it in chunks, writing output as soon as it is
+/// known. Only the unfinished tail of the input is held between chunks: a
+/// partial tag, word or UTF-8 sequence, the end of an open comment, or the
+/// content of an open `<script>`/`<style>` element while its end tag is
+/// awaited. Each chunk is scanned once, however long that tail grows.
+///
+/// Memory is therefore not bounded by the chunk size: script and style
+/// bodies are minified whole, so it grows with the largest of them, as it
+/// does with the longest tag or run of text without whitespace.
+///
+/// Without the whole document up front, class names and attributes are
+/// sorted by [`MinifyOptions::class_name_order`] only, not by document
+/// frequency.
+pub struct Minifier<'o, W: Write> {
+    writer: W,
+    emitter: HtmlEmitter<'o>,
+    state: TokenizerState,
+    /// Input not yet minified, starting with an unfinished token.
+    buffer: String,
+    /// How much of `buffer` has been scanned for the end of that token.
+    scanned: usize,
+    /// The start of a UTF-8 sequence split across chunks.
+    partial: Vec<u8>,
+    emitted: String,
+}
+
+/// What a [`Minifier`] hands back once all output has been written.
+#[derive(Debug)]
+pub struct StreamOutput<W> {
+    pub writer: W,
+    pub warnings: Vec<MinifyWarning>,
+    /// Present when [`MinifyOptions::collect_report`] is set.
+    pub report: Option<MinifyReport>,
+}
+
+impl<'o, W: Write> Minifier<'o, W> {
+    pub fn new(writer: W, options: &'o MinifyOptions) -> Self {
+        let class_order = options
+            .sort_class_names
+            .then(|| FrequencyOrder::new(&options.class_name_order, HashMap::new()));
+        let attribute_order = options
+            .sort_attributes
+            .then(|| FrequencyOrder::new(&[], HashMap::new()));
+
+        // Source maps need the whole output, which a stream never holds
+        let mut emitter = HtmlEmitter::new(options, class_order, attribute_order);
+        emitter.offsets = None;
+
+        Self {
+            writer,
+            emitter,
+            state: TokenizerState::default(),
+            buffer: String::new(),
+            scanned: 0,
+            partial: Vec::new(),
+            emitted: String::new(),
+        }
+    }
+
+    /// Feeds the next chunk of input. Chunks may split tags, entities and
+    /// UTF-8 sequences anywhere.
+    pub fn push(&mut self, chunk: &[u8]) -> io::Result<()> {
+        let chunk = if self.partial.is_empty() {
+            chunk
+        } else {
+            self.partial.extend_from_slice(chunk);
+            &std::mem::take(&mut self.partial)
+        };
+        match std::str::from_utf8(chunk) {
+            Ok(text) => self.buffer.push_str(text),
+            // A multi-byte sequence split across chunks completes later
+            Err(err) if err.error_len().is_none() => {
+                let (valid, rest) = chunk.split_at(err.valid_up_to());
+                self.buffer
+                    .push_str(std::str::from_utf8(valid).expect("prefix is valid UTF-8"));
+                self.partial = rest.to_vec();
+            }
+            Err(err) => return Err(io::Error::new(io::ErrorKind::InvalidData, err)),
+        }
+        self.process(false)
+    }
+
+    /// Feeds everything `reader` yields, returning the number of bytes read.
+    pub fn read_from<R: Read>(&mut self, mut reader: R) -> io::Result<u64> {
+        let mut chunk = vec![0; READ_CHUNK_SIZE];
+        let mut total = 0;
+        loop {
+            match reader.read(&mut chunk) {
+                Ok(0) => return Ok(total),
+                Ok(n) => {
+                    self.push(&chunk[..n])?;
+                    total += n as u64;
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Processes the remaining input and flushes the writer.
+    pub fn finish(mut self) -> io::Result<StreamOutput<W>> {
+        self.process(true)?;
+        self.emitter.finish(&mut self.emitted);
+        self.write_emitted()?;
+        self.writer.flush()?;
+
+        Ok(StreamOutput {
+            writer: self.writer,
+            warnings: self.emitter.warnings,
+            report: self.emitter.report,
+        })
+    }
+
+    fn process(&mut self, is_final: bool) -> io::Result<()> {
+        if is_final && !self.partial.is_empty() {
+            let err = std::str::from_utf8(&self.partial).expect_err("sequence is incomplete");
+            return Err(io::Error::new(io::ErrorKind::InvalidData, err));
+        }
+
+        let mut tokenizer = Tokenizer::resume(&self.buffer, self.state);
+        tokenizer.scanned = self.scanned;
+        let consumed = self
+            .emitter
+            .drive(&mut tokenizer, &mut self.emitted, is_final);
+        self.state = tokenizer.state();
+        self.buffer.drain(..consumed);
+
+        if let Some(report) = self.emitter.report.as_mut() {
+            report.input_bytes += consumed as u64;
+        }
+        self.drop_comment_body();
+        self.scanned = self.buffer.len();
+
+        self.write_emitted()
+    }
+
+    /// Drops the body of a comment still awaiting its `-->`, as the
+    /// comment is removed anyway. The last bytes are kept in case they
+    /// start the `-->`.
+    fn drop_comment_body(&mut self) {
+        const OPEN: &str = "<!--";
+        if self.state.in_tag || self.state.in_raw_text || !self.buffer.starts_with(OPEN) {
+            return;
+        }
+        let Some(end) = (OPEN.len()..self.buffer.len().saturating_sub(2))
+            .rev()
+            .find(|&end| self.buffer.is_char_boundary(end))
+        else {
+            return;
+        };
+        self.buffer.replace_range(OPEN.len()..end, "");
+
+        if let Some(report) = self.emitter.report.as_mut() {
+            let dropped = (end - OPEN.len()) as u64;
+            report.input_bytes += dropped;
+            report.bytes_removed.comments += dropped;
+        }
+    }
+
+    fn write_emitted(&mut self) -> io::Result<()> {
+        if let Some(report) = self.emitter.report.as_mut() {
+            report.output_bytes += self.emitted.len() as u64;
+        }
+        self.writer.write_all(self.emitted.as_bytes())?;
+        self.emitted.clear();
+        Ok(())
+    }
+}
+
+/// Minifies everything `reader` yields into `writer` without holding the
+/// whole document in memory, only the largest `<script>` or `<style>` body
+/// at most; see [`Minifier`].
+pub fn minify_stream<R: Read, W: Write>(
+    reader: R,
+    writer: W,
+    options: &MinifyOptions,
+) -> io::Result<StreamOutput<W>> {
+    let mut minifier = Minifier::new(writer, options);
+    minifier.read_from(reader)?;
+    minifier.finish()
+}
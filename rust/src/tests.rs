@@ -0,0 +1,950 @@
+//! Unit tests of the crate's internals: tokenizer conformance against the
+//! html5lib-tests suite and cases of our own in its format, kept in
+//! `tests/fixtures/html5lib/`, the rules applied to attributes, the C entry
+//! points and configuration files.
+
+use serde_json::{json, Map, Value};
+
+use super::*;
+
+const FIXTURES: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/html5lib/tokenizer.test"
+);
+
+/// The tokenizer files of html5lib-tests, vendored by `update.sh` from the
+/// commit named in `upstream/REVISION`.
+const UPSTREAM_FIXTURES: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/html5lib/upstream"
+);
+
+/// Upstream files that are not run. `xmlViolation.test` needs no entry: its
+/// cases sit under `xmlViolationTests` rather than `tests`.
+const SKIPPED_FILES: &[&str] = &[
+    // Character references are left as written for the browser to decode,
+    // and these files test nothing but decoding them
+    "entities.test",
+    "namedEntities.test",
+    "numericEntities.test",
+];
+
+/// Upstream cases the tokenizer is known to fail, as file and description,
+/// each with a comment on why. A listed case that passes fails the run, so
+/// the list only shrinks when the tokenizer gets better.
+const KNOWN_FAILURES: &[(&str, &str)] = &[];
+
+/// Tokenizes `input` and converts the tokens to html5lib's JSON notation:
+/// names lowercased, the first of repeated attributes kept and adjacent
+/// character tokens merged.
+fn html5lib_tokens(input: &str, raw_text_element: Option<&'static str>) -> Vec<Value> {
+    let mut tokenizer = Tokenizer::resume(
+        input,
+        TokenizerState {
+            in_tag: false,
+            raw_text_element,
+            in_raw_text: raw_text_element.is_some(),
+        },
+    );
+
+    let mut tokens: Vec<Value> = Vec::new();
+    let mut start_tag: Option<(String, Map<String, Value>)> = None;
+    while let Some(token) = tokenizer.next_token() {
+        if let Token::Attribute(attr) = token {
+            if let Some((_, attributes)) = start_tag.as_mut() {
+                attributes
+                    .entry(attr.name.to_ascii_lowercase())
+                    .or_insert_with(|| json!(attr.value.unwrap_or_default()));
+            }
+            continue;
+        }
+        if let Some((name, attributes)) = start_tag.take() {
+            if token == Token::TagSelfClose {
+                tokens.push(json!(["StartTag", name, attributes, true]));
+                continue;
+            }
+            tokens.push(json!(["StartTag", name, attributes]));
+        }
+
+        match token {
+            Token::TagOpenStart(name) => start_tag = Some((name.to_ascii_lowercase(), Map::new())),
+            Token::TagClose(name) => {
+                let name = name.split_ascii_whitespace().next().unwrap_or_default();
+                tokens.push(json!(["EndTag", name.to_ascii_lowercase()]));
+            }
+            Token::TextNode(text) => match tokens.last_mut() {
+                Some(Value::Array(last)) if last[0] == "Character" => {
+                    let merged = format!("{}{text}", last[1].as_str().unwrap_or_default());
+                    last[1] = json!(merged);
+                }
+                _ => tokens.push(json!(["Character", text])),
+            },
+            Token::Comment(text) => tokens.push(json!(["Comment", text])),
+            Token::Doctype(doctype) => {
+                let name = doctype["<!DOCTYPE".len()..]
+                    .trim_end_matches('>')
+                    .split_ascii_whitespace()
+                    .next()
+                    .map(str::to_ascii_lowercase);
+                tokens.push(json!(["DOCTYPE", name, null, null, true]));
+            }
+            Token::Cdata(text) => tokens.push(json!(["Character", text])),
+            Token::Attribute(_) | Token::TagOpenEnd | Token::TagSelfClose => {}
+        }
+    }
+    if let Some((name, attributes)) = start_tag {
+        tokens.push(json!(["StartTag", name, attributes]));
+    }
+    tokens
+}
+
+/// Undoes the `\uXXXX` escapes of a `doubleEscaped` case, or returns `None`
+/// for a lone surrogate, which a Rust string cannot hold.
+fn unescape_html5lib(text: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(index) = rest.find("\\u") {
+        unescaped.push_str(&rest[..index]);
+        let code = rest.get(index + 2..index + 6)?;
+        unescaped.push(char::from_u32(u32::from_str_radix(code, 16).ok()?)?);
+        rest = &rest[index + 6..];
+    }
+    unescaped.push_str(rest);
+    Some(unescaped)
+}
+
+/// Applies [`unescape_html5lib`] to every string in an expected output.
+fn unescape_html5lib_value(value: &Value) -> Option<Value> {
+    Some(match value {
+        Value::String(text) => Value::String(unescape_html5lib(text)?),
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(unescape_html5lib_value)
+                .collect::<Option<_>>()?,
+        ),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, field)| {
+                    Some((unescape_html5lib(key)?, unescape_html5lib_value(field)?))
+                })
+                .collect::<Option<_>>()?,
+        ),
+        value => value.clone(),
+    })
+}
+
+/// Runs the cases of an html5lib-tests tokenizer file, returning how many
+/// ran and the description of each failure with what went wrong.
+///
+/// Cases start in the data state or in the raw text of one of
+/// [`RAW_TEXT_ELEMENTS`]; those starting elsewhere, such as in the PLAINTEXT
+/// state or after an element the tokenizer does not treat as raw text, are
+/// not run. Parse errors are not reported by the tokenizer and not compared.
+fn run_html5lib_fixtures(source: &str) -> (usize, Vec<(String, String)>) {
+    let fixtures: Value = serde_json::from_str(source).expect("fixture file is valid JSON");
+    let cases = fixtures["tests"].as_array().map_or(&[][..], Vec::as_slice);
+
+    let mut ran = 0;
+    let mut failures = Vec::new();
+    for case in cases {
+        let description = case["description"].as_str().unwrap_or_default();
+        let mut input = case["input"]
+            .as_str()
+            .expect("every case has an input")
+            .to_string();
+        // Older files list parse errors among the tokens
+        let mut expected = case["output"].clone();
+        if let Value::Array(tokens) = &mut expected {
+            tokens.retain(|token| token != "ParseError");
+        }
+        if case["doubleEscaped"] == true {
+            match (
+                unescape_html5lib(&input),
+                unescape_html5lib_value(&expected),
+            ) {
+                (Some(unescaped), Some(output)) => (input, expected) = (unescaped, output),
+                _ => continue,
+            }
+        }
+        let raw_text_element = case["lastStartTag"]
+            .as_str()
+            .and_then(|tag| RAW_TEXT_ELEMENTS.iter().copied().find(|&raw| raw == tag));
+        let states = case["initialStates"]
+            .as_array()
+            .map_or(vec!["Data state"], |states| {
+                states.iter().filter_map(Value::as_str).collect()
+            });
+
+        for state in states {
+            let element = match state {
+                "Data state" => None,
+                "RCDATA state" | "RAWTEXT state" | "Script data state" => match raw_text_element {
+                    Some(element) => Some(element),
+                    None => continue,
+                },
+                _ => continue,
+            };
+            ran += 1;
+            let actual = Value::Array(html5lib_tokens(&input, element));
+            if actual != expected {
+                failures.push((
+                    description.to_string(),
+                    format!("{description} ({state}): expected {expected}, got {actual}"),
+                ));
+            }
+        }
+    }
+    (ran, failures)
+}
+
+#[test]
+fn html5lib_tokenizer_fixtures() {
+    let source = std::fs::read_to_string(FIXTURES).expect("fixture file is readable");
+    let (ran, failures) = run_html5lib_fixtures(&source);
+
+    assert!(ran > 20, "fixture file lost its cases");
+    let messages: Vec<_> = failures.into_iter().map(|(_, message)| message).collect();
+    assert!(messages.is_empty(), "{}", messages.join("\n"));
+}
+
+#[test]
+fn html5lib_upstream_tokenizer_tests() {
+    // Nothing to run until update.sh has vendored the suite
+    let Ok(entries) = std::fs::read_dir(UPSTREAM_FIXTURES) else {
+        return;
+    };
+    let mut files: Vec<_> = entries
+        .map(|entry| entry.expect("fixture directory is readable").path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "test")
+        })
+        .collect();
+    files.sort();
+
+    let mut unexpected = Vec::new();
+    let mut fixed = Vec::new();
+    for path in files {
+        let file = path.file_name().unwrap().to_str().unwrap();
+        if SKIPPED_FILES.contains(&file) {
+            continue;
+        }
+        let source = std::fs::read_to_string(&path).expect("fixture file is readable");
+        let (_, failures) = run_html5lib_fixtures(&source);
+        for (description, message) in &failures {
+            if !KNOWN_FAILURES.contains(&(file, description.as_str())) {
+                unexpected.push(format!("{file}: {message}"));
+            }
+        }
+        fixed.extend(
+            KNOWN_FAILURES
+                .iter()
+                .filter(|(known, description)| {
+                    *known == file && !failures.iter().any(|(failed, _)| failed == description)
+                })
+                .map(|(_, description)| format!("{file}: {description}")),
+        );
+    }
+
+    assert!(unexpected.is_empty(), "{}", unexpected.join("\n"));
+    assert!(
+        fixed.is_empty(),
+        "known failures that now pass:\n{}",
+        fixed.join("\n")
+    );
+}
+
+// =============================================================================
+// Enumerated Attributes
+// =============================================================================
+
+#[test]
+fn enumerated_values_are_lowercased_for_listed_attributes() {
+    use EnumeratedValue::{Default, Empty, Keyword};
+    assert_eq!(
+        normalize_enumerated_value("form", "method", "POST"),
+        Some(Keyword("post"))
+    );
+    assert_eq!(
+        normalize_enumerated_value("form", "method", "Get"),
+        Some(Default)
+    );
+    assert_eq!(
+        normalize_enumerated_value("img", "crossorigin", "Anonymous"),
+        Some(Empty)
+    );
+    assert_eq!(
+        normalize_enumerated_value("p", "dir", "RTL"),
+        Some(Keyword("rtl"))
+    );
+
+    // Unknown keywords, other elements and other attributes are left alone
+    assert_eq!(normalize_enumerated_value("form", "method", "PUT"), None);
+    assert_eq!(normalize_enumerated_value("div", "method", "POST"), None);
+    assert_eq!(normalize_enumerated_value("form", "action", "POST"), None);
+    assert_eq!(normalize_enumerated_value("div", "loading", "LAZY"), None);
+}
+
+#[test]
+fn only_enumerated_values_change_case() {
+    assert_eq!(
+        minify_html_tokens(
+            "<form method=\"POST\" action=\"/Submit\"><input name=\"Q\" value=\"POST\">\
+             <p dir=\"RTL\" title=\"RTL\"><img loading=\"LAZY\" alt=\"LAZY\" crossorigin=\"\">"
+        ),
+        "<form method=post action=/Submit><input name=Q value=POST>\
+         <p dir=rtl title=RTL><img loading=lazy alt=LAZY crossorigin>"
+    );
+}
+
+// =============================================================================
+// Boolean Attributes
+// =============================================================================
+
+#[test]
+fn boolean_attributes_are_scoped_by_element() {
+    assert!(is_boolean_attribute("video", "muted"));
+    assert!(is_boolean_attribute("div", "inert"));
+    assert!(!is_boolean_attribute("div", "muted"));
+    assert!(!is_boolean_attribute("p", "hidden"));
+    assert!(!is_boolean_attribute("video", "title"));
+}
+
+#[test]
+fn only_boolean_attributes_lose_their_values() {
+    assert_eq!(
+        minify_html_tokens(
+            "<video muted=\"muted\" title=\"muted\"></video><div muted=\"muted\"></div>\
+             <option SELECTED=\"Selected\"><p hidden=\"until-found\">"
+        ),
+        "<video muted title=muted></video><div muted=muted></div>\
+         <option selected><p hidden=until-found>"
+    );
+
+    let custom = "<x-list selected=\"false\" inert=\"inert\"></x-list>";
+    assert_eq!(
+        minify_html_tokens(custom),
+        "<x-list selected=false inert=inert></x-list>"
+    );
+    let options = MinifyOptions {
+        collapse_custom_element_booleans: true,
+        ..MinifyOptions::default()
+    };
+    assert_eq!(
+        minify_html_with_options(custom, &options),
+        "<x-list selected=false inert></x-list>"
+    );
+}
+
+// =============================================================================
+// Class Lists
+// =============================================================================
+
+#[test]
+fn class_lists_are_trimmed_and_deduplicated() {
+    assert_eq!(process_class_attribute("a b", None), "a b");
+    assert_eq!(process_class_attribute("  b\ta\n b  ", None), "b a");
+    assert_eq!(process_class_attribute("a\tb", None), "a b");
+    assert_eq!(process_class_attribute("", None), "");
+    assert_eq!(process_class_attribute(" \t\n ", None), "");
+
+    assert_eq!(
+        minify_html_tokens("<p class=\"\">a<p class=\" \t\">b<p class=\"x  y x\">c"),
+        "<p>a<p>b<p class=\"x y\">c"
+    );
+}
+
+#[test]
+fn class_lists_sort_by_frequency_when_asked() {
+    let html = "<p class=\"b a c\"><p class=\"c a\"><p class=\"a\">";
+    assert_eq!(
+        minify_html_tokens(html),
+        "<p class=\"b a c\"><p class=\"c a\"><p class=a>"
+    );
+
+    let options = MinifyOptions {
+        sort_class_names: true,
+        ..MinifyOptions::default()
+    };
+    assert_eq!(
+        minify_html_with_options(html, &options),
+        "<p class=\"a c b\"><p class=\"a c\"><p class=a>"
+    );
+
+    // A given order ranks ahead of the document's frequencies
+    let options = MinifyOptions {
+        class_name_order: vec!["b".to_string()],
+        ..options
+    };
+    assert_eq!(
+        minify_html_with_options(html, &options),
+        "<p class=\"b a c\"><p class=\"a c\"><p class=a>"
+    );
+}
+
+// =============================================================================
+// Attribute Sorting
+// =============================================================================
+
+#[test]
+fn sorting_attributes_only_reorders_them() {
+    let html = "<a href=\"/y\" class=\"x\">1</a><a title=\"t\" class=\"x\" href=\"/z\">2</a>\
+                <a id=\"i\" class=\"x\" CLASS=\"z\" class=\"w\">3</a><x-a href=\"/\" class=\"x\"></x-a>";
+    let unsorted = minify_html_tokens(html);
+    assert_eq!(
+        unsorted,
+        "<a href=/y class=x>1</a><a title=t class=x href=/z>2</a>\
+         <a id=i class=x>3</a><x-a href=/ class=x></x-a>"
+    );
+
+    // The first of repeated attributes is kept before sorting, and custom
+    // elements keep their order
+    let options = MinifyOptions {
+        sort_attributes: true,
+        ..MinifyOptions::default()
+    };
+    let sorted = minify_html_with_options(html, &options);
+    assert_eq!(
+        sorted,
+        "<a class=x href=/y>1</a><a class=x href=/z title=t>2</a>\
+         <a class=x id=i>3</a><x-a href=/ class=x></x-a>"
+    );
+
+    let mut unsorted_bytes = unsorted.into_bytes();
+    let mut sorted_bytes = sorted.into_bytes();
+    unsorted_bytes.sort_unstable();
+    sorted_bytes.sort_unstable();
+    assert_eq!(sorted_bytes, unsorted_bytes);
+}
+
+// =============================================================================
+// Duplicate Attributes
+// =============================================================================
+
+#[test]
+fn attribute_names_match_case_insensitively() {
+    let mut names = AttributeNames::default();
+    names.insert("Class");
+    names.insert("data-X");
+    assert!(names.contains("class"));
+    assert!(names.contains("CLASS"));
+    assert!(names.contains("data-x"));
+    assert!(!names.contains("data"));
+    assert!(!names.contains("id"));
+
+    names.clear();
+    assert!(!names.contains("class"));
+}
+
+#[test]
+fn duplicate_attributes_keep_the_first_in_any_case() {
+    let options = MinifyOptions {
+        warn_duplicate_attributes: true,
+        ..MinifyOptions::default()
+    };
+    let output = minify_html(
+        "<div ID=\"a\" class=\"x\" id=\"b\" CLASS=\"y\"></div>",
+        &options,
+    );
+    assert_eq!(output.html, "<div id=a class=x></div>");
+    assert_eq!(
+        output.warnings,
+        [
+            MinifyWarning::DuplicateAttribute {
+                tag: "div".to_string(),
+                attribute: "id".to_string(),
+            },
+            MinifyWarning::DuplicateAttribute {
+                tag: "div".to_string(),
+                attribute: "class".to_string(),
+            },
+        ]
+    );
+}
+
+// =============================================================================
+// FFI Entry Points
+// =============================================================================
+
+/// Signature of the length-delimited entry points such as
+/// [`minify_html_bytes`].
+type BytesFn = unsafe extern "C" fn(
+    *const std::os::raw::c_char,
+    usize,
+    *mut *mut std::os::raw::c_char,
+    *mut usize,
+) -> MinifyStatus;
+
+/// Passes `input` to a length-delimited entry point, returning a copy of
+/// the output buffer after freeing it, or the failing status.
+fn call_bytes(minify: BytesFn, input: &[u8]) -> Result<Vec<u8>, MinifyStatus> {
+    let mut out_ptr = std::ptr::null_mut();
+    let mut out_len = 0;
+    let status = unsafe {
+        minify(
+            input.as_ptr().cast(),
+            input.len(),
+            &mut out_ptr,
+            &mut out_len,
+        )
+    };
+    if status != MinifyStatus::Ok {
+        return Err(status);
+    }
+    let output = unsafe { std::slice::from_raw_parts(out_ptr as *const u8, out_len) }.to_vec();
+    unsafe { free_buffer(out_ptr, out_len) };
+    Ok(output)
+}
+
+#[test]
+fn byte_buffers_keep_embedded_nul_bytes() {
+    assert_eq!(
+        call_bytes(minify_html_bytes, b"<p>  a\0b  </p>\0<!-- c -->"),
+        Ok(b"<p>a\0b\0".to_vec())
+    );
+    assert_eq!(
+        call_bytes(minify_css_bytes, b"a{content: \"\0\"}"),
+        Ok(b"a{content:\"\0\"}".to_vec())
+    );
+    assert_eq!(
+        call_bytes(minify_javascript_bytes, b"x = '\0' ;"),
+        Ok(b"x='\0';".to_vec())
+    );
+
+    // An empty input may come with a null pointer
+    let mut out_ptr = std::ptr::null_mut();
+    let mut out_len = 1;
+    let status = unsafe { minify_html_bytes(std::ptr::null(), 0, &mut out_ptr, &mut out_len) };
+    assert_eq!((status, out_len), (MinifyStatus::Ok, 0));
+    unsafe { free_buffer(out_ptr, out_len) };
+}
+
+/// The calling thread's last error message, or `None` after a success.
+fn last_error() -> Option<String> {
+    let message = html_minifier_last_error();
+    (!message.is_null()).then(|| {
+        unsafe { std::ffi::CStr::from_ptr(message) }
+            .to_string_lossy()
+            .into_owned()
+    })
+}
+
+#[test]
+fn failures_return_their_status_and_set_the_last_error() {
+    let mut out_ptr = std::ptr::null_mut();
+    let mut out_len = 0;
+
+    let status = unsafe { minify_html_bytes(std::ptr::null(), 3, &mut out_ptr, &mut out_len) };
+    assert_eq!(status, MinifyStatus::NullPointer);
+    assert_eq!(html_minifier_last_status(), MinifyStatus::NullPointer);
+    assert_eq!(last_error().as_deref(), Some("input pointer is null"));
+
+    let input = b"<p>";
+    let status = unsafe {
+        minify_html_bytes(
+            input.as_ptr().cast(),
+            input.len(),
+            std::ptr::null_mut(),
+            &mut out_len,
+        )
+    };
+    assert_eq!(status, MinifyStatus::NullPointer);
+    assert_eq!(last_error().as_deref(), Some("output pointer is null"));
+
+    assert_eq!(
+        call_bytes(minify_css_bytes, b"a{content:\"\xff\"}"),
+        Err(MinifyStatus::InvalidUtf8)
+    );
+    assert_eq!(html_minifier_last_status(), MinifyStatus::InvalidUtf8);
+    assert!(last_error()
+        .unwrap()
+        .starts_with("input is not valid UTF-8: "));
+
+    // A success clears the error
+    assert_eq!(call_bytes(minify_html_bytes, b"<p>"), Ok(b"<p>".to_vec()));
+    assert_eq!(html_minifier_last_status(), MinifyStatus::Ok);
+    assert_eq!(last_error(), None);
+}
+
+#[test]
+fn string_entry_points_return_null_on_failure() {
+    assert!(unsafe { minify_html_string(std::ptr::null()) }.is_null());
+    assert_eq!(html_minifier_last_status(), MinifyStatus::NullPointer);
+    assert_eq!(last_error().as_deref(), Some("input pointer is null"));
+
+    let invalid = std::ffi::CString::new(b"<p>\xc3(".to_vec()).unwrap();
+    assert!(unsafe { minify_javascript_string(invalid.as_ptr()) }.is_null());
+    assert_eq!(html_minifier_last_status(), MinifyStatus::InvalidUtf8);
+    assert_eq!(last_error().as_deref(), Some("input is not valid UTF-8"));
+
+    let html = std::ffi::CString::new("<p>  a  </p>").unwrap();
+    let output = unsafe { minify_html_string(html.as_ptr()) };
+    assert_eq!(
+        unsafe { std::ffi::CStr::from_ptr(output) }.to_str(),
+        Ok("<p>a")
+    );
+    assert_eq!(last_error(), None);
+    unsafe { free_string(output) };
+}
+
+#[test]
+fn status_codes_keep_their_documented_values() {
+    let codes = [
+        (MinifyStatus::Ok, 0),
+        (MinifyStatus::NullPointer, 1),
+        (MinifyStatus::InvalidUtf8, 2),
+        (MinifyStatus::InteriorNul, 3),
+        (MinifyStatus::Panic, 4),
+        (MinifyStatus::UnknownOption, 5),
+        (MinifyStatus::InvalidOption, 6),
+        (MinifyStatus::VerificationFailed, 7),
+    ];
+    for (status, code) in codes {
+        assert_eq!(status as i32, code, "{status:?}");
+    }
+}
+
+#[test]
+fn config_handles_set_options_for_minify_html_with_config() {
+    let name = |name: &str| std::ffi::CString::new(name).unwrap();
+    let html = b"<x-a class=\"b a\" inert=inert></x-a>";
+    let minify = |config: *const MinifierConfig| {
+        let mut out_ptr = std::ptr::null_mut();
+        let mut out_len = 0;
+        let status = unsafe {
+            minify_html_with_config(
+                config,
+                html.as_ptr().cast(),
+                html.len(),
+                &mut out_ptr,
+                &mut out_len,
+            )
+        };
+        assert_eq!(status, MinifyStatus::Ok);
+        let output = unsafe { std::slice::from_raw_parts(out_ptr as *const u8, out_len) };
+        let output = String::from_utf8(output.to_vec()).unwrap();
+        unsafe { free_buffer(out_ptr, out_len) };
+        output
+    };
+
+    let config = minifier_config_new();
+    unsafe {
+        assert_eq!(
+            minifier_config_set_bool(config, name("sort_class_names").as_ptr(), true),
+            MinifyStatus::Ok
+        );
+        assert_eq!(
+            minifier_config_set_str(
+                config,
+                name("collapse_custom_element_booleans").as_ptr(),
+                name("1").as_ptr()
+            ),
+            MinifyStatus::Ok
+        );
+    }
+    assert_eq!(minify(config), "<x-a class=\"a b\" inert></x-a>");
+    assert_eq!(
+        minify(std::ptr::null()),
+        "<x-a class=\"b a\" inert=inert></x-a>"
+    );
+
+    unsafe {
+        assert_eq!(
+            minifier_config_set_bool(config, name("no_such_option").as_ptr(), true),
+            MinifyStatus::UnknownOption
+        );
+        assert!(last_error().unwrap().contains("no_such_option"));
+        assert_eq!(
+            minifier_config_set_str(config, name("svg_precision").as_ptr(), name("two").as_ptr()),
+            MinifyStatus::InvalidOption
+        );
+        assert!(last_error().unwrap().contains("two"));
+        assert_eq!(
+            minifier_config_set_bool(std::ptr::null_mut(), name("minify_svg").as_ptr(), true),
+            MinifyStatus::NullPointer
+        );
+        assert_eq!(
+            minifier_config_set_bool(config, std::ptr::null(), true),
+            MinifyStatus::NullPointer
+        );
+        minifier_config_free(config);
+    }
+}
+
+#[test]
+fn minify_css_string_returns_minified_css() {
+    let css = std::ffi::CString::new("a{color: red;\n  margin:0}").unwrap();
+    let output = unsafe { minify_css_string(css.as_ptr()) };
+    assert_eq!(
+        unsafe { std::ffi::CStr::from_ptr(output) }.to_str(),
+        Ok("a{color:red;margin:0}")
+    );
+    unsafe { free_string(output) };
+}
+
+#[test]
+fn version_exports_match_the_generated_header() {
+    let header = std::fs::read_to_string(concat!(env!("OUT_DIR"), "/html_minifier.h"))
+        .expect("the build script generates the header");
+    let define = format!(
+        "#define HTML_MINIFIER_ABI_VERSION {}",
+        html_minifier_abi_version()
+    );
+    assert!(header.lines().any(|line| line == define), "{header}");
+    assert_eq!(html_minifier_abi_version(), HTML_MINIFIER_ABI_VERSION);
+
+    let version = unsafe { std::ffi::CStr::from_ptr(html_minifier_version()) };
+    assert_eq!(version.to_str(), Ok(env!("CARGO_PKG_VERSION")));
+}
+
+#[test]
+fn reports_account_for_every_removed_byte() {
+    let html = b"<!-- a --><div  id=\"x\" >\n  <p>One </p>\n</div><style>a { color : red }</style>";
+    let mut out_ptr = std::ptr::null_mut();
+    let mut out_len = 0;
+    let mut report = MinifyReport::default();
+    let status = unsafe {
+        minify_html_with_report(
+            std::ptr::null(),
+            html.as_ptr().cast(),
+            html.len(),
+            &mut out_ptr,
+            &mut out_len,
+            &mut report,
+        )
+    };
+    assert_eq!(status, MinifyStatus::Ok);
+    unsafe { free_buffer(out_ptr, out_len) };
+
+    assert_eq!(report.input_bytes, html.len() as u64);
+    assert_eq!(report.output_bytes, out_len as u64);
+    assert_eq!(
+        report.bytes_removed.total(),
+        report.input_bytes - report.output_bytes
+    );
+    assert!(report.bytes_removed.comments > 0 && report.bytes_removed.css > 0);
+    assert_eq!((report.elements, report.attributes), (3, 1));
+
+    let status = unsafe {
+        minify_html_with_report(
+            std::ptr::null(),
+            html.as_ptr().cast(),
+            html.len(),
+            &mut out_ptr,
+            &mut out_len,
+            std::ptr::null_mut(),
+        )
+    };
+    assert_eq!(status, MinifyStatus::NullPointer);
+    assert_eq!(last_error().as_deref(), Some("report pointer is null"));
+}
+
+#[test]
+fn batch_results_stay_in_input_order_with_their_own_status() {
+    let documents: [&[u8]; 4] = [b"<p>  one  </p>", b"<p>\xff</p>", b"", b"<b> two </b>"];
+    let inputs: Vec<*const std::os::raw::c_char> = documents
+        .iter()
+        .map(|document| document.as_ptr().cast())
+        .collect();
+    let input_lens: Vec<usize> = documents.iter().map(|document| document.len()).collect();
+    let mut outputs = vec![std::ptr::null_mut(); documents.len()];
+    let mut output_lens = vec![usize::MAX; documents.len()];
+    let mut statuses = vec![MinifyStatus::Panic; documents.len()];
+
+    let status = unsafe {
+        minify_html_batch(
+            std::ptr::null(),
+            inputs.as_ptr(),
+            input_lens.as_ptr(),
+            documents.len(),
+            outputs.as_mut_ptr(),
+            output_lens.as_mut_ptr(),
+            statuses.as_mut_ptr(),
+        )
+    };
+    assert_eq!(status, MinifyStatus::InvalidUtf8);
+    assert!(last_error().unwrap().starts_with("document 1: "));
+    assert_eq!(
+        statuses,
+        [
+            MinifyStatus::Ok,
+            MinifyStatus::InvalidUtf8,
+            MinifyStatus::Ok,
+            MinifyStatus::Ok,
+        ]
+    );
+
+    let results: Vec<Option<String>> = outputs
+        .iter()
+        .zip(&output_lens)
+        .map(|(&ptr, &len)| {
+            (!ptr.is_null()).then(|| {
+                let output = unsafe { std::slice::from_raw_parts(ptr as *const u8, len) };
+                let output = String::from_utf8(output.to_vec()).unwrap();
+                unsafe { free_buffer(ptr, len) };
+                output
+            })
+        })
+        .collect();
+    assert_eq!(
+        results,
+        [
+            Some("<p>one".to_string()),
+            None,
+            Some(String::new()),
+            Some("<b>two </b>".to_string()),
+        ]
+    );
+    assert_eq!(output_lens[1], 0);
+}
+
+// =============================================================================
+// Configuration Files
+// =============================================================================
+
+/// An empty directory of its own for each test, with `files` written into it.
+fn config_dir(test: &str, files: &[(&str, &str)]) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("html-minifier-{}-{test}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    for (path, contents) in files {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+    dir
+}
+
+#[test]
+fn discovery_takes_the_nearest_file_and_prefers_toml() {
+    let dir = config_dir(
+        "discover",
+        &[
+            ("htmlmin.toml", "sort_attributes = true"),
+            (".htmlminrc", "{ \"sort_class_names\": true }"),
+            ("a/b/page.html", ""),
+            ("a/c/.htmlminrc", "minify_svg = true"),
+        ],
+    );
+    let discovered = |start: &str| {
+        MinifyConfig::discover(dir.join(start))
+            .unwrap()
+            .expect("a configuration is found")
+    };
+
+    let config = discovered("a/b/page.html");
+    assert_eq!(config.path(), dir.join("htmlmin.toml"));
+    assert!(config.options().sort_attributes);
+    assert!(!config.options().sort_class_names);
+
+    let config = discovered("a/c");
+    assert_eq!(config.path(), dir.join("a/c/.htmlminrc"));
+    assert!(config.options().minify_svg);
+    assert_eq!(
+        discovered("a/c/new.html").path(),
+        dir.join("a/c/.htmlminrc")
+    );
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn overrides_apply_in_file_order_to_matching_paths() {
+    let dir = config_dir(
+        "overrides",
+        &[(
+            "htmlmin.toml",
+            "sort_attributes = true\n\
+             [[override]]\npaths = [\"emails/**\"]\nsort_attributes = false\n\
+             [[override]]\npaths = \"emails/promo/*.html\"\nsort_attributes = true\n\
+             [[override]]\npaths = [\"*.htm\"]\nminify_svg = true\n",
+        )],
+    );
+    let config = MinifyConfig::load(dir.join("htmlmin.toml")).unwrap();
+    let options = |path: &str| config.options_for(dir.join(path));
+
+    assert!(options("index.html").sort_attributes);
+    assert!(!options("emails/welcome.html").sort_attributes);
+    assert!(!options("emails/promo/old/sale.html").sort_attributes);
+    assert!(options("emails/promo/sale.html").sort_attributes);
+
+    // `*` stops at directory separators, and other trees get the top level
+    assert!(options("page.htm").minify_svg);
+    assert!(!options("emails/page.htm").minify_svg);
+    assert!(
+        config
+            .options_for(std::env::temp_dir().join("emails/x.html"))
+            .sort_attributes
+    );
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn bad_configuration_files_name_the_file_and_problem() {
+    let dir = config_dir(
+        "errors",
+        &[
+            ("bad.toml", "sort_attributes = "),
+            ("bad.json", "{ \"sort_attributes\": tru }"),
+            ("array.json", "[true]"),
+            (".htmlminrc", "{ \"no_such_option\": true }"),
+            ("type.toml", "sort_attributes = \"yes\""),
+            ("paths.toml", "[[override]]\nminify_svg = true"),
+            ("tables.toml", "[[minify_svg]]\nenabled = true"),
+            (
+                "nested.json",
+                "{ \"override\": [{ \"paths\": \"*\", \"verify\": [{}] }] }",
+            ),
+            (
+                "glob.toml",
+                "[[override]]\npaths = [\"a/[b\"]\nminify_svg = true",
+            ),
+        ],
+    );
+    let error = |name: &str| MinifyConfig::load(dir.join(name)).unwrap_err();
+    let message = |name: &str| error(name).to_string();
+    let path = |name: &str| dir.join(name).display().to_string();
+
+    assert!(matches!(error("bad.toml"), ConfigError::Parse { .. }));
+    assert!(message("bad.toml").starts_with(&format!("{}: ", path("bad.toml"))));
+    assert!(matches!(error("bad.json"), ConfigError::Parse { .. }));
+    assert_eq!(
+        message("array.json"),
+        format!("{}: expected a JSON object", path("array.json"))
+    );
+    assert_eq!(
+        message(".htmlminrc"),
+        format!("{}: unknown option `no_such_option`", path(".htmlminrc"))
+    );
+    assert_eq!(
+        message("type.toml"),
+        format!(
+            "{}: invalid value `yes` for option `sort_attributes`",
+            path("type.toml")
+        )
+    );
+    assert_eq!(
+        message("paths.toml"),
+        format!("{}: override without `paths`", path("paths.toml"))
+    );
+    assert_eq!(
+        message("tables.toml"),
+        format!(
+            "{}: `minify_svg` is an array of tables, not an option value",
+            path("tables.toml")
+        )
+    );
+    assert_eq!(
+        message("nested.json"),
+        format!(
+            "{}: `verify` is an array of tables, not an option value",
+            path("nested.json")
+        )
+    );
+    assert!(matches!(error("glob.toml"), ConfigError::Glob { pattern, .. } if pattern == "a/[b"));
+    assert!(matches!(error("missing.toml"), ConfigError::Io { .. }));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
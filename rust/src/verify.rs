@@ -0,0 +1,1154 @@
+//! Checks that minified output still means what the input meant.
+//!
+//! Both documents are parsed into simplified trees with the crate's own
+//! tokenizer and compared after normalization:
+//!
+//! - comments are dropped and whitespace runs collapse to one space, except
+//!   inside `<pre>`, `<code>`, `<textarea>` and MathML token elements such
+//!   as `<mi>`. Whitespace at the start or end of a line, next to a block
+//!   element such as `<div>` or before the first text after one, does not
+//!   render and is dropped; whitespace between inline elements is kept;
+//! - attributes form a set per element: the first of repeated names wins,
+//!   and values are compared in a canonical form computed here from tables
+//!   of this module's own rather than by the minifier's attribute code, so
+//!   that a bug or a wrong table entry there shows up as a difference. The
+//!   canonical form has the equivalences the HTML standard defines: boolean
+//!   attributes, default values, empty values that mean a missing
+//!   attribute, enumerated keywords, class lists, the spacing of style
+//!   declarations and, with [`MinifyOptions::minify_svg`], how SVG numbers
+//!   are written. An empty `alt` or `value` is kept apart from a missing
+//!   one;
+//! - `<script>` and `<style>` content is compared after running it through
+//!   [`minify_javascript`] and [`minify_css`], so the check covers where that
+//!   content ends up rather than the CSS and JavaScript minifiers themselves;
+//! - close tags the minifier omits are implied where HTML implies them.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use crate::foreign::{self, ForeignContent, Namespace, SkippedElement};
+use crate::{
+    contains_ignore_ascii_case, is_inline_tag, minify_css, minify_html, minify_javascript,
+    Attribute, MinifyOptions, Token, Tokenizer, SINGLETON_ELEMENTS,
+};
+
+// =============================================================================
+// Verification Results
+// =============================================================================
+
+/// Outcome of [`verify_minified`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Verification {
+    /// Places where the normalized trees of the input and output disagree,
+    /// in document order.
+    pub differences: Vec<Difference>,
+    /// Set when minifying the output again changes it.
+    pub second_pass: Option<SecondPass>,
+}
+
+impl Verification {
+    /// Whether the output is equivalent to the input and stable under
+    /// another minification pass.
+    pub fn is_ok(&self) -> bool {
+        self.differences.is_empty() && self.second_pass.is_none()
+    }
+}
+
+impl std::fmt::Display for Verification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.differences.split_first() {
+            Some((first, [])) => write!(f, "output differs from input at {first}")?,
+            Some((first, rest)) => write!(
+                f,
+                "output differs from input at {first} (and {} more)",
+                rest.len()
+            )?,
+            None if self.second_pass.is_none() => return f.write_str("output is equivalent"),
+            None => {}
+        }
+        if let Some(second_pass) = &self.second_pass {
+            if !self.differences.is_empty() {
+                f.write_str("; ")?;
+            }
+            write!(f, "{second_pass}")?;
+        }
+        Ok(())
+    }
+}
+
+/// One disagreement between the input and output trees.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Difference {
+    /// Location of the node, such as `/html[2]/body[2]/#text[1]`, where each
+    /// index is the 1-based position among the parent's normalized children.
+    pub path: String,
+    pub mismatch: Mismatch,
+}
+
+impl std::fmt::Display for Difference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.mismatch)
+    }
+}
+
+/// What differs at a [`Difference::path`]. Nodes are described in short
+/// form, such as `<div>` or `text "Hello"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mismatch {
+    /// The input has a node the output lacks.
+    Missing(String),
+    /// The output has a node the input lacks.
+    Unexpected(String),
+    /// The nodes differ in kind or element name. Their siblings after this
+    /// point are not compared, since they are likely misaligned.
+    Node { input: String, output: String },
+    /// Text nodes differ after normalization.
+    Text { input: String, output: String },
+    /// An attribute was added, removed or changed.
+    Attribute {
+        name: String,
+        input: Option<String>,
+        output: Option<String>,
+    },
+}
+
+impl std::fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Mismatch::Missing(node) => write!(f, "{node} is missing from the output"),
+            Mismatch::Unexpected(node) => write!(f, "output has an extra {node}"),
+            Mismatch::Node { input, output } => write!(f, "{input} became {output}"),
+            Mismatch::Text { input, output } => {
+                write!(f, "text {:?} became {:?}", excerpt(input), excerpt(output))
+            }
+            Mismatch::Attribute {
+                name,
+                input,
+                output,
+            } => match (input, output) {
+                (Some(_), None) => write!(f, "attribute `{name}` was removed"),
+                (None, Some(value)) => write!(f, "attribute `{name}={value:?}` was added"),
+                (input, output) => write!(
+                    f,
+                    "attribute `{name}` changed from {:?} to {:?}",
+                    input.as_deref().unwrap_or_default(),
+                    output.as_deref().unwrap_or_default()
+                ),
+            },
+        }
+    }
+}
+
+/// The output changed when minified again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecondPass {
+    /// Byte offset of the first difference between the two outputs.
+    pub offset: usize,
+    /// Output of the second pass.
+    pub output: String,
+}
+
+impl std::fmt::Display for SecondPass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "minifying the output again changes it at byte {}",
+            self.offset
+        )
+    }
+}
+
+/// Shortens text for messages to its first 40 characters.
+fn excerpt(text: &str) -> Cow<'_, str> {
+    match text.char_indices().nth(40) {
+        Some((end, _)) => Cow::Owned(format!("{}...", &text[..end])),
+        None => Cow::Borrowed(text),
+    }
+}
+
+// =============================================================================
+// Normalized Trees
+// =============================================================================
+
+#[derive(Debug, PartialEq, Eq)]
+enum Node {
+    Doctype(String),
+    Element(Element),
+    Text(String),
+    Cdata(String),
+    /// A stray end tag of a block-level element, which ends the line of
+    /// text it sits in without being part of the tree.
+    Break,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Element {
+    name: String,
+    attributes: BTreeMap<String, String>,
+    children: Vec<Node>,
+    /// A MathML token element, or an element inside one, whose whitespace
+    /// renders.
+    math_text: bool,
+    /// The element sits within a line of text rather than breaking it.
+    inline: bool,
+    /// The element was ended by its own end tag rather than implicitly.
+    closed: bool,
+    /// How the text of the element's children is normalized.
+    text_mode: TextMode,
+}
+
+impl Element {
+    fn new(name: String) -> Self {
+        Element {
+            name,
+            attributes: BTreeMap::new(),
+            children: Vec::new(),
+            math_text: false,
+            inline: false,
+            closed: false,
+            text_mode: TextMode::Collapse,
+        }
+    }
+}
+
+impl Node {
+    fn segment(&self) -> &str {
+        match self {
+            Node::Doctype(_) => "#doctype",
+            Node::Element(element) => &element.name,
+            Node::Text(_) => "#text",
+            Node::Cdata(_) => "#cdata",
+            Node::Break => "#break",
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Node::Doctype(_) => "doctype".to_string(),
+            Node::Element(element) => format!("<{}>", element.name),
+            Node::Text(text) => format!("text {:?}", excerpt(text)),
+            Node::Cdata(_) => "CDATA section".to_string(),
+            Node::Break => "stray end tag".to_string(),
+        }
+    }
+}
+
+/// Elements whose open `<p>` is closed by their start tag.
+const P_CLOSING_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "details",
+    "dialog",
+    "div",
+    "dl",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hgroup",
+    "hr",
+    "main",
+    "menu",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "ul",
+];
+
+/// Whether a `next` start tag implies the end of an open `open` element,
+/// for the elements whose close tags the minifier omits.
+pub(crate) fn closes_implicitly(open: &str, next: &str) -> bool {
+    match open {
+        "p" => P_CLOSING_ELEMENTS.contains(&next),
+        "li" => next == "li",
+        "dt" | "dd" => matches!(next, "dt" | "dd"),
+        "option" => matches!(next, "option" | "optgroup"),
+        "td" | "th" => matches!(next, "td" | "th" | "tr" | "thead" | "tbody" | "tfoot"),
+        "tr" => matches!(next, "tr" | "thead" | "tbody" | "tfoot"),
+        "thead" | "tbody" | "tfoot" => matches!(next, "thead" | "tbody" | "tfoot"),
+        "colgroup" => next != "col",
+        _ => false,
+    }
+}
+
+/// How text inside an element is normalized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TextMode {
+    Collapse,
+    Verbatim,
+    Script,
+    Style,
+}
+
+/// Builds normalized trees from the token stream.
+struct TreeBuilder<'o> {
+    options: &'o MinifyOptions,
+    /// Open elements, outermost first. The bottom entry is the document.
+    stack: Vec<Element>,
+    /// Whether tags are HTML or SVG.
+    foreign: ForeignContent,
+    /// Lowercased names of the attributes seen on the tag being parsed.
+    seen_attributes: HashSet<String>,
+}
+
+impl<'o> TreeBuilder<'o> {
+    fn parse(html: &str, options: &'o MinifyOptions) -> Vec<Node> {
+        let mut builder = TreeBuilder {
+            options,
+            stack: vec![Element::new(String::new())],
+            foreign: ForeignContent::default(),
+            seen_attributes: HashSet::new(),
+        };
+
+        // The element whose start tag is being parsed, pushed once it ends
+        let mut opening: Option<Element> = None;
+        // An SVG element dropped along with its content, as the minifier
+        // drops it
+        let mut skipping: Option<SkippedElement> = None;
+        let mut tokenizer = Tokenizer::new(html);
+        while let Some(token) = tokenizer.next_token() {
+            if let Token::TagOpenStart(name) = token {
+                if builder.foreign.start_tag_namespace(name) != Namespace::Html {
+                    tokenizer.foreign_start_tag();
+                }
+            }
+            if let Some(skipped) = skipping.as_mut() {
+                if skipped.token(&token) {
+                    skipping = None;
+                }
+                continue;
+            }
+            if !matches!(token, Token::Attribute(_)) {
+                if let Some(element) = opening.take() {
+                    builder.open(element, matches!(token, Token::TagSelfClose));
+                }
+            }
+
+            match token {
+                Token::TagOpenStart(name)
+                    if foreign::is_removable_element(
+                        builder.foreign.namespace(),
+                        name,
+                        options,
+                    ) =>
+                {
+                    skipping = Some(SkippedElement::new(name));
+                }
+                Token::TagOpenStart(name) => {
+                    let inline = is_inline_tag(name, builder.foreign.start_tag_namespace(name));
+                    builder.foreign.start_tag(name);
+                    builder.seen_attributes.clear();
+                    opening = Some(Element {
+                        inline,
+                        ..Element::new(name.to_ascii_lowercase())
+                    });
+                }
+                Token::Attribute(attr) => {
+                    if let Some(element) = opening.as_mut() {
+                        // A `<font>` with this attribute is an HTML element
+                        if builder.foreign.attribute(attr.name) {
+                            element.inline = is_inline_tag(&element.name, Namespace::Html);
+                        }
+                        builder.add_attribute(element, attr);
+                    }
+                }
+                Token::TagOpenEnd | Token::TagSelfClose | Token::Comment(_) => {}
+                Token::TagClose(name) => {
+                    let inline = is_inline_tag(name, builder.foreign.end_tag_namespace(name));
+                    builder.foreign.end_tag(name);
+                    builder.close(name, inline);
+                }
+                Token::TextNode(text) => builder.push_text(text),
+                Token::Doctype(doctype) => {
+                    let doctype = collapse_whitespace(doctype).to_ascii_lowercase();
+                    builder.push(Node::Doctype(doctype));
+                }
+                Token::Cdata(content) => builder.push(Node::Cdata(content.to_string())),
+            }
+        }
+        if let Some(element) = opening.take() {
+            builder.open(element, false);
+        }
+
+        while builder.stack.len() > 1 {
+            builder.pop();
+        }
+        let mut document = builder
+            .stack
+            .pop()
+            .map_or_else(Vec::new, |root| root.children);
+        normalize(&mut document, TextMode::Collapse, false);
+        trim_line_starts(&mut document, TextMode::Collapse, &mut false);
+        trim_line_ends(&mut document, TextMode::Collapse, &mut false);
+        drop_empty_text(&mut document);
+        document
+    }
+
+    fn current(&mut self) -> &mut Element {
+        self.stack.last_mut().expect("the document is never popped")
+    }
+
+    fn push(&mut self, node: Node) {
+        self.current().children.push(node);
+    }
+
+    /// Adds text, merging it with text that precedes it, as happens when
+    /// a comment between them is dropped.
+    fn push_text(&mut self, text: &str) {
+        match self.current().children.last_mut() {
+            Some(Node::Text(previous)) => previous.push_str(text),
+            _ => self.push(Node::Text(text.to_string())),
+        }
+    }
+
+    fn pop(&mut self) {
+        if let Some(element) = self.stack.pop() {
+            self.push(Node::Element(element));
+        }
+    }
+
+    fn open(&mut self, mut element: Element, self_closing: bool) {
+        // SVG and MathML have no void elements or implied end tags
+        let foreign = self.foreign.is_foreign();
+        self.foreign.end_start_tag(&element.name, self_closing);
+        element.math_text = self.foreign.in_math_text();
+        while !foreign
+            && self.stack.len() > 1
+            && closes_implicitly(&self.current().name, &element.name)
+        {
+            self.pop();
+        }
+
+        if self_closing || (!foreign && SINGLETON_ELEMENTS.contains(&element.name.as_str())) {
+            self.push(Node::Element(element));
+        } else {
+            self.stack.push(element);
+        }
+    }
+
+    /// Closes the innermost open element named `name`, along with any
+    /// elements left open inside it. Stray close tags are ignored, apart
+    /// from the line break left by a block-level one.
+    fn close(&mut self, name: &str, inline: bool) {
+        let name = name.split_ascii_whitespace().next().unwrap_or_default();
+        let Some(depth) = self
+            .stack
+            .iter()
+            .skip(1)
+            .rposition(|element| element.name.eq_ignore_ascii_case(name))
+        else {
+            if !inline {
+                self.push(Node::Break);
+            }
+            return;
+        };
+        // A block-level end tag breaks the line on both sides, even where
+        // it ends an inline element
+        if !inline {
+            self.push(Node::Break);
+        }
+        while self.stack.len() > depth + 2 {
+            self.pop();
+        }
+        self.current().closed = true;
+        self.pop();
+        if !inline {
+            self.push(Node::Break);
+        }
+    }
+
+    /// Records an attribute in its canonical form, keeping only the first
+    /// of repeated names.
+    fn add_attribute(&mut self, element: &mut Element, attr: Attribute) {
+        let name = attr.name.to_ascii_lowercase();
+        if name.is_empty() || !self.seen_attributes.insert(name) {
+            return;
+        }
+        let namespace = self.foreign.namespace();
+        if let Some((name, value)) =
+            canonical_attribute(&element.name, attr, namespace, self.options)
+        {
+            element.attributes.insert(name, value);
+        }
+    }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_ascii_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Collapses whitespace runs to one space, keeping a space at either end
+/// for [`trim_line_starts`] and [`trim_line_ends`] to judge.
+fn collapse_spaces(text: &str) -> String {
+    let mut collapsed = String::with_capacity(text.len());
+    for c in text.chars() {
+        if !c.is_ascii_whitespace() {
+            collapsed.push(c);
+        } else if !collapsed.ends_with(' ') {
+            collapsed.push(' ');
+        }
+    }
+    collapsed
+}
+
+/// Applies the text rules of `mode` to `nodes` and their descendants,
+/// noting on each element how its own children are normalized. Text
+/// anywhere inside `<pre>` is kept as written, while a MathML token
+/// element only keeps its own.
+fn normalize(nodes: &mut [Node], mode: TextMode, in_pre: bool) {
+    for node in nodes {
+        match node {
+            Node::Element(element) => {
+                let pre = matches!(element.name.as_str(), "pre" | "code" | "textarea");
+                element.text_mode = match element.name.as_str() {
+                    _ if pre => TextMode::Verbatim,
+                    "script" => TextMode::Script,
+                    "style" => TextMode::Style,
+                    _ if element.math_text || in_pre => TextMode::Verbatim,
+                    _ => TextMode::Collapse,
+                };
+                normalize(&mut element.children, element.text_mode, in_pre || pre);
+            }
+            Node::Text(text) => {
+                *text = match mode {
+                    TextMode::Collapse => collapse_spaces(text),
+                    TextMode::Verbatim => std::mem::take(text),
+                    TextMode::Script => minify_javascript(text),
+                    TextMode::Style => minify_css(text),
+                };
+            }
+            Node::Doctype(_) | Node::Cdata(_) | Node::Break => {}
+        }
+    }
+}
+
+/// Drops the space at the start of collapsed text when nothing has been
+/// rendered on its line before it. `rendered` carries that across the
+/// document: text, void elements such as `<img>` and the end of an inline
+/// element set it, and block elements clear it.
+fn trim_line_starts(nodes: &mut [Node], mode: TextMode, rendered: &mut bool) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => {
+                if mode == TextMode::Collapse && !*rendered && text.starts_with(' ') {
+                    text.remove(0);
+                }
+                *rendered |= !text.trim_start_matches(' ').is_empty();
+            }
+            Node::Element(element) if element.inline => {
+                trim_line_starts(&mut element.children, element.text_mode, rendered);
+                *rendered |= element.closed || SINGLETON_ELEMENTS.contains(&element.name.as_str());
+            }
+            Node::Element(element) => {
+                *rendered = false;
+                trim_line_starts(&mut element.children, element.text_mode, rendered);
+                *rendered = false;
+            }
+            Node::Cdata(_) => *rendered = true,
+            Node::Doctype(_) | Node::Break => *rendered = false,
+        }
+    }
+}
+
+/// Drops the space at the end of collapsed text when nothing is rendered
+/// on its line after it, the mirror image of [`trim_line_starts`].
+fn trim_line_ends(nodes: &mut [Node], mode: TextMode, rendered: &mut bool) {
+    for node in nodes.iter_mut().rev() {
+        match node {
+            Node::Text(text) => {
+                if mode == TextMode::Collapse && !*rendered && text.ends_with(' ') {
+                    text.pop();
+                }
+                *rendered |= !text.trim_end_matches(' ').is_empty();
+            }
+            Node::Element(element) if element.inline => {
+                *rendered |= element.closed || SINGLETON_ELEMENTS.contains(&element.name.as_str());
+                trim_line_ends(&mut element.children, element.text_mode, rendered);
+            }
+            Node::Element(element) => {
+                *rendered = false;
+                trim_line_ends(&mut element.children, element.text_mode, rendered);
+                *rendered = false;
+            }
+            Node::Cdata(_) => *rendered = true,
+            Node::Doctype(_) | Node::Break => *rendered = false,
+        }
+    }
+}
+
+/// Drops text left empty by trimming, and the line breaks once trimming
+/// no longer needs them.
+fn drop_empty_text(nodes: &mut Vec<Node>) {
+    nodes.retain_mut(|node| match node {
+        Node::Text(text) => !text.is_empty(),
+        Node::Break => false,
+        Node::Element(element) => {
+            drop_empty_text(&mut element.children);
+            true
+        }
+        Node::Doctype(_) | Node::Cdata(_) => true,
+    });
+}
+
+// =============================================================================
+// Canonical Attributes
+// =============================================================================
+
+/// The name and value of an attribute in a form that is equal for any two
+/// ways of writing it that mean the same, or `None` when it means the same
+/// as leaving it out.
+fn canonical_attribute(
+    tag: &str,
+    attr: Attribute,
+    namespace: Namespace,
+    options: &MinifyOptions,
+) -> Option<(String, String)> {
+    // SVG names are case-sensitive; a bare name has the empty value
+    let name = match namespace {
+        Namespace::Svg => attr.name.to_string(),
+        _ => attr.name.to_ascii_lowercase(),
+    };
+    let raw = attr.value.unwrap_or_default().replace("&quot;", "\"");
+
+    let value = if name.eq_ignore_ascii_case("class") {
+        let classes: BTreeSet<&str> = raw.split_ascii_whitespace().collect();
+        classes.into_iter().collect::<Vec<_>>().join(" ")
+    } else if name.eq_ignore_ascii_case("style") {
+        canonical_style(&raw)
+    } else {
+        match namespace {
+            Namespace::Html => return canonical_html_attribute(tag, name, raw, options),
+            Namespace::Svg if options.minify_svg => {
+                if foreign::is_removable_attribute(tag, &name, &raw) {
+                    return None;
+                }
+                let path = name == "d";
+                let numeric = path
+                    || contains_ignore_ascii_case(foreign::NUMBER_ATTRIBUTES, &name)
+                    || contains_ignore_ascii_case(foreign::NUMBER_LIST_ATTRIBUTES, &name);
+                match numeric {
+                    true => canonical_numbers(&raw, path, options.svg_precision),
+                    false => raw,
+                }
+            }
+            Namespace::Svg | Namespace::MathMl => raw,
+        }
+    };
+
+    // Classes, and in HTML styles, that come to nothing are left out
+    let empty_is_missing = namespace == Namespace::Html || name.eq_ignore_ascii_case("class");
+    (!(value.is_empty() && empty_is_missing)).then_some((name, value))
+}
+
+/// Boolean attributes of the HTML standard paired with the elements they
+/// apply to. An empty element list marks a global attribute.
+const BOOLEAN_ATTRIBUTES: &[(&str, &[&str])] = &[
+    ("allowfullscreen", &["iframe"]),
+    ("async", &["script"]),
+    ("autofocus", &[]),
+    ("autoplay", &["audio", "video"]),
+    ("checked", &["input"]),
+    ("controls", &["audio", "video"]),
+    ("default", &["track"]),
+    ("defer", &["script"]),
+    (
+        "disabled",
+        &[
+            "button", "fieldset", "input", "link", "optgroup", "option", "select", "textarea",
+        ],
+    ),
+    ("disablepictureinpicture", &["video"]),
+    ("disableremoteplayback", &["audio", "video"]),
+    ("formnovalidate", &["button", "input"]),
+    ("inert", &[]),
+    ("ismap", &["img"]),
+    ("itemscope", &[]),
+    ("loop", &["audio", "video"]),
+    ("multiple", &["input", "select"]),
+    ("muted", &["audio", "video"]),
+    ("nomodule", &["script"]),
+    ("novalidate", &["form"]),
+    ("open", &["details", "dialog"]),
+    ("playsinline", &["video"]),
+    ("readonly", &["input", "textarea"]),
+    ("required", &["input", "select", "textarea"]),
+    ("reversed", &["ol"]),
+    ("selected", &["option"]),
+    ("typemustmatch", &["object"]),
+];
+
+/// Attributes whose empty value means the same as leaving them out: event
+/// handlers with no code, and values that are invalid when empty and fall
+/// back to the missing state. `alt=""` marks an image as decorative and an
+/// empty `value` is the value itself, so neither is here.
+const EMPTY_MEANS_MISSING: &[&str] = &[
+    "action", "class", "dir", "id", "lang", "style", "target", "title", "type",
+];
+
+/// Values that elements take when the attribute is left out, as element,
+/// attribute and value.
+const DEFAULT_VALUES: &[(&str, &str, &str)] = &[
+    ("button", "type", "submit"),
+    ("form", "enctype", "application/x-www-form-urlencoded"),
+    ("input", "type", "text"),
+    ("script", "type", "text/javascript"),
+    ("style", "media", "all"),
+    ("style", "type", "text/css"),
+];
+
+/// An enumerated attribute of the HTML standard: the elements it applies
+/// to, where none means any element, its keywords, the keyword the empty
+/// value stands for and the one a missing attribute stands for.
+struct Enumerated {
+    tags: &'static [&'static str],
+    name: &'static str,
+    keywords: &'static [&'static str],
+    empty: Option<&'static str>,
+    missing: Option<&'static str>,
+}
+
+const ENUMERATED_ATTRIBUTES: &[Enumerated] = &[
+    Enumerated {
+        tags: &[],
+        name: "contenteditable",
+        keywords: &["true", "false", "plaintext-only"],
+        empty: Some("true"),
+        missing: None,
+    },
+    Enumerated {
+        tags: &[],
+        name: "dir",
+        keywords: &["ltr", "rtl", "auto"],
+        empty: None,
+        missing: None,
+    },
+    Enumerated {
+        tags: &[],
+        name: "draggable",
+        keywords: &["true", "false", "auto"],
+        empty: None,
+        missing: Some("auto"),
+    },
+    Enumerated {
+        tags: &[],
+        name: "hidden",
+        keywords: &["hidden", "until-found"],
+        empty: Some("hidden"),
+        missing: None,
+    },
+    Enumerated {
+        tags: &[],
+        name: "spellcheck",
+        keywords: &["true", "false"],
+        empty: Some("true"),
+        missing: None,
+    },
+    Enumerated {
+        tags: &[],
+        name: "translate",
+        keywords: &["yes", "no"],
+        empty: Some("yes"),
+        missing: None,
+    },
+    Enumerated {
+        tags: &["area"],
+        name: "shape",
+        keywords: &["rect", "circle", "poly", "default"],
+        empty: None,
+        missing: Some("rect"),
+    },
+    Enumerated {
+        tags: &["audio", "img", "link", "script", "video"],
+        name: "crossorigin",
+        keywords: &["anonymous", "use-credentials"],
+        empty: Some("anonymous"),
+        missing: None,
+    },
+    Enumerated {
+        tags: &["button", "input"],
+        name: "formmethod",
+        keywords: &["get", "post", "dialog"],
+        empty: None,
+        missing: None,
+    },
+    Enumerated {
+        tags: &["form"],
+        name: "autocomplete",
+        keywords: &["on", "off"],
+        empty: None,
+        missing: Some("on"),
+    },
+    Enumerated {
+        tags: &["form"],
+        name: "method",
+        keywords: &["get", "post", "dialog"],
+        empty: None,
+        missing: Some("get"),
+    },
+    Enumerated {
+        tags: &["iframe", "img"],
+        name: "loading",
+        keywords: &["eager", "lazy"],
+        empty: None,
+        missing: Some("eager"),
+    },
+    Enumerated {
+        tags: &["iframe", "img", "link", "script"],
+        name: "fetchpriority",
+        keywords: &["high", "low", "auto"],
+        empty: None,
+        missing: Some("auto"),
+    },
+    Enumerated {
+        tags: &["img"],
+        name: "decoding",
+        keywords: &["sync", "async", "auto"],
+        empty: None,
+        missing: Some("auto"),
+    },
+    Enumerated {
+        tags: &["input", "select", "textarea"],
+        name: "autocomplete",
+        keywords: &["on", "off"],
+        empty: None,
+        missing: None,
+    },
+    Enumerated {
+        tags: &["textarea"],
+        name: "wrap",
+        keywords: &["soft", "hard"],
+        empty: None,
+        missing: Some("soft"),
+    },
+    Enumerated {
+        tags: &["th"],
+        name: "scope",
+        keywords: &["row", "col", "rowgroup", "colgroup", "auto"],
+        empty: None,
+        missing: Some("auto"),
+    },
+    Enumerated {
+        tags: &["track"],
+        name: "kind",
+        keywords: &[
+            "subtitles",
+            "captions",
+            "descriptions",
+            "chapters",
+            "metadata",
+        ],
+        empty: None,
+        missing: Some("subtitles"),
+    },
+];
+
+/// Whether `tags` lists `tag`, or is empty and so covers every element.
+fn applies_to(tags: &[&str], tag: &str) -> bool {
+    tags.is_empty() || tags.contains(&tag)
+}
+
+/// The canonical form of an HTML attribute with a lowercase `name`.
+fn canonical_html_attribute(
+    tag: &str,
+    name: String,
+    value: String,
+    options: &MinifyOptions,
+) -> Option<(String, String)> {
+    // Any value of a boolean attribute means it is set. Custom elements,
+    // whose names have a hyphen, define their own attributes
+    let boolean = BOOLEAN_ATTRIBUTES
+        .iter()
+        .any(|(attr, tags)| *attr == name && applies_to(tags, tag));
+    if boolean && (options.collapse_custom_element_booleans || !tag.contains('-')) {
+        return Some((name, String::new()));
+    }
+    let empty_is_missing =
+        EMPTY_MEANS_MISSING.contains(&name.as_str()) || (name.starts_with("on") && name.len() > 2);
+    let default = DEFAULT_VALUES.iter().any(|(element, attr, default)| {
+        *element == tag && *attr == name && default.eq_ignore_ascii_case(&value)
+    });
+    if (value.is_empty() && empty_is_missing) || default {
+        return None;
+    }
+
+    // Enumerated keywords are case-insensitive, and the empty value and a
+    // missing attribute each stand for one of them
+    let spec = ENUMERATED_ATTRIBUTES
+        .iter()
+        .find(|spec| spec.name == name && applies_to(spec.tags, tag));
+    if let Some(spec) = spec {
+        let state = match value.as_str() {
+            "" => spec.empty,
+            value => spec
+                .keywords
+                .iter()
+                .copied()
+                .find(|keyword| keyword.eq_ignore_ascii_case(value)),
+        };
+        if let Some(state) = state {
+            return (spec.missing != Some(state)).then(|| (name, state.to_string()));
+        }
+    }
+    Some((name, value))
+}
+
+/// A style attribute as its declarations, without the whitespace around
+/// them, their colons and semicolons, and with other whitespace runs
+/// collapsed. Quoted strings are kept as written.
+fn canonical_style(value: &str) -> String {
+    let mut canonical = String::with_capacity(value.len());
+    let mut quote = None;
+    let mut space = false;
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        if let Some(open) = quote {
+            canonical.push(ch);
+            match ch {
+                '\\' => canonical.extend(chars.next()),
+                _ if ch == open => quote = None,
+                _ => {}
+            }
+            continue;
+        }
+        match ch {
+            _ if ch.is_ascii_whitespace() => space = true,
+            ':' | ';' => {
+                if ch == ':' || !(canonical.is_empty() || canonical.ends_with(';')) {
+                    canonical.push(ch);
+                }
+                space = false;
+            }
+            _ => {
+                if space && !(canonical.is_empty() || canonical.ends_with([':', ';'])) {
+                    canonical.push(' ');
+                }
+                space = false;
+                if matches!(ch, '"' | '\'') {
+                    quote = Some(ch);
+                }
+                canonical.push(ch);
+            }
+        }
+    }
+    canonical.truncate(canonical.trim_end_matches(';').len());
+    canonical
+}
+
+/// SVG path data or numbers as the values of their numbers, rounded to
+/// `precision` decimals when given, among the command letters, units and
+/// other characters around them. How the numbers are written and
+/// separated does not matter, and neither do command letters that the
+/// previous command implies.
+fn canonical_numbers(value: &str, path: bool, precision: Option<u32>) -> String {
+    let mut tokens: Vec<String> = Vec::new();
+    // The command that a bare group of arguments continues, and how many
+    // of its arguments have been read
+    let mut command = None;
+    let mut arguments = 0;
+    let mut rest = value;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == ',');
+        let Some(ch) = rest.chars().next() else {
+            break;
+        };
+        let is_flag = matches!(command, Some('A' | 'a')) && matches!(arguments % 7, 3 | 4);
+        if path && ch.is_ascii_alphabetic() {
+            let implied = match command {
+                Some('M') => Some('L'),
+                Some('m') => Some('l'),
+                command => command,
+            };
+            if implied != Some(ch) || ch.eq_ignore_ascii_case(&'z') {
+                tokens.push(ch.to_string());
+            }
+            command = Some(ch);
+            arguments = 0;
+            rest = &rest[1..];
+        } else if path && is_flag && matches!(ch, '0' | '1') {
+            tokens.push(ch.to_string());
+            arguments += 1;
+            rest = &rest[1..];
+        } else if let Some((number, after)) = leading_number(rest) {
+            let number = match precision {
+                Some(precision) => format!("{number:.0$}", precision as usize)
+                    .parse()
+                    .unwrap_or(number),
+                None => number,
+            };
+            // Rounding can leave a negative zero
+            tokens.push((number + 0.0).to_string());
+            arguments += 1;
+            rest = after;
+        } else {
+            tokens.push(ch.to_string());
+            rest = &rest[ch.len_utf8()..];
+        }
+    }
+    tokens.join(" ")
+}
+
+/// Parses the number at the start of `text`, in SVG syntax, returning it
+/// with the text after it.
+fn leading_number(text: &str) -> Option<(f64, &str)> {
+    let bytes = text.as_bytes();
+    let digits = |from: usize| {
+        bytes[from.min(bytes.len())..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count()
+    };
+    let mut end = usize::from(matches!(bytes.first(), Some(b'+' | b'-')));
+    let integer = digits(end);
+    end += integer;
+    let mut fraction = 0;
+    if bytes.get(end) == Some(&b'.') {
+        fraction = digits(end + 1);
+        end += 1 + fraction;
+    }
+    if integer == 0 && fraction == 0 {
+        return None;
+    }
+    if matches!(bytes.get(end), Some(b'e' | b'E')) {
+        let sign = usize::from(matches!(bytes.get(end + 1), Some(b'+' | b'-')));
+        let exponent = digits(end + 1 + sign);
+        if exponent > 0 {
+            end += 1 + sign + exponent;
+        }
+    }
+    let number = text[..end].parse::<f64>().ok()?;
+    number.is_finite().then(|| (number, &text[end..]))
+}
+
+// =============================================================================
+// Comparison
+// =============================================================================
+
+fn compare(input: &[Node], output: &[Node], path: &str, differences: &mut Vec<Difference>) {
+    for (index, (input_node, output_node)) in input.iter().zip(output).enumerate() {
+        let node_path = format!("{path}/{}[{}]", input_node.segment(), index + 1);
+        match (input_node, output_node) {
+            (Node::Element(input), Node::Element(output)) if input.name == output.name => {
+                compare_attributes(input, output, &node_path, differences);
+                compare(&input.children, &output.children, &node_path, differences);
+            }
+            (Node::Text(input), Node::Text(output)) => {
+                if input != output {
+                    differences.push(Difference {
+                        path: node_path,
+                        mismatch: Mismatch::Text {
+                            input: input.clone(),
+                            output: output.clone(),
+                        },
+                    });
+                }
+            }
+            (input, output) if input == output => {}
+            (input, output) => {
+                differences.push(Difference {
+                    path: node_path,
+                    mismatch: Mismatch::Node {
+                        input: input.describe(),
+                        output: output.describe(),
+                    },
+                });
+                return;
+            }
+        }
+    }
+
+    for (index, node) in input.iter().enumerate().skip(output.len()) {
+        differences.push(Difference {
+            path: format!("{path}/{}[{}]", node.segment(), index + 1),
+            mismatch: Mismatch::Missing(node.describe()),
+        });
+    }
+    for (index, node) in output.iter().enumerate().skip(input.len()) {
+        differences.push(Difference {
+            path: format!("{path}/{}[{}]", node.segment(), index + 1),
+            mismatch: Mismatch::Unexpected(node.describe()),
+        });
+    }
+}
+
+fn compare_attributes(
+    input: &Element,
+    output: &Element,
+    path: &str,
+    differences: &mut Vec<Difference>,
+) {
+    let names: BTreeSet<&String> = input
+        .attributes
+        .keys()
+        .chain(output.attributes.keys())
+        .collect();
+    for name in names {
+        let input_value = input.attributes.get(name);
+        let output_value = output.attributes.get(name);
+        if input_value != output_value {
+            differences.push(Difference {
+                path: path.to_string(),
+                mismatch: Mismatch::Attribute {
+                    name: name.clone(),
+                    input: input_value.cloned(),
+                    output: output_value.cloned(),
+                },
+            });
+        }
+    }
+}
+
+/// Offset of the first byte at which `a` and `b` differ.
+fn first_difference(a: &str, b: &str) -> usize {
+    a.bytes()
+        .zip(b.bytes())
+        .position(|(a, b)| a != b)
+        .unwrap_or(a.len().min(b.len()))
+}
+
+// =============================================================================
+// Entry Point
+// =============================================================================
+
+/// Checks that `output`, minified from `input` with `options`, is equivalent
+/// to it and that minifying it again leaves it unchanged. See the module
+/// documentation for what counts as equivalent.
+pub fn verify_minified(input: &str, output: &str, options: &MinifyOptions) -> Verification {
+    let mut differences = Vec::new();
+    compare(
+        &TreeBuilder::parse(input, options),
+        &TreeBuilder::parse(output, options),
+        "",
+        &mut differences,
+    );
+
+    let second_options = MinifyOptions {
+        verify: false,
+        collect_report: false,
+        warn_duplicate_attributes: false,
+        source_map: false,
+        ..options.clone()
+    };
+    let second = minify_html(output, &second_options).html;
+    let second_pass = (second != output).then(|| SecondPass {
+        offset: first_difference(output, &second),
+        output: second,
+    });
+
+    Verification {
+        differences,
+        second_pass,
+    }
+}
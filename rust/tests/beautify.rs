@@ -0,0 +1,91 @@
+//! Layout produced by `beautify_html`.
+
+use html_minifier_ffi::{
+    beautify_html, beautify_html_with_options, minify_html_tokens, BeautifyOptions,
+};
+
+/// Beautifies `html`, checking that the result minifies like the input.
+fn beautify(html: &str) -> String {
+    let pretty = beautify_html(html, "  ");
+    assert_eq!(
+        minify_html_tokens(&pretty),
+        minify_html_tokens(html),
+        "{pretty}"
+    );
+    pretty
+}
+
+#[test]
+fn indents_blocks_and_keeps_inline_content_together() {
+    let html = "<!DOCTYPE html><html><head><title> A   page </title></head>\
+                <body><div class=\"card\"  id=x><h1>Hi  <em>there</em></h1>\
+                <p>One\n   line<br>two<p>Three</div>stray</span></body></html>";
+    assert_eq!(
+        beautify(html),
+        "<!DOCTYPE html>\n\
+         <html>\n\
+         \x20 <head>\n\
+         \x20   <title>A page</title>\n\
+         \x20 </head>\n\
+         \x20 <body>\n\
+         \x20   <div class=\"card\" id=x>\n\
+         \x20     <h1>Hi <em>there</em></h1>\n\
+         \x20     <p>One line<br>two\n\
+         \x20     <p>Three\n\
+         \x20   </div>\n\
+         \x20   stray</span>\n\
+         \x20 </body>\n\
+         </html>\n"
+    );
+}
+
+#[test]
+fn keeps_whitespace_sensitive_content_as_written() {
+    let html = "<div><pre>  a\n    b</pre><textarea>\n x  y</textarea><p>Unclosed <b title=\"x  ";
+    assert_eq!(
+        beautify(html),
+        "<div>\n  <pre>  a\n    b</pre>\n  <textarea>\n x  y</textarea>\n  <p>Unclosed\n<b title=\"x  "
+    );
+}
+
+#[test]
+fn lays_out_css_and_javascript() {
+    // The CSS minifier keeps a space before a brace, so none is added
+    // where the input has none
+    let html = "<style>a,b {color:red;margin:0 auto }@media print{a{display:none}}</style>\
+                <script>function f(x){if(x){return 1;}else{return 2;}}</script>";
+    assert_eq!(
+        beautify(html),
+        "<style>\n\
+         \x20 a,\n\
+         \x20 b {\n\
+         \x20   color: red;\n\
+         \x20   margin: 0 auto\n\
+         \x20 }\n\
+         \x20 @media print{\n\
+         \x20   a{\n\
+         \x20     display: none}\n\
+         \x20 }\n\
+         </style>\n\
+         <script>\n\
+         \x20 function f(x) {\n\
+         \x20   if(x) {\n\
+         \x20     return 1;\n\
+         \x20   } else {\n\
+         \x20     return 2;\n\
+         \x20   }\n\
+         \x20 }\n\
+         </script>\n"
+    );
+
+    let options = BeautifyOptions {
+        indent: "\t".to_string(),
+        format_css: false,
+        format_javascript: false,
+    };
+    let html = "<div><script type=\"text/template\"><p> x</p></script><style>a{b:c}</style></div>";
+    assert_eq!(
+        beautify_html_with_options(html, &options),
+        "<div>\n\t<script type=\"text/template\"><p> x</p></script>\n\t<style>a{b:c}</style>\n</div>\n"
+    );
+}
@@ -0,0 +1,152 @@
+//! The `html-minifier` binary: inputs, outputs and exit statuses.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+/// An empty directory of its own for each test.
+fn scratch_dir(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("html-minifier-cli-{}-{test}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Runs the binary in `dir` without configuration files, feeding `stdin`.
+fn html_minifier(dir: &Path, args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_html-minifier"))
+        .current_dir(dir)
+        .arg("--no-config")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // The binary may exit on a usage error before reading its input
+    let written = child.stdin.take().unwrap().write_all(stdin.as_bytes());
+    if let Err(err) = written {
+        assert_eq!(err.kind(), std::io::ErrorKind::BrokenPipe, "{err}");
+    }
+    child.wait_with_output().unwrap()
+}
+
+fn write(path: PathBuf, contents: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+}
+
+#[test]
+fn minifies_standard_input_to_standard_output() {
+    let dir = scratch_dir("stdio");
+    for args in [&[][..], &["-"]] {
+        let output = html_minifier(&dir, args, "<p>  Hello   world </p>\n");
+        assert_eq!(output.status.code(), Some(0));
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "<p>Hello world");
+    }
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn writes_to_a_file_or_into_a_directory() {
+    let dir = scratch_dir("output");
+    write(dir.join("site/index.html"), "<p> Home </p>");
+    write(dir.join("site/blog/post.html"), "<p> Post </p>");
+
+    let output = html_minifier(&dir, &["-q", "site/index.html", "-o", "home.html"], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        fs::read_to_string(dir.join("home.html")).unwrap(),
+        "<p>Home"
+    );
+
+    // An existing directory receives the file under its own name
+    fs::create_dir(dir.join("out")).unwrap();
+    let output = html_minifier(&dir, &["-q", "site/index.html", "-o", "out"], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        fs::read_to_string(dir.join("out/index.html")).unwrap(),
+        "<p>Home"
+    );
+
+    // An output directory inside the input tree is not read back as input
+    for _ in 0..2 {
+        let output = html_minifier(&dir, &["-q", "site", "-o", "site/min"], "");
+        assert_eq!(output.status.code(), Some(0));
+    }
+    assert_eq!(
+        fs::read_to_string(dir.join("site/min/blog/post.html")).unwrap(),
+        "<p>Post"
+    );
+    assert!(!dir.join("site/min/min").exists());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn rewrites_files_in_place_without_leftovers() {
+    let dir = scratch_dir("in-place");
+    write(dir.join("site/index.html"), "<p> Home </p>");
+
+    let output = html_minifier(&dir, &["-q", "-i", "site"], "");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        fs::read_to_string(dir.join("site/index.html")).unwrap(),
+        "<p>Home"
+    );
+    let entries: Vec<_> = fs::read_dir(dir.join("site")).unwrap().collect();
+    assert_eq!(entries.len(), 1);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn exit_status_tells_failures_apart() {
+    let dir = scratch_dir("status");
+    write(dir.join("good.html"), "<p>Fine</p>");
+    write(dir.join("bad.html"), "<div><b></div></b>");
+    fs::create_dir(dir.join("out")).unwrap();
+
+    let status = |args: &[&str], stdin: &str| html_minifier(&dir, args, stdin).status.code();
+    assert_eq!(status(&["-q", "--check", "good.html"], ""), Some(0));
+    assert_eq!(status(&["-q", "missing.html"], ""), Some(1));
+    assert_eq!(
+        status(&["-q", "--check", "good.html", "missing.html"], ""),
+        Some(1)
+    );
+    assert_eq!(status(&["--no-such-flag"], ""), Some(2));
+    assert_eq!(status(&["-o", "out"], "<p>"), Some(2));
+    assert_eq!(
+        status(&["-q", "--check", "good.html", "bad.html"], ""),
+        Some(3)
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn minifying_malformed_markup_fails_but_writes_output() {
+    let dir = scratch_dir("malformed");
+    write(dir.join("good.html"), "<p>Fine</p>");
+    write(dir.join("comment.html"), "<div></span><!-- x");
+
+    let output = html_minifier(&dir, &["-q"], "<b attr=\"x");
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "<b attr='\"x'");
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("<stdin>:1:4: error[unclosed-attribute-value]"));
+
+    // Warnings alone, such as the stray `</span>`, do not fail the run
+    let output = html_minifier(&dir, &["-q", "good.html", "comment.html", "-o", "out"], "");
+    assert_eq!(output.status.code(), Some(3));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("comment.html:1:13: error[unclosed-comment]"));
+    assert!(!stderr.contains("stray-end-tag"));
+    assert_eq!(
+        fs::read_to_string(dir.join("out/comment.html")).unwrap(),
+        "<div></span>"
+    );
+
+    let status = |html: &str| html_minifier(&dir, &["-q"], html).status.code();
+    assert_eq!(status("<div></span>"), Some(0));
+    assert_eq!(status("<div><b></div></b>"), Some(3));
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,26 @@
+#!/bin/bash
+#
+# Vendors the tokenizer tests of html5lib-tests
+# (https://github.com/html5lib/html5lib-tests) into upstream/, along with
+# their license and the commit they were taken from in upstream/REVISION.
+#
+# Usage: update.sh [revision]    (a commit, tag or branch; default master)
+
+set -euo pipefail
+
+REVISION="${1:-master}"
+HERE="$(cd "$(dirname "$0")" && pwd)"
+WORK="$(mktemp -d)"
+trap 'rm -rf "$WORK"' EXIT
+
+git -C "$WORK" init -q
+git -C "$WORK" fetch -q --depth 1 https://github.com/html5lib/html5lib-tests.git "$REVISION"
+git -C "$WORK" checkout -q FETCH_HEAD
+
+rm -rf "$HERE/upstream"
+mkdir "$HERE/upstream"
+cp "$WORK"/tokenizer/*.test "$WORK/LICENSE" "$HERE/upstream/"
+git -C "$WORK" rev-parse HEAD > "$HERE/upstream/REVISION"
+
+echo "Vendored html5lib-tests $(cat "$HERE/upstream/REVISION") into $HERE/upstream"
+echo "Run cargo test and update KNOWN_FAILURES in src/tests.rs to match"
@@ -0,0 +1,64 @@
+//! Diagnostics reported by `lint`.
+
+use html_minifier_ffi::{lint, DiagnosticCode, Severity};
+
+/// Each diagnostic as `line:column code`.
+fn codes(html: &str) -> Vec<String> {
+    lint(html)
+        .iter()
+        .map(|diagnostic| format!("{} {}", diagnostic.span.position, diagnostic.code))
+        .collect()
+}
+
+#[test]
+fn accepts_omitted_optional_end_tags() {
+    let html = "<!DOCTYPE html><html><head><title>t</title><body>\
+                <ul><li>a<li>b</ul><table><tr><td>1<td>2</table>\
+                <p>one<p>two<br/><input disabled value=x required data-id>";
+    assert_eq!(codes(html), Vec::<String>::new());
+}
+
+#[test]
+fn reports_attribute_problems() {
+    let html = "<p class=a CLASS=b>\n<img alt=A photo src=x.png>\n<a title=\"open>";
+    assert_eq!(
+        codes(html),
+        [
+            "1:12 duplicate-attribute",
+            "2:6 unquoted-value-with-space",
+            "3:4 unclosed-attribute-value",
+        ]
+    );
+
+    let split = &lint(html)[1];
+    assert_eq!(&html[split.span.start..split.span.end], "alt=A photo");
+    assert_eq!(split.severity, Severity::Error);
+}
+
+#[test]
+fn reports_end_tags_that_do_not_match() {
+    let html = "<div><b><i>x</b></i></span>\n<p>a<div>b</div></p><br></br>\n<section>";
+    assert_eq!(
+        codes(html),
+        [
+            "1:1 unclosed-element",
+            "1:13 misnested-end-tag",
+            "1:21 stray-end-tag",
+            "2:17 stray-end-tag",
+            "2:25 stray-end-tag",
+            "3:1 unclosed-element",
+        ]
+    );
+    assert_eq!(
+        lint(html)[1].message,
+        "`</b>` closes <b> while <i> opened inside it at 1:9 is still open"
+    );
+}
+
+#[test]
+fn reports_an_unclosed_comment() {
+    let diagnostics = lint("<p>a</p><!-- TODO\n<p>b</p>");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].code, DiagnosticCode::UnclosedComment);
+    assert_eq!(diagnostics[0].span.start, 8);
+}
@@ -0,0 +1,56 @@
+//! Inline MathML: foreign content whose token elements keep their text.
+
+use html_minifier_ffi::{beautify_html, minify_html, minify_html_tokens, MinifyOptions};
+
+const KATEX: &str =
+    "<p>Inline <span class=\"katex\"><math xmlns=\"http://www.w3.org/1998/Math/MathML\">\
+                     <semantics><mrow><mi>f</mi><mo stretchy=\"false\">(</mo><mi>x</mi>\
+                     <mo stretchy=\"false\">)</mo><mtext> if </mtext><mspace width=\"1em\"/></mrow>\
+                     <annotation encoding=\"application/x-tex\">f(x) \\text{ if }</annotation>\
+                     </semantics></math></span> done.</p>";
+
+const MATHJAX: &str = "<div>\n  <MATH display=\"block\" definitionURL=\"http://example.com\">\n    <mrow>\n      \
+                       <mi> x </mi>\n      <mo>+</mo>\n      <mtext>a  <b>bold</b>  b</mtext>\n      \
+                       <MSPACE Width=\"1em\" />\n    </mrow>\n  </MATH>\n<P>After<BR/></div>";
+
+#[test]
+fn keeps_token_element_text_and_names() {
+    assert_eq!(
+        minify_html_tokens(KATEX),
+        "<p>Inline <span class=katex><math xmlns=http://www.w3.org/1998/Math/MathML>\
+         <semantics><mrow><mi>f</mi><mo stretchy=false>(</mo><mi>x</mi>\
+         <mo stretchy=false>)</mo><mtext> if </mtext><mspace width=1em /></mrow>\
+         <annotation encoding=application/x-tex>f(x) \\text{ if }</annotation>\
+         </semantics></math></span> done."
+    );
+    assert_eq!(
+        minify_html_tokens(MATHJAX),
+        "<div><math display=block definitionURL=http://example.com><mrow>\
+         <mi> x </mi><mo>+</mo><mtext>a  <b>bold</b>  b</mtext><mspace width=1em /></mrow>\
+         </math><p>After<br></div>"
+    );
+}
+
+#[test]
+fn verifies_and_beautifies_mathml() {
+    let options = MinifyOptions {
+        verify: true,
+        ..MinifyOptions::default()
+    };
+    for html in [KATEX, MATHJAX] {
+        let verification = minify_html(html, &options)
+            .verification
+            .expect("verification was requested");
+        assert!(verification.is_ok(), "{verification}");
+
+        let beautified = beautify_html(html, "  ");
+        assert_eq!(minify_html_tokens(&beautified), minify_html_tokens(html));
+    }
+
+    // A token element left open keeps the rest of the input as it was
+    let html = "<math><mi>x <b>y</b>  z";
+    assert_eq!(
+        minify_html_tokens(&beautify_html(html, "  ")),
+        minify_html_tokens(html)
+    );
+}
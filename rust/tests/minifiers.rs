@@ -0,0 +1,35 @@
+//! Whitespace handling in the HTML minifier.
+
+use html_minifier_ffi::minify_html_tokens;
+
+#[test]
+fn keeps_html_whitespace_next_to_inline_elements() {
+    assert_eq!(
+        minify_html_tokens("<span>a</span> <span>b</span>"),
+        "<span>a</span> <span>b</span>"
+    );
+    assert_eq!(
+        minify_html_tokens("<p>Hello <b> world</b> !</p>"),
+        "<p>Hello <b> world</b> !"
+    );
+    assert_eq!(
+        minify_html_tokens("a <img src=x.png> b"),
+        "a <img src=x.png> b"
+    );
+    assert_eq!(
+        minify_html_tokens("<svg><text>x <tspan>y</tspan></text></svg>"),
+        "<svg><text>x <tspan>y</tspan></text></svg>"
+    );
+}
+
+#[test]
+fn drops_html_whitespace_next_to_block_elements() {
+    assert_eq!(
+        minify_html_tokens("<div>\n  <span> a </span>\n</div>\n<p> b </p>"),
+        "<div><span>a </span></div><p>b"
+    );
+    assert_eq!(
+        minify_html_tokens("a <svg>\n <g>\n  <path d=M0/>\n </g>\n</svg> b"),
+        "a <svg><g><path d=M0/></g></svg> b"
+    );
+}
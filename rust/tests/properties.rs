@@ -0,0 +1,470 @@
+//! Invariants of the HTML, CSS and JavaScript minifiers over generated input.
+
+use html_minifier_ffi::{
+    beautify_html, minify_css, minify_html, minify_html_tokens, minify_javascript, verify_minified,
+    MinifyOptions, Position,
+};
+use proptest::prelude::*;
+use proptest::test_runner::FileFailurePersistence;
+
+// =============================================================================
+// HTML Generators
+// =============================================================================
+
+/// A generated HTML node, kept so that properties can compare the output
+/// with what was generated.
+#[derive(Debug, Clone)]
+enum Node {
+    Text(String),
+    Comment(String),
+    Element {
+        name: &'static str,
+        attributes: Vec<(&'static str, String)>,
+        children: Vec<Node>,
+    },
+    Void {
+        name: &'static str,
+        attributes: Vec<(&'static str, String)>,
+    },
+    Pre(String),
+}
+
+impl Node {
+    fn render(&self, out: &mut String, space: &mut impl Iterator<Item = &'static str>) {
+        match self {
+            Node::Text(text) => out.push_str(text),
+            Node::Comment(text) => {
+                out.push_str("<!--");
+                out.push_str(text);
+                out.push_str("-->");
+            }
+            Node::Element {
+                name,
+                attributes,
+                children,
+            } => {
+                render_start_tag(out, name, attributes, space);
+                for child in children {
+                    out.push_str(space.next().unwrap_or_default());
+                    child.render(out, space);
+                }
+                out.push_str("</");
+                out.push_str(name);
+                out.push('>');
+            }
+            Node::Void { name, attributes } => render_start_tag(out, name, attributes, space),
+            Node::Pre(text) => {
+                out.push_str("<pre>");
+                out.push_str(text);
+                out.push_str("</pre>");
+            }
+        }
+    }
+
+    /// Appends the text a reader would see, ignoring whitespace.
+    fn visible_text(&self, out: &mut String) {
+        match self {
+            Node::Text(text) | Node::Pre(text) => {
+                out.extend(text.chars().filter(|c| !c.is_ascii_whitespace()))
+            }
+            Node::Element { children, .. } => {
+                for child in children {
+                    child.visible_text(out);
+                }
+            }
+            Node::Comment(_) | Node::Void { .. } => {}
+        }
+    }
+}
+
+fn render_start_tag(
+    out: &mut String,
+    name: &str,
+    attributes: &[(&str, String)],
+    space: &mut impl Iterator<Item = &'static str>,
+) {
+    out.push('<');
+    out.push_str(name);
+    for (attribute, value) in attributes {
+        out.push_str(space.next().filter(|s| !s.is_empty()).unwrap_or(" "));
+        out.push_str(attribute);
+        let quote = if value.contains('"') { '\'' } else { '"' };
+        out.push('=');
+        out.push(quote);
+        out.push_str(value);
+        out.push(quote);
+    }
+    out.push_str(space.next().unwrap_or_default());
+    out.push('>');
+}
+
+fn render(nodes: &[Node], spaces: &[&'static str]) -> String {
+    let mut out = String::new();
+    let mut space = spaces.iter().copied().cycle();
+    for node in nodes {
+        node.render(&mut out, &mut space);
+        out.push_str(space.next().unwrap_or_default());
+    }
+    out
+}
+
+fn text() -> impl Strategy<Value = String> {
+    "[a-zA-Z0-9.,!?'\"é漢]{1,8}( {1,3}|\n\t?)?[a-zA-Z0-9.,!?é漢]{0,8}"
+}
+
+/// Values that can be quoted: none contain both kinds of quote.
+fn attribute_value() -> impl Strategy<Value = String> {
+    "[a-z0-9 _:;/.'\"-]{0,12}".prop_filter("needs both quotes", |value| {
+        !(value.contains('"') && value.contains('\''))
+    })
+}
+
+fn attributes() -> impl Strategy<Value = Vec<(&'static str, String)>> {
+    prop::collection::vec(
+        (
+            prop::sample::select(vec!["id", "class", "title", "data-x", "href", "lang"]),
+            attribute_value(),
+        ),
+        0..4,
+    )
+}
+
+/// Documents built from elements whose end tags the minifier always keeps.
+fn nodes() -> impl Strategy<Value = Vec<Node>> {
+    let leaf = prop_oneof![
+        4 => text().prop_map(Node::Text),
+        1 => "[a-z ]{0,10}".prop_map(Node::Comment),
+        1 => (prop::sample::select(vec!["br", "hr", "img"]), attributes())
+            .prop_map(|(name, attributes)| Node::Void { name, attributes }),
+        1 => "[a-z]{1,4}( {2,3}|\n)[a-z]{1,4}\n?".prop_map(Node::Pre),
+    ];
+    let tree = leaf.prop_recursive(4, 32, 4, |inner| {
+        (
+            prop::sample::select(vec!["div", "span", "section", "a", "b", "em", "h1"]),
+            attributes(),
+            prop::collection::vec(inner, 0..4),
+        )
+            .prop_map(|(name, attributes, children)| Node::Element {
+                name,
+                attributes,
+                children,
+            })
+    });
+    prop::collection::vec(tree, 1..4)
+}
+
+fn spaces() -> impl Strategy<Value = Vec<&'static str>> {
+    prop::collection::vec(
+        prop::sample::select(vec!["", "", " ", "\n", "\n  ", "\t"]),
+        1..8,
+    )
+}
+
+/// Text between the tags of `html`, without whitespace.
+fn output_text(html: &str) -> String {
+    let mut text = String::new();
+    let mut rest = html;
+    loop {
+        let end = rest.find('<').unwrap_or(rest.len());
+        text.extend(rest[..end].chars().filter(|c| !c.is_ascii_whitespace()));
+        match rest[end..].find('>') {
+            Some(close) => rest = &rest[end + close + 1..],
+            None => return text,
+        }
+    }
+}
+
+/// The character at `position`, whose column counts UTF-16 code units.
+fn char_at(text: &str, position: Position) -> Option<char> {
+    let line = text.split('\n').nth(position.line as usize)?;
+    let mut column = 0;
+    for ch in line.chars().chain(Some('\n')) {
+        if column == position.column as usize {
+            return Some(ch);
+        }
+        column += ch.len_utf16();
+    }
+    None
+}
+
+// =============================================================================
+// CSS and JavaScript Generators
+// =============================================================================
+
+fn css() -> impl Strategy<Value = (String, Vec<String>)> {
+    let declaration = (
+        prop::sample::select(vec!["color", "margin", "content", "font-family", "width"]),
+        prop_oneof![
+            "[a-z0-9#%]{1,8}( [a-z0-9]{1,4})?".prop_map(|value| (value, None)),
+            "[a-z /*{};:,'-]{0,10}".prop_map(|s| (format!("\"{s}\""), Some(format!("\"{s}\"")))),
+        ],
+        spaces(),
+    );
+    let rule = (
+        prop::sample::select(vec!["a", ".card", "#main", "ul > li", "h1, h2", "p:hover"]),
+        prop::collection::vec(declaration, 1..4),
+        spaces(),
+    );
+    prop::collection::vec(rule, 1..4).prop_map(|rules| {
+        let mut css = String::new();
+        let mut strings = Vec::new();
+        for (selector, declarations, space) in rules {
+            let space = |i: usize| space[i % space.len()];
+            css.push_str(selector);
+            css.push_str(space(0));
+            css.push('{');
+            for (i, (property, (value, string), gap)) in declarations.into_iter().enumerate() {
+                let gap = |j: usize| gap[j % gap.len()];
+                css.push_str(gap(0));
+                css.push_str(property);
+                css.push_str(gap(1));
+                css.push(':');
+                css.push_str(gap(2));
+                css.push_str(&value);
+                css.push(';');
+                if i % 2 == 1 {
+                    css.push_str("/* note */");
+                }
+                strings.extend(string);
+            }
+            css.push_str(space(1));
+            css.push('}');
+            css.push_str(space(2));
+        }
+        (css, strings)
+    })
+}
+
+fn javascript() -> impl Strategy<Value = (String, Vec<String>)> {
+    let expression = prop_oneof![
+        "[0-9]{1,4}".prop_map(|number| (number, None)),
+        "[a-z]{1,6}".prop_map(|name| (name, None)),
+        "[a-z /*+=;.'-]{0,10}".prop_map(|s| (format!("\"{s}\""), Some(format!("\"{s}\"")))),
+        "[a-z /*+=;.\"-]{0,10}".prop_map(|s| (format!("'{s}'"), Some(format!("'{s}'")))),
+    ];
+    let statement = (
+        prop::sample::select(vec!["var", "let", "const"]),
+        "[a-z][a-z0-9_]{0,5}",
+        expression.clone(),
+        prop::sample::select(vec![" + ", " * ", " == ", " && ", "+", ","]),
+        expression,
+        spaces(),
+        prop::sample::select(vec!["", " // trailing note", " /* block */"]),
+    );
+    prop::collection::vec(statement, 1..5).prop_map(|statements| {
+        let mut js = String::new();
+        let mut strings = Vec::new();
+        for (keyword, name, (left, left_string), op, (right, right_string), space, comment) in
+            statements
+        {
+            let space = |i: usize| space[i % space.len()];
+            js.push_str(keyword);
+            js.push(' ');
+            js.push_str(&name);
+            js.push_str(space(0));
+            js.push('=');
+            js.push_str(space(1));
+            js.push_str(&left);
+            js.push_str(op);
+            js.push_str(&right);
+            js.push(';');
+            js.push_str(comment);
+            js.push('\n');
+            js.push_str(space(2));
+            strings.extend(left_string);
+            strings.extend(right_string);
+        }
+        (js, strings)
+    })
+}
+
+/// Whether `needles` occur in `haystack` in order, without overlapping.
+fn contains_in_order(haystack: &str, needles: &[String]) -> bool {
+    let mut rest = haystack;
+    needles
+        .iter()
+        .all(|needle| match rest.find(needle.as_str()) {
+            Some(at) => {
+                rest = &rest[at + needle.len()..];
+                true
+            }
+            None => false,
+        })
+}
+
+// =============================================================================
+// SVG Generators
+// =============================================================================
+
+/// Path data with assorted separators, signs and implicit zeros. Some of it
+/// does not parse, and must come through unchanged.
+fn path_data() -> impl Strategy<Value = String> {
+    "M ?-?[0-9]{1,2}(\\.[0-9]{1,2})?[ ,]-?[0-9]{1,2}(\\.[0-9]{1,2})?\
+     ( ?[MmLlHhVvCcSsQqTtZz]?( ?[-+]?[0-9]{0,2}(\\.[0-9]{1,3})?[ ,]?){1,4}){0,6}"
+}
+
+/// The numbers in path data, in order.
+fn path_numbers(data: &str) -> Vec<f64> {
+    let bytes = data.as_bytes();
+    let mut numbers = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        if matches!(bytes[i], b'+' | b'-') {
+            i += 1;
+        }
+        let mut seen_dot = false;
+        while i < bytes.len() && (bytes[i].is_ascii_digit() || (bytes[i] == b'.' && !seen_dot)) {
+            seen_dot |= bytes[i] == b'.';
+            i += 1;
+        }
+        match data[start..i].parse() {
+            Ok(number) => numbers.push(number),
+            Err(_) => i = start + 1,
+        }
+    }
+    numbers
+}
+
+// =============================================================================
+// Properties
+// =============================================================================
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        failure_persistence: Some(Box::new(FileFailurePersistence::WithSource("proptest-regressions"))),
+        ..ProptestConfig::default()
+    })]
+
+    #[test]
+    fn html_output_is_idempotent_and_no_longer(nodes in nodes(), spaces in spaces()) {
+        let html = render(&nodes, &spaces);
+        let once = minify_html_tokens(&html);
+        prop_assert!(once.len() <= html.len(), "{html:?} grew to {once:?}");
+        prop_assert_eq!(minify_html_tokens(&once), once);
+    }
+
+    #[test]
+    fn html_text_content_is_preserved(nodes in nodes(), spaces in spaces()) {
+        let html = render(&nodes, &spaces);
+        let mut expected = String::new();
+        for node in &nodes {
+            node.visible_text(&mut expected);
+        }
+        prop_assert_eq!(output_text(&minify_html_tokens(&html)), expected);
+    }
+
+    #[test]
+    fn html_output_verifies(
+        nodes in nodes(),
+        spaces in spaces(),
+        sort_attributes: bool,
+        sort_class_names: bool,
+    ) {
+        let html = render(&nodes, &spaces);
+        let options = MinifyOptions {
+            sort_attributes,
+            sort_class_names,
+            ..MinifyOptions::default()
+        };
+        let output = minify_html(&html, &options).html;
+        let verification = verify_minified(&html, &output, &options);
+        prop_assert!(verification.is_ok(), "{html:?} -> {output:?}: {verification}");
+    }
+
+    #[test]
+    fn html_source_map_points_at_emitted_characters(
+        nodes in nodes(),
+        spaces in spaces(),
+        sort_attributes: bool,
+    ) {
+        let html = render(&nodes, &spaces);
+        let options = MinifyOptions {
+            sort_attributes,
+            source_map: true,
+            ..MinifyOptions::default()
+        };
+        let output = minify_html(&html, &options);
+        let source_map = output.source_map.expect("source map was requested");
+        for mapping in source_map.mappings() {
+            let generated = char_at(&output.html, mapping.generated);
+            let original = char_at(&html, mapping.original);
+            let matches = match (generated, original) {
+                (Some(' '), Some(original)) => original.is_ascii_whitespace(),
+                (Some(generated), Some(original)) => generated.eq_ignore_ascii_case(&original),
+                _ => false,
+            };
+            prop_assert!(matches, "{html:?} -> {:?}: {mapping:?}", output.html);
+        }
+    }
+
+    #[test]
+    fn css_output_is_idempotent_and_keeps_strings((css, strings) in css()) {
+        let once = minify_css(&css);
+        prop_assert!(once.len() <= css.len(), "{css:?} grew to {once:?}");
+        prop_assert_eq!(minify_css(&once), once.clone());
+        prop_assert!(contains_in_order(&once, &strings), "{css:?} -> {once:?}");
+    }
+
+    #[test]
+    fn javascript_output_is_idempotent_and_keeps_strings((js, strings) in javascript()) {
+        let once = minify_javascript(&js);
+        prop_assert!(once.len() <= js.len(), "{js:?} grew to {once:?}");
+        prop_assert_eq!(minify_javascript(&once), once.clone());
+        prop_assert!(contains_in_order(&once, &strings), "{js:?} -> {once:?}");
+    }
+
+    #[test]
+    fn beautified_html_minifies_like_its_input(nodes in nodes(), spaces in spaces()) {
+        let html = render(&nodes, &spaces);
+        let pretty = beautify_html(&html, "  ");
+        prop_assert_eq!(minify_html_tokens(&pretty), minify_html_tokens(&html), "{:?}", pretty);
+        prop_assert_eq!(beautify_html(&pretty, "  "), pretty);
+    }
+
+    #[test]
+    fn beautified_css_and_javascript_minify_like_their_input(
+        (css, _) in css(),
+        (js, _) in javascript(),
+    ) {
+        let html = format!("<head><style>{css}</style><script>{js}</script></head>");
+        let pretty = beautify_html(&html, "\t");
+        prop_assert_eq!(minify_html_tokens(&pretty), minify_html_tokens(&html), "{:?}", pretty);
+    }
+
+    #[test]
+    fn svg_path_data_keeps_its_numbers(data in path_data()) {
+        let options = MinifyOptions {
+            minify_svg: true,
+            ..MinifyOptions::default()
+        };
+        let html = format!("<svg><path d=\"{data}\"/></svg>");
+        let output = minify_html(&html, &options).html;
+        prop_assert_eq!(minify_html(&output, &options).html, output.clone());
+
+        let value = output
+            .strip_prefix("<svg><path d=")
+            .and_then(|rest| rest.split_once("/></svg>"))
+            .map(|(value, _)| value.trim_end().trim_matches('"'))
+            .unwrap_or_default();
+        prop_assert_eq!(path_numbers(value), path_numbers(&data), "{:?}", output);
+    }
+
+    #[test]
+    fn arbitrary_input_never_panics(input in "\\PC*", soup in "[<>/!=\"' a-zA-Z\n-]{0,64}") {
+        for input in [input.as_str(), soup.as_str()] {
+            minify_html_tokens(input);
+            minify_css(input);
+            minify_javascript(input);
+            let options = MinifyOptions {
+                sort_attributes: true,
+                sort_class_names: true,
+                verify: true,
+                ..MinifyOptions::default()
+            };
+            minify_html(input, &options);
+            let pretty = beautify_html(input, "  ");
+            prop_assert_eq!(minify_html_tokens(&pretty), minify_html_tokens(input), "{:?}", pretty);
+        }
+    }
+}
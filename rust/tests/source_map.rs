@@ -0,0 +1,124 @@
+//! Source maps of minified documents.
+
+use html_minifier_ffi::{minify_html, MinifyOptions, Position, SourceMap};
+
+fn minify_with_map(html: &str) -> (String, SourceMap) {
+    let options = MinifyOptions {
+        source_map: true,
+        ..MinifyOptions::default()
+    };
+    let output = minify_html(html, &options);
+    (
+        output.html,
+        output.source_map.expect("source map was requested"),
+    )
+}
+
+/// Each mapping as `generated column -> original line:column`, one-based on
+/// the original side as editors show it.
+fn describe(source_map: &SourceMap) -> Vec<String> {
+    source_map
+        .mappings()
+        .iter()
+        .map(|mapping| format!("{} -> {}", mapping.generated.column, mapping.original))
+        .collect()
+}
+
+#[test]
+fn maps_tags_text_and_inline_css() {
+    let html = "<div  id=a>\n  <b>Hi</b>\n</div>\n<style>\n  a { color: red }\n</style>";
+    let (output, source_map) = minify_with_map(html);
+
+    assert_eq!(
+        output,
+        "<div id=a><b>Hi</b></div><style>a {color:red }</style>"
+    );
+    assert!(source_map.mappings().iter().all(|m| m.generated.line == 0));
+    assert_eq!(
+        describe(&source_map),
+        [
+            "0 -> 1:1",
+            "5 -> 1:7",
+            "9 -> 1:11",
+            "10 -> 2:3",
+            "12 -> 2:5",
+            "13 -> 2:6",
+            "15 -> 2:8",
+            "19 -> 3:1",
+            "25 -> 4:1",
+            "31 -> 4:7",
+            "32 -> 5:3",
+            "33 -> 5:4",
+            "34 -> 5:5",
+            "35 -> 5:7",
+            "40 -> 5:12",
+            "41 -> 5:14",
+            "44 -> 5:17",
+            "45 -> 5:18",
+            "46 -> 6:1",
+        ]
+    );
+}
+
+#[test]
+fn maps_inline_javascript_tokens() {
+    let html = "<script>\n  // setup\n  var total = add(1,  2);\n</script>";
+    let (output, source_map) = minify_with_map(html);
+    assert_eq!(output, "<script>var total=add(1,2);</script>");
+
+    let column = |needle: &str| output.find(needle).expect("needle is in the output") as u32;
+    let original = |needle: &str| {
+        source_map.original_position(Position {
+            line: 0,
+            column: column(needle),
+        })
+    };
+    assert_eq!(original("var").map(|p| p.to_string()), Some("3:3".into()));
+    assert_eq!(original("total").map(|p| p.to_string()), Some("3:7".into()));
+    assert_eq!(original("add").map(|p| p.to_string()), Some("3:15".into()));
+    assert_eq!(original("2)").map(|p| p.to_string()), Some("3:23".into()));
+}
+
+#[test]
+fn maps_each_line_of_preformatted_text() {
+    let html = "<p>a</p>\n<pre>one\n  two\nthree</pre>";
+    let (output, source_map) = minify_with_map(html);
+    assert_eq!(output, "<p>a<pre>one\n  two\nthree</pre>");
+
+    let line_starts: Vec<_> = source_map
+        .mappings()
+        .iter()
+        .filter(|mapping| mapping.generated.line > 0 && mapping.generated.column == 0)
+        .map(|mapping| (mapping.generated.line, mapping.original.to_string()))
+        .collect();
+    assert_eq!(
+        line_starts,
+        [(1, "3:1".to_string()), (2, "4:1".to_string())]
+    );
+}
+
+#[test]
+fn counts_columns_in_utf16_code_units() {
+    let html = "<p>😀  é <b>x</b>";
+    let (output, source_map) = minify_with_map(html);
+    assert_eq!(output, "<p>😀 é <b>x</b>");
+
+    let bold = source_map
+        .original_position(Position { line: 0, column: 8 })
+        .expect("the <b> tag is mapped");
+    assert_eq!(bold, Position { line: 0, column: 9 });
+}
+
+#[test]
+fn serializes_as_source_map_v3() {
+    let (_, source_map) = minify_with_map("<div>\n  <p>x</p>\n</div>");
+    assert_eq!(
+        source_map.to_json("page.min.html", "page.html", None),
+        r#"{"version":3,"file":"page.min.html","sources":["page.html"],"names":[],"mappings":"AAAA,IAAI,CACF,EAAE,CAAC,CACL"}"#
+    );
+
+    let json = source_map.to_json("a\"b.html", "page.html", Some("<div>\n"));
+    let parsed: serde_json::Value = serde_json::from_str(&json).expect("output is JSON");
+    assert_eq!(parsed["file"], "a\"b.html");
+    assert_eq!(parsed["sourcesContent"][0], "<div>\n");
+}
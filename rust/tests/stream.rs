@@ -0,0 +1,71 @@
+//! Streaming minification: output must not depend on where chunks split.
+
+use html_minifier_ffi::{minify_html, minify_stream, Minifier, MinifyOptions};
+
+const DOCUMENT: &str = "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n  <title>Caf\u{e9} \u{2014} menu</title>\n  \
+    <style>\n    a > b { color : red ; }\n  </style>\n  <!-- a comment -- with dashes --->\n</head>\n\
+    <body class=\" one  two one \">\n  <p title='It\u{2019}s \"quoted\"' hidden>Hello,   <b>world</b> \u{1F600}</p>\n  \
+    <pre>  keep\n   this  </pre>\n  <textarea>  <b>raw</b> </textarea>\n  \
+    <script type=\"text/javascript\">\n    var  total = 1; // </scrip\n    if (a < b) { total++; }\n  </script>\n  \
+    <![CDATA[ x ]]><svg viewBox=\"0 0 10 10\"><path d=\"M0 0\"/></svg>\n  <!---->\n</body>\n</html>\n";
+
+fn options() -> MinifyOptions {
+    MinifyOptions {
+        collect_report: true,
+        ..MinifyOptions::default()
+    }
+}
+
+/// Streams `chunks` through a [`Minifier`], returning the output and the
+/// input bytes the report counted.
+fn stream<'c>(chunks: impl IntoIterator<Item = &'c [u8]>) -> (String, u64) {
+    let options = options();
+    let mut minifier = Minifier::new(Vec::new(), &options);
+    for chunk in chunks {
+        minifier.push(chunk).unwrap();
+    }
+    let output = minifier.finish().unwrap();
+    let report = output.report.expect("a report was requested");
+    (
+        String::from_utf8(output.writer).unwrap(),
+        report.input_bytes,
+    )
+}
+
+#[test]
+fn splitting_anywhere_gives_the_whole_document_output() {
+    let whole = minify_html(DOCUMENT, &options()).html;
+    let bytes = DOCUMENT.as_bytes();
+
+    for split in 0..=bytes.len() {
+        let (streamed, input_bytes) = stream([&bytes[..split], &bytes[split..]]);
+        assert_eq!(streamed, whole, "split at byte {split}");
+        assert_eq!(input_bytes, bytes.len() as u64, "split at byte {split}");
+    }
+    for size in 1..=8 {
+        let (streamed, _) = stream(bytes.chunks(size));
+        assert_eq!(streamed, whole, "chunks of {size} bytes");
+    }
+}
+
+#[test]
+fn long_comments_and_scripts_stream_in_one_pass() {
+    let comment = format!("<p>a<!--{}-->b</p>", "- -> x ".repeat(200_000));
+    let script = format!(
+        "<script>{}</script>",
+        "let  x = '</scrip';\n".repeat(100_000)
+    );
+    for html in [comment, script] {
+        let whole = minify_html(&html, &options()).html;
+        let (streamed, input_bytes) = stream(html.as_bytes().chunks(4096));
+        assert_eq!(streamed, whole);
+        assert_eq!(input_bytes, html.len() as u64);
+    }
+
+    // Invalid UTF-8 is reported, even when split
+    let options = options();
+    assert!(minify_stream(&b"<p>\xff</p>"[..], Vec::new(), &options).is_err());
+    let mut minifier = Minifier::new(Vec::new(), &options);
+    minifier.push(b"<p>\xe2\x80").unwrap();
+    assert!(minifier.finish().is_err());
+}
@@ -0,0 +1,145 @@
+//! Inline SVG: case-sensitive names and the `minify_svg` optimizations.
+
+use html_minifier_ffi::{minify_html, minify_html_tokens, MinifyOptions, OptionError};
+
+/// Minifies `html` with `minify_svg`, checking that the output verifies.
+fn minify_svg(html: &str, precision: Option<u32>) -> String {
+    let options = MinifyOptions {
+        minify_svg: true,
+        svg_precision: precision,
+        verify: true,
+        ..MinifyOptions::default()
+    };
+    let output = minify_html(html, &options);
+    let verification = output.verification.expect("verification was requested");
+    assert!(verification.is_ok(), "{verification}");
+    output.html
+}
+
+#[test]
+fn keeps_svg_names_and_self_closing_tags() {
+    let html = "<DIV><SVG viewBox=\"0 0 10 10\"><defs><linearGradient id=\"g\" \
+                gradientUnits=\"userSpaceOnUse\"><stop offset=\"0.5\"/></linearGradient></defs>\
+                <foreignObject><P>Text<BR/></foreignObject><path d=\"M0 0\" fill=\"url(#g)\"/>\
+                </SVG><BR/></DIV>";
+    assert_eq!(
+        minify_html_tokens(html),
+        "<div><svg viewBox=\"0 0 10 10\"><defs><linearGradient id=g \
+         gradientUnits=userSpaceOnUse><stop offset=0.5 /></linearGradient></defs>\
+         <foreignObject><p>Text<br></foreignObject><path d=\"M0 0\" fill=\"url(#g)\"/>\
+         </svg><br></div>"
+    );
+
+    // HTML elements such as `<p>` end SVG content, and are lowercased
+    assert_eq!(
+        minify_html_tokens("<svg><Rect/><p>A<BR/></p></svg>"),
+        "<svg><Rect/><p>A<br></svg>"
+    );
+    assert_eq!(
+        minify_html_tokens("<svg><Rect/><P>A</P><DIV></DIV></svg>"),
+        "<svg><Rect/><p>A<div></div></svg>"
+    );
+}
+
+#[test]
+fn reads_svg_style_and_title_as_markup() {
+    // A comment in an SVG `<title>` is markup, not part of its text
+    assert_eq!(
+        minify_html_tokens("<svg><title>Chart <!-- draft --> of  sales</title></svg>"),
+        "<svg><title>Chart of sales</title></svg>"
+    );
+    // Self-closed, they end at once rather than taking in the document
+    assert_eq!(
+        minify_html_tokens("<svg><title/><style/><rect/></svg><!-- note --><p> x </p>"),
+        "<svg><title/><style/><rect/></svg><p>x"
+    );
+    // SVG style sheets are kept as written, HTML ones minified
+    assert_eq!(
+        minify_html_tokens(
+            "<svg><style><![CDATA[a > b { fill: red }]]></style></svg>\
+             <style>a > b { fill: red }</style>"
+        ),
+        "<svg><style><![CDATA[a > b { fill: red }]]></style></svg>\
+         <style>a >b {fill:red }</style>"
+    );
+}
+
+#[test]
+fn font_ends_svg_content_only_with_presentation_attributes() {
+    // An SVG `<font>` keeps its names, and whitespace next to it goes
+    assert_eq!(
+        minify_html_tokens("<svg><font X=1> a </font></svg>"),
+        "<svg><font X=1>a</font></svg>"
+    );
+    // With `color`, `face` or `size` it is an inline HTML element, and a
+    // `<style>` after it holds CSS again
+    for attribute in ["COLOR=red", "face=serif", "size=2"] {
+        let html = format!("<svg><font {attribute} X=1> a </font><style>a {{ b }}</style></svg>");
+        let attribute = attribute.to_ascii_lowercase();
+        assert_eq!(
+            minify_html_tokens(&html),
+            format!("<svg><font {attribute} x=1>a </font><style>a {{b }}</style></svg>")
+        );
+    }
+}
+
+#[test]
+fn optimizes_svg_attributes_and_drops_editor_markup() {
+    let html = "<svg xmlns=\"http://www.w3.org/2000/svg\" \
+                xmlns:inkscape=\"http://www.inkscape.org/namespaces/inkscape\" \
+                inkscape:version=\"1.0\" viewBox=\"0,0, 24.00 24\">\
+                <metadata><rdf:RDF><cc:Work/></rdf:RDF></metadata>\
+                <sodipodi:namedview pagecolor=\"#fff\"/>\
+                <path d=\"M 10.500 20 L 30 -0.5 L 40 0.254 Z m 1e2,0 a 5 5 0 1 0 10 10\" \
+                opacity=\"1\" fill=\"#000\"/>\
+                <rect x=\"0\" y=\"0.0\" width=\"10.0px\" height=\"5\"/>\
+                <path d=\"M 1 2 unparsed\"/></svg>";
+    assert_eq!(
+        minify_svg(html, None),
+        "<svg viewBox=\"0 0 24 24\">\
+         <path d=\"M10.5 20 30-.5 40 .254Zm1e2 0a5 5 0 1 0 10 10\" fill=#000 />\
+         <rect width=10px height=5 /><path d=\"M 1 2 unparsed\"/></svg>"
+    );
+    assert_eq!(
+        minify_svg(html, Some(2)),
+        "<svg viewBox=\"0 0 24 24\">\
+         <path d=\"M10.5 20 30-.5 40 .25Zm100 0a5 5 0 1 0 10 10\" fill=#000 />\
+         <rect width=10px height=5 /><path d=\"M 1 2 unparsed\"/></svg>"
+    );
+
+    // Outside SVG, and without the option, attributes are left alone
+    let html = "<div x=\"0\" opacity=\"1\"></div><svg><rect x=\"0.0\"/></svg>";
+    assert_eq!(
+        minify_svg(html, None),
+        "<div x=0 opacity=1></div><svg><rect/></svg>"
+    );
+    assert_eq!(
+        minify_html_tokens(html),
+        "<div x=0 opacity=1></div><svg><rect x=0.0 /></svg>"
+    );
+}
+
+#[test]
+fn sets_svg_options_by_name() {
+    let mut options = MinifyOptions::default();
+    options.set_bool("minify_svg", true).unwrap();
+    options.set_str("svg_precision", "1").unwrap();
+    assert_eq!(options.svg_precision, Some(1));
+    assert_eq!(
+        options.set_str("svg_precision", "two"),
+        Err(OptionError::InvalidValue {
+            name: "svg_precision".to_string(),
+            value: "two".to_string(),
+        })
+    );
+    assert!(matches!(
+        options.set_bool("svg_precision", true),
+        Err(OptionError::InvalidValue { .. })
+    ));
+
+    let html = "<svg><circle cx=\"3.14159\" r=\"0.05\"/></svg>";
+    assert_eq!(
+        minify_html(html, &options).html,
+        "<svg><circle cx=3.1 r=.1 /></svg>"
+    );
+}
@@ -0,0 +1,87 @@
+//! The public tokenizer: tokens, attribute parts and spans.
+
+use html_minifier_ffi::{Attribute, AttributeQuote, Position, Token, Tokenizer};
+
+fn attribute<'a>(
+    name: &'a str,
+    value: Option<&'a str>,
+    quote: Option<AttributeQuote>,
+) -> Token<'a> {
+    Token::Attribute(Attribute { name, value, quote })
+}
+
+#[test]
+fn splits_attributes_into_parts() {
+    let html = r#"<input type="text" name='q' value=a&b disabled data-x = "1">"#;
+    let tokens: Vec<Token> = Tokenizer::new(html).map(|(token, _)| token).collect();
+
+    assert_eq!(
+        tokens,
+        [
+            Token::TagOpenStart("input"),
+            attribute("type", Some("text"), Some(AttributeQuote::Double)),
+            attribute("name", Some("q"), Some(AttributeQuote::Single)),
+            attribute("value", Some("a&b"), None),
+            attribute("disabled", None, None),
+            attribute("data-x", Some("1"), Some(AttributeQuote::Double)),
+            Token::TagOpenEnd,
+        ]
+    );
+}
+
+#[test]
+fn keeps_the_quote_of_an_unterminated_value() {
+    let tokens: Vec<Token> = Tokenizer::new("<a title=\"open")
+        .map(|(token, _)| token)
+        .collect();
+    assert_eq!(tokens[1], attribute("title", Some("\"open"), None));
+}
+
+#[test]
+fn spans_cover_each_token_without_surrounding_whitespace() {
+    let html = "<ul>\n  <li  class=\"a\" >Één</li>\n<!-- done --><br/></ul>";
+    let spans: Vec<(&str, String)> = Tokenizer::new(html)
+        .map(|(_, span)| (&html[span.start..span.end], span.position.to_string()))
+        .collect();
+
+    let expected = [
+        ("<ul", "1:1"),
+        (">", "1:4"),
+        ("\n  ", "1:5"),
+        ("<li", "2:3"),
+        ("class=\"a\"", "2:8"),
+        (">", "2:18"),
+        ("Één", "2:19"),
+        ("</li>", "2:22"),
+        ("\n", "2:27"),
+        ("<!-- done -->", "3:1"),
+        ("<br", "3:14"),
+        ("/>", "3:17"),
+        ("</ul>", "3:19"),
+    ];
+    let expected: Vec<(&str, String)> = expected
+        .iter()
+        .map(|&(text, position)| (text, position.to_string()))
+        .collect();
+    assert_eq!(spans, expected);
+}
+
+#[test]
+fn yields_raw_text_content_whole() {
+    let html = "<script>if (a < b) { x = '</div>'; }</script>";
+    let (token, span) = Tokenizer::new(html)
+        .find(|(token, _)| matches!(token, Token::TextNode(_)))
+        .expect("the script has content");
+
+    assert_eq!(token, Token::TextNode("if (a < b) { x = '</div>'; }"));
+    assert_eq!(span.position, Position { line: 0, column: 8 });
+}
+
+#[test]
+fn reads_svg_style_as_markup() {
+    let html = "<svg><style><!-- a --></style></svg><style><!-- b --></style>";
+    let tokens: Vec<Token> = Tokenizer::new(html).map(|(token, _)| token).collect();
+
+    assert_eq!(tokens[4], Token::Comment(" a "));
+    assert_eq!(tokens[9], Token::TextNode("<!-- b -->"));
+}
@@ -0,0 +1,102 @@
+//! `verify_minified`: which rewrites it accepts as equivalent and which it
+//! reports.
+
+use html_minifier_ffi::{minify_html_tokens, verify_minified, MinifyOptions, Mismatch};
+
+/// Verifies `output` as the minified form of `input` with default options.
+fn verify(input: &str, output: &str) -> Vec<Mismatch> {
+    let verification = verify_minified(input, output, &MinifyOptions::default());
+    verification
+        .differences
+        .into_iter()
+        .map(|difference| difference.mismatch)
+        .collect()
+}
+
+#[test]
+fn accepts_rewrites_that_render_the_same() {
+    let html = "<div>\n  <span class='b  a'>x</span>\n</div>\n\
+                <p style=\"color : red ; margin:0\"> y <input type=text disabled=disabled> <b> z </b>";
+    let minified = minify_html_tokens(html);
+    assert_eq!(
+        minified,
+        "<div><span class=\"b a\">x</span></div>\
+         <p style=\"color: red; margin:0\">y <input disabled> <b> z </b>"
+    );
+    assert_eq!(verify(html, &minified), []);
+}
+
+#[test]
+fn rejects_whitespace_dropped_between_inline_elements() {
+    assert_eq!(
+        verify(
+            "<span>a</span> <span>b</span>",
+            "<span>a</span><span>b</span>"
+        ),
+        [Mismatch::Node {
+            input: "text \" \"".to_string(),
+            output: "<span>".to_string(),
+        }]
+    );
+    assert_eq!(
+        verify("<p>a <b>c</b> d", "<p>a <b>c</b>d"),
+        [Mismatch::Text {
+            input: " d".to_string(),
+            output: "d".to_string(),
+        }]
+    );
+}
+
+#[test]
+fn rejects_attribute_values_the_minifier_would_also_rewrite() {
+    // Spaces inside a quoted CSS string are content, not layout
+    assert_eq!(
+        verify(
+            "<p style='content:\"a ; b\"'>x",
+            "<p style='content:\"a; b\"'>x"
+        ),
+        [Mismatch::Attribute {
+            name: "style".to_string(),
+            input: Some("content:\"a ; b\"".to_string()),
+            output: Some("content:\"a; b\"".to_string()),
+        }]
+    );
+    assert_eq!(
+        verify("<input type=checkbox checked>", "<input type=checkbox>"),
+        [Mismatch::Attribute {
+            name: "checked".to_string(),
+            input: Some(String::new()),
+            output: None,
+        }]
+    );
+}
+
+#[test]
+fn rejects_empty_attributes_that_mean_something() {
+    // A decorative image and an empty value differ from missing attributes,
+    // whatever the minifier's own removal rules say
+    assert_eq!(
+        verify("<img src=a.png alt=''>", "<img src=a.png>"),
+        [Mismatch::Attribute {
+            name: "alt".to_string(),
+            input: Some(String::new()),
+            output: None,
+        }]
+    );
+    assert_eq!(
+        verify("<option value=''>None", "<option>None"),
+        [Mismatch::Attribute {
+            name: "value".to_string(),
+            input: Some(String::new()),
+            output: None,
+        }]
+    );
+
+    let html = "<img src=a.png alt=\"\"><select><option value=\"\">None</select>";
+    let minified = minify_html_tokens(html);
+    assert_eq!(
+        minified,
+        "<img src=a.png alt=\"\"><select><option value=\"\">None</select>"
+    );
+    assert_eq!(verify(html, &minified), []);
+}
@@ -10,14 +10,54 @@ class HTMLMinifier
 {
     private FFI $ffi;
     private static ?HTMLMinifier $instance = null;
+    private ?string $lastError = null;
+
+    /**
+     * ABI version this wrapper was written against; must match
+     * html_minifier_abi_version() of the loaded library.
+     */
+    private const ABI_VERSION = 2;
 
     private function __construct(string $libraryPath)
     {
-        $this->ffi = FFI::cdef(
-            "char* minify_html_string(const char* html_ptr);
-             void free_string(char* ptr);",
-            $libraryPath
-        );
+        try {
+            $this->ffi = FFI::cdef(self::loadHeader(), $libraryPath);
+        } catch (FFI\Exception $e) {
+            throw new \RuntimeException(
+                "Could not load HTML minifier library {$libraryPath}; it may predate this wrapper. " .
+                "Rebuild it with 'bash build.sh'. ({$e->getMessage()})",
+                0,
+                $e
+            );
+        }
+
+        $abiVersion = $this->ffi->html_minifier_abi_version();
+        if ($abiVersion !== self::ABI_VERSION) {
+            throw new \RuntimeException(sprintf(
+                "HTML minifier library %s (version %s) has ABI version %d, expected %d. " .
+                "Rebuild it with 'bash build.sh'.",
+                $libraryPath,
+                FFI::string($this->ffi->html_minifier_version()),
+                $abiVersion,
+                self::ABI_VERSION
+            ));
+        }
+    }
+
+    /**
+     * Reads the C header generated from the Rust sources, dropping the
+     * preprocessor lines that FFI::cdef() cannot parse.
+     */
+    private static function loadHeader(): string
+    {
+        $headerPath = __DIR__ . '/html_minifier.h';
+        $header = file_get_contents($headerPath);
+
+        if ($header === false) {
+            throw new \RuntimeException("Could not read C header {$headerPath}");
+        }
+
+        return (string) preg_replace('/^\s*#.*$/m', '', $header);
     }
 
     public static function getInstance(?string $libraryPath = null): self
@@ -59,18 +99,221 @@ class HTMLMinifier
         );
     }
 
-    public function minify(string $html): string
+    /**
+     * @param array<string, bool|int|string|string[]> $options Options named as the
+     *     fields of the Rust MinifyOptions struct, e.g. ['sort_attributes' => true].
+     */
+    public function minify(string $html, array $options = []): string
+    {
+        if ($options === []) {
+            return $this->runMinifier('minify_html_bytes', $html);
+        }
+
+        $config = $this->createConfig($options);
+
+        try {
+            return $this->runMinifier('minify_html_with_config', $html, [$config]);
+        } finally {
+            $this->ffi->minifier_config_free($config);
+        }
+    }
+
+    /**
+     * Minifies $html and returns the output together with the core's report
+     * of bytes removed per category, element/attribute counts and timings.
+     *
+     * @param array<string, bool|int|string|string[]> $options See minify().
+     * @return array{html: string, report: array<string, int|array<string, int>>|null}
+     */
+    public function minifyWithReport(string $html, array $options = []): array
     {
-        $minifiedPtr = $this->ffi->minify_html_string($html);
+        $config = $this->createConfig($options);
+        $report = $this->ffi->new('MinifyReport');
 
-        if ($minifiedPtr === null) {
-            return $html;
+        try {
+            $minified = $this->runMinifier('minify_html_with_report', $html, [$config], [FFI::addr($report)]);
+        } finally {
+            $this->ffi->minifier_config_free($config);
         }
 
-        $minified = FFI::string($minifiedPtr);
+        if ($this->lastError !== null) {
+            return ['html' => $minified, 'report' => null];
+        }
+
+        $removed = $report->bytes_removed;
+
+        return [
+            'html' => $minified,
+            'report' => [
+                'input_bytes' => $report->input_bytes,
+                'output_bytes' => $report->output_bytes,
+                'bytes_removed' => [
+                    'comments' => $removed->comments,
+                    'whitespace' => $removed->whitespace,
+                    'attributes' => $removed->attributes,
+                    'optional_tags' => $removed->optional_tags,
+                    'css' => $removed->css,
+                    'js' => $removed->js,
+                ],
+                'elements' => $report->elements,
+                'attributes' => $report->attributes,
+                'parse_nanos' => $report->parse_nanos,
+                'emit_nanos' => $report->emit_nanos,
+            ],
+        ];
+    }
+
+    /**
+     * Minifies many documents in a single call, which the Rust core spreads
+     * across all CPU cores. Keys are preserved. A document that fails is
+     * returned unchanged, and getLastError() describes the first failure.
+     *
+     * @param array<array-key, string> $documents
+     * @param array<string, bool|int|string|string[]> $options See minify().
+     * @return array<array-key, string>
+     */
+    public function minifyBatch(array $documents, array $options = []): array
+    {
+        $this->lastError = null;
+        $count = count($documents);
+        if ($count === 0) {
+            return [];
+        }
+
+        $keys = array_keys($documents);
+        $values = array_values($documents);
+
+        // Copy every document into one C buffer and point into it
+        $joined = implode('', $values);
+        $buffer = $this->ffi->new('char[' . max(1, strlen($joined)) . ']', false);
+        FFI::memcpy($buffer, $joined, strlen($joined));
+        $base = $this->ffi->cast('char*', FFI::addr($buffer));
 
-        $this->ffi->free_string($minifiedPtr);
+        $inputs = $this->ffi->new("char*[{$count}]");
+        $inputLens = $this->ffi->new("size_t[{$count}]");
+        $offset = 0;
+        foreach ($values as $i => $document) {
+            $inputs[$i] = $base + $offset;
+            $inputLens[$i] = strlen($document);
+            $offset += strlen($document);
+        }
+
+        $outputs = $this->ffi->new("char*[{$count}]");
+        $outputLens = $this->ffi->new("size_t[{$count}]");
+        $statuses = $this->ffi->new("MinifyStatus[{$count}]");
+        $config = $options === [] ? null : $this->createConfig($options);
+
+        try {
+            $status = $this->ffi->minify_html_batch(
+                $config,
+                $inputs,
+                $inputLens,
+                $count,
+                $outputs,
+                $outputLens,
+                $statuses
+            );
+        } finally {
+            FFI::free($buffer);
+            if ($config !== null) {
+                $this->ffi->minifier_config_free($config);
+            }
+        }
+
+        if ($status !== 0) {
+            $this->lastError = $this->readLastError($status);
+        }
+
+        $minified = [];
+        foreach ($keys as $i => $key) {
+            if ($statuses[$i] !== 0) {
+                $minified[$key] = $values[$i];
+                continue;
+            }
+
+            $minified[$key] = FFI::string($outputs[$i], $outputLens[$i]);
+            $this->ffi->free_buffer($outputs[$i], $outputLens[$i]);
+        }
+
+        return $minified;
+    }
+
+    public function minifyCss(string $css): string
+    {
+        return $this->runMinifier('minify_css_bytes', $css);
+    }
+
+    /**
+     * @param array<mixed> $before Arguments preceding the input buffer.
+     * @param array<mixed> $after Arguments following the output pointers.
+     */
+    private function runMinifier(string $function, string $input, array $before = [], array $after = []): string
+    {
+        $outPtr = $this->ffi->new('char*');
+        $outLen = $this->ffi->new('size_t');
+
+        $status = $this->ffi->$function(
+            ...$before,
+            ...[$input, strlen($input), FFI::addr($outPtr), FFI::addr($outLen)],
+            ...$after
+        );
+
+        if ($status !== 0) {
+            $this->lastError = $this->readLastError($status);
+            return $input;
+        }
+
+        $this->lastError = null;
+
+        $minified = FFI::string($outPtr, $outLen->cdata);
+
+        $this->ffi->free_buffer($outPtr, $outLen->cdata);
 
         return $minified;
     }
+
+    /**
+     * @param array<string, bool|int|string|string[]> $options
+     */
+    private function createConfig(array $options): FFI\CData
+    {
+        $config = $this->ffi->minifier_config_new();
+
+        foreach ($options as $name => $value) {
+            if (is_bool($value)) {
+                $status = $this->ffi->minifier_config_set_bool($config, $name, $value);
+            } else {
+                $value = is_array($value) ? implode(' ', $value) : (string) $value;
+                $status = $this->ffi->minifier_config_set_str($config, $name, $value);
+            }
+
+            if ($status !== 0) {
+                $error = $this->readLastError($status);
+                $this->ffi->minifier_config_free($config);
+                throw new \InvalidArgumentException($error);
+            }
+        }
+
+        return $config;
+    }
+
+    /**
+     * Describes why the most recent minify call returned its input
+     * unchanged, or null if it succeeded.
+     */
+    public function getLastError(): ?string
+    {
+        return $this->lastError;
+    }
+
+    private function readLastError(int $status): string
+    {
+        $message = $this->ffi->html_minifier_last_error();
+
+        if ($message === null) {
+            return "minification failed with status {$status}";
+        }
+
+        return FFI::string($message) . " (status {$status})";
+    }
 }